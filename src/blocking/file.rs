@@ -0,0 +1,163 @@
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+
+use reqwest::blocking::Response;
+use reqwest::header::{HeaderValue, CONTENT_LENGTH, RANGE};
+use reqwest::{StatusCode, Url};
+
+use super::{check, WebDAVClient};
+use crate::error::Error;
+use crate::Method;
+
+/// A remote file read with `GET` requests, returned by
+/// [`WebDAVClient::open`].
+///
+/// A read continues the body of the last response; after a seek, the next
+/// read requests the rest of the file with a `Range` header. Seeking from
+/// the end sends a `HEAD` request for the length.
+pub struct RemoteFile {
+    client: WebDAVClient,
+    url: Url,
+    pos: u64,
+    len: Option<u64>,
+    response: Option<Response>,
+}
+
+impl RemoteFile {
+    pub(crate) fn new(client: WebDAVClient, url: Url) -> Self {
+        Self {
+            client,
+            url,
+            pos: 0,
+            len: None,
+            response: None,
+        }
+    }
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// The body from the current position, or `None` past the end.
+    fn fetch(&self) -> io::Result<Option<Response>> {
+        let mut request = self.client.get(self.url.clone());
+        if self.pos > 0 {
+            let range = HeaderValue::from_str(&format!("bytes={}-", self.pos)).unwrap();
+            request = request.header(RANGE, range);
+        }
+        let mut response = request.send().map_err(io::Error::other)?;
+        match response.status() {
+            StatusCode::PARTIAL_CONTENT => (),
+            StatusCode::RANGE_NOT_SATISFIABLE => return Ok(None),
+            // The server ignored the range and sent the whole file.
+            status if status.is_success() => {
+                io::copy(&mut (&mut response).take(self.pos), &mut io::sink())?;
+            }
+            _ => {
+                let err = Error::from_blocking_response(Method::GET.convert(), response);
+                return Err(io::Error::other(err));
+            }
+        }
+        Ok(Some(response))
+    }
+
+    fn remote_len(&mut self) -> io::Result<u64> {
+        if let Some(len) = self.len {
+            return Ok(len);
+        }
+        let response = self
+            .client
+            .request(Method::HEAD, self.url.clone())
+            .send()
+            .and_then(|response| check(Method::HEAD, response))
+            .map_err(io::Error::other)?;
+        let len = response
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|len| len.to_str().ok()?.parse().ok())
+            .ok_or_else(|| io::Error::other("the response has no Content-Length"))?;
+        self.len = Some(len);
+        Ok(len)
+    }
+}
+
+impl Read for RemoteFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.response.is_none() {
+            match self.fetch()? {
+                Some(response) => self.response = Some(response),
+                None => return Ok(0),
+            }
+        }
+        let read = self.response.as_mut().unwrap().read(buf)?;
+        self.pos += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for RemoteFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+            SeekFrom::End(offset) => self.remote_len()?.checked_add_signed(offset),
+        }
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid seek position"))?;
+        if pos != self.pos {
+            self.response = None;
+            self.pos = pos;
+        }
+        Ok(pos)
+    }
+}
+
+/// A remote file written in memory and uploaded with a `PUT` request on
+/// [`Write::flush`], returned by [`WebDAVClient::create`].
+///
+/// Nothing is uploaded on drop; writes after the last flush are lost.
+pub struct RemoteFileWriter {
+    client: WebDAVClient,
+    url: Url,
+    buf: Cursor<Vec<u8>>,
+    dirty: bool,
+}
+
+impl RemoteFileWriter {
+    pub(crate) fn new(client: WebDAVClient, url: Url) -> Self {
+        Self {
+            client,
+            url,
+            buf: Cursor::default(),
+            dirty: false,
+        }
+    }
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+}
+
+impl Write for RemoteFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.dirty = true;
+        self.buf.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        self.client
+            .put(self.url.clone())
+            .body(self.buf.get_ref().clone())
+            .send()
+            .and_then(|response| check(Method::PUT, response))
+            .map_err(|err| self.client.with_quota(err))
+            .map_err(io::Error::other)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+impl Seek for RemoteFileWriter {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.buf.seek(pos)
+    }
+}
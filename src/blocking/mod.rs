@@ -0,0 +1,551 @@
+//! A blocking WebDAV client, built on [`reqwest::blocking`].
+//!
+//! It mirrors [`crate::WebDAVClient`] and shares its configuration, so
+//! retries, redirects and authentication behave the same. Like
+//! [`reqwest::blocking`], it must not be used from within an async runtime.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use std::io::Read;
+//! use webdav_request::blocking::WebDAVClient;
+//!
+//! let client = WebDAVClient::new("name", "password").unwrap();
+//! let collection = client.list("https://webdav.example.com/dir/").unwrap();
+//! let mut file = client.open("https://webdav.example.com/dir/file.txt").unwrap();
+//! let mut text = String::new();
+//! file.read_to_string(&mut text).unwrap();
+//! ```
+mod file;
+mod reader;
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use reqwest::blocking::{Body, RequestBuilder, Response};
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, IF_MATCH, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+    IF_UNMODIFIED_SINCE, RANGE,
+};
+use reqwest::{IntoUrl, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::client::lock::LOCK_TOKEN;
+use crate::client::{
+    conditional, dispatch::Dispatch, dispatch::Step, lock, sync_collection, Auth, InnerClient,
+    Lock, Report, SyncChanges, WebDAVClientBuilder, ALL_DROP,
+};
+use crate::depth::{Depth, DEPTH};
+use crate::error::Error;
+use crate::if_header::{IfHeader, IF};
+use crate::res::acl::{self, AccessControl, Ace};
+use crate::res::principal::{self, Principal, PrincipalMultiStatus, PrincipalSearch};
+use crate::res::quota::{self, QuotaInfo};
+use crate::res::{Collection, DResponse, MultiStatus, Resource};
+use crate::search::SearchQuery;
+use crate::Method;
+pub use file::{RemoteFile, RemoteFileWriter};
+pub use reader::LazyResponseReader;
+
+const TIMEOUT: HeaderName = HeaderName::from_static("timeout");
+
+/// The blocking counterpart of [`crate::WebDAVClient`].
+#[derive(Clone)]
+pub struct WebDAVClient {
+    pub(crate) inner: Arc<InnerClient<reqwest::blocking::Client>>,
+}
+
+impl WebDAVClient {
+    pub fn new(username: &str, password: &str) -> Result<Self, reqwest::Error> {
+        Self::builder()
+            .basic_auth(username, password)
+            .build_blocking()
+    }
+    /// A client that sends no credentials, e.g. for public read-only shares.
+    pub fn anonymous() -> Result<Self, reqwest::Error> {
+        Self::builder().auth(Auth::Anonymous).build_blocking()
+    }
+    /// The builder shared with the async client; finish it with
+    /// [`WebDAVClientBuilder::build_blocking`].
+    pub fn builder() -> WebDAVClientBuilder {
+        WebDAVClientBuilder::new()
+    }
+    /// # Panics
+    ///
+    /// Panics if `url` is invalid.
+    pub fn request(&self, method: Method, url: impl IntoUrl) -> WevDAVRequestBuilder {
+        let url = url.into_url().unwrap_or_else(|e| panic!("{e}"));
+        WevDAVRequestBuilder::new(self.inner.clone(), url, method)
+    }
+    pub fn get(&self, url: impl IntoUrl) -> WevDAVRequestBuilder {
+        self.request(Method::GET, url)
+    }
+    pub fn put(&self, url: impl IntoUrl) -> WevDAVRequestBuilder {
+        self.request(Method::PUT, url)
+    }
+
+    /// Uploads `body` only if nothing exists at `url` yet.
+    ///
+    /// Fails with [`Error::PreconditionFailed`] otherwise.
+    pub fn put_if_absent(
+        &self,
+        url: impl IntoUrl,
+        body: impl Into<Body>,
+    ) -> Result<Response, Error> {
+        let response = self.put(url).if_none_match_any().body(body).send()?;
+        check(Method::PUT, response).map_err(|err| self.with_quota(err))
+    }
+
+    /// Overwrites `url` only if its entity tag is still `etag`.
+    ///
+    /// Fails with [`Error::PreconditionFailed`] if the resource has changed
+    /// since.
+    pub fn put_if_unchanged(
+        &self,
+        url: impl IntoUrl,
+        etag: &str,
+        body: impl Into<Body>,
+    ) -> Result<Response, Error> {
+        let response = self.put(url).if_match(etag).body(body).send()?;
+        check(Method::PUT, response).map_err(|err| self.with_quota(err))
+    }
+
+    /// Takes an exclusive write lock on `url` for `timeout`, or without a
+    /// time limit if `None`. The server may grant a different timeout.
+    pub fn lock(&self, url: impl IntoUrl, timeout: Option<Duration>) -> Result<Lock, Error> {
+        let url = url.into_url()?;
+        let response = self
+            .request(Method::LOCK, url.clone())
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/xml"))
+            .header(TIMEOUT, lock::timeout_header(timeout))
+            .body(lock::LOCK_INFO)
+            .send()?;
+        lock_response(url, check(Method::LOCK, response)?)
+    }
+
+    /// Extends the timeout of `lock`.
+    pub fn refresh_lock(&self, lock: &Lock, timeout: Option<Duration>) -> Result<Lock, Error> {
+        let response = self
+            .request(Method::LOCK, lock.url().clone())
+            .if_header(lock)
+            .header(TIMEOUT, lock::timeout_header(timeout))
+            .send()?;
+        let mut refreshed = lock_response(lock.url().clone(), check(Method::LOCK, response)?)?;
+        refreshed.token = lock.token().to_owned();
+        Ok(refreshed)
+    }
+
+    pub fn unlock(&self, lock: &Lock) -> Result<(), Error> {
+        let response = self
+            .request(Method::UNLOCK, lock.url().clone())
+            .try_header(LOCK_TOKEN, lock::lock_token_header(lock))
+            .send()?;
+        check(Method::UNLOCK, response).map(drop)
+    }
+
+    pub fn list(&self, url: impl IntoUrl) -> Result<Collection, Error> {
+        let response = check(Method::PROPFIND, self.all_propfind(url)?)?;
+        let xml = response.text()?;
+        Ok(Collection::from(MultiStatus::parse(&xml)?))
+    }
+    /// Sends `query` with the `SEARCH` method to `url` and returns the
+    /// matching resources.
+    pub fn search(&self, url: impl IntoUrl, query: &SearchQuery) -> Result<Vec<Resource>, Error> {
+        let response = self
+            .request(Method::SEARCH, url.into_url()?)
+            .header(CONTENT_TYPE, HeaderValue::from_static("text/xml"))
+            .body(query.to_xml()?)
+            .send()?;
+        let xml = check(Method::SEARCH, response)?.text()?;
+        let multi_status = MultiStatus::parse(&xml)?;
+        Ok(multi_status
+            .response
+            .into_iter()
+            .map(Resource::from)
+            .collect())
+    }
+    /// Reads the access control properties of `url`, see
+    /// [`crate::WebDAVClient::access_control`].
+    pub fn access_control(&self, url: impl IntoUrl) -> Result<AccessControl, Error> {
+        let response = self
+            .request(Method::PROPFIND, url.into_url()?)
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/xml"))
+            .header(DEPTH, HeaderValue::from(Depth::Zero))
+            .body(acl::ACL_PROPFIND)
+            .send()?;
+        let xml = check(Method::PROPFIND, response)?.text()?;
+        Ok(AccessControl::parse(&xml)?)
+    }
+    /// Returns the quota of the collection at `url`, see
+    /// [`crate::WebDAVClient::quota`].
+    pub fn quota(&self, url: impl IntoUrl) -> Result<QuotaInfo, Error> {
+        let response = self
+            .request(Method::PROPFIND, url.into_url()?)
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/xml"))
+            .header(DEPTH, HeaderValue::from(Depth::Zero))
+            .body(quota::QUOTA_PROPFIND)
+            .send()?;
+        let xml = check(Method::PROPFIND, response)?.text()?;
+        let multi_status = MultiStatus::parse(&xml)?;
+        let prop = multi_status
+            .response
+            .into_iter()
+            .next()
+            .map(DResponse::into_prop);
+        Ok(prop.map(|prop| prop.quota()).unwrap_or_default())
+    }
+    /// Adds the quota of the target collection to an
+    /// [`Error::InsufficientStorage`].
+    pub(crate) fn with_quota(&self, err: Error) -> Error {
+        match err {
+            Error::InsufficientStorage(response, None) => {
+                let collection = quota::parent_collection(response.url());
+                let quota = self.quota(collection).ok();
+                Error::InsufficientStorage(response, quota)
+            }
+            err => err,
+        }
+    }
+    /// Replaces the ACL of `url`, see [`crate::WebDAVClient::set_acl`].
+    pub fn set_acl(&self, url: impl IntoUrl, aces: &[Ace]) -> Result<(), Error> {
+        let response = self
+            .request(Method::ACL, url.into_url()?)
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/xml"))
+            .body(acl::acl_xml(aces)?)
+            .send()?;
+        check(Method::ACL, response).map(drop)
+    }
+    /// Returns the principal of the authenticated user, see
+    /// [`crate::WebDAVClient::current_user_principal`].
+    pub fn current_user_principal(&self, url: impl IntoUrl) -> Result<Option<Principal>, Error> {
+        let url = url.into_url()?;
+        let response = self
+            .request(Method::PROPFIND, url.clone())
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/xml"))
+            .header(DEPTH, HeaderValue::from(Depth::Zero))
+            .body(principal::CURRENT_USER_PRINCIPAL)
+            .send()?;
+        let xml = check(Method::PROPFIND, response)?.text()?;
+        match PrincipalMultiStatus::parse(&xml)?.current_user_principal() {
+            Some(href) => self.principal(url.join(&href)?).map(Some),
+            None => Ok(None),
+        }
+    }
+    /// Reads the principal at `url`.
+    pub fn principal(&self, url: impl IntoUrl) -> Result<Principal, Error> {
+        let url = url.into_url()?;
+        let response = self
+            .request(Method::PROPFIND, url.clone())
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/xml"))
+            .header(DEPTH, HeaderValue::from(Depth::Zero))
+            .body(principal::PRINCIPAL_PROPFIND)
+            .send()?;
+        let xml = check(Method::PROPFIND, response)?.text()?;
+        let principals = PrincipalMultiStatus::parse(&xml)?.into_principals();
+        Ok(principals.into_iter().next().unwrap_or_else(|| Principal {
+            href: url.path().to_owned(),
+            ..Default::default()
+        }))
+    }
+    /// Finds the principals matching `search`, see
+    /// [`crate::WebDAVClient::principal_property_search`].
+    pub fn principal_property_search(
+        &self,
+        url: impl IntoUrl,
+        search: &PrincipalSearch,
+    ) -> Result<Vec<Principal>, Error> {
+        let multi_status: PrincipalMultiStatus = self.report(url, &search.report())?;
+        Ok(multi_status.into_principals())
+    }
+    /// Returns the changes to the members of the collection at `url` since
+    /// `sync_token`, see [`crate::WebDAVClient::sync_collection`]. The
+    /// result is still [`SyncChanges::truncated`] if the server stopped
+    /// advancing the token.
+    pub fn sync_collection(
+        &self,
+        url: impl IntoUrl,
+        sync_token: Option<&str>,
+    ) -> Result<SyncChanges, Error> {
+        let url = url.into_url()?;
+        let mut changes = self.sync_collection_page(url.clone(), sync_token, None)?;
+        while changes.truncated {
+            let token = changes.sync_token.clone();
+            let next = self.sync_collection_page(url.clone(), Some(&token), None)?;
+            let stalled = next.sync_token == token;
+            changes.merge(next);
+            if stalled {
+                break;
+            }
+        }
+        Ok(changes)
+    }
+    /// Requests at most `limit` changes since `sync_token`, see
+    /// [`crate::WebDAVClient::sync_collection_page`].
+    pub fn sync_collection_page(
+        &self,
+        url: impl IntoUrl,
+        sync_token: Option<&str>,
+        limit: Option<u32>,
+    ) -> Result<SyncChanges, Error> {
+        let body = sync_collection::SyncCollection::new(sync_token, limit);
+        let multi_status: MultiStatus = self.report(url, &Report::new(body))?;
+        Ok(SyncChanges::from(multi_status))
+    }
+    /// Sends the `REPORT` method with `report` as body and parses the
+    /// response, see [`crate::WebDAVClient::report`].
+    pub fn report<Req: Serialize, Resp: DeserializeOwned>(
+        &self,
+        url: impl IntoUrl,
+        report: &Report<Req>,
+    ) -> Result<Resp, Error> {
+        let response = self
+            .request(Method::REPORT, url.into_url()?)
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/xml"))
+            .header(DEPTH, HeaderValue::from(report.depth))
+            .body(report.to_xml()?)
+            .send()?;
+        let xml = check(Method::REPORT, response)?.text()?;
+        Ok(quick_xml::de::from_str(&xml)?)
+    }
+    pub fn all_propfind(&self, url: impl IntoUrl) -> Result<Response, Error> {
+        self.request(Method::PROPFIND, url.into_url()?)
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/xml"))
+            .header(DEPTH, HeaderValue::from_static("1"))
+            .body(ALL_DROP)
+            .send()
+    }
+
+    /// Opens `url` for reading. Nothing is requested until the first read.
+    pub fn open(&self, url: impl IntoUrl) -> Result<RemoteFile, Error> {
+        Ok(RemoteFile::new(self.clone(), url.into_url()?))
+    }
+    /// Opens an empty file that is uploaded to `url` on
+    /// [`std::io::Write::flush`].
+    pub fn create(&self, url: impl IntoUrl) -> Result<RemoteFileWriter, Error> {
+        Ok(RemoteFileWriter::new(self.clone(), url.into_url()?))
+    }
+}
+
+/// Turns an unsuccessful response into an error.
+fn check(method: Method, response: Response) -> Result<Response, Error> {
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        Err(Error::from_blocking_response(method.convert(), response))
+    }
+}
+
+fn lock_response(url: Url, response: Response) -> Result<Lock, Error> {
+    let headers = response.headers().clone();
+    let xml = response.text()?;
+    lock::parse_lock(url, &headers, &xml)
+}
+
+/// The result of a request sent with
+/// [`WevDAVRequestBuilder::send_conditional`].
+#[derive(Debug)]
+pub enum Conditional {
+    /// The preconditions held and the request was applied.
+    Applied(Response),
+    /// `304 Not Modified`: the cached representation is still current.
+    NotModified(Response),
+}
+
+/// The blocking counterpart of [`crate::client::WevDAVRequestBuilder`].
+pub struct WevDAVRequestBuilder {
+    client: Arc<InnerClient<reqwest::blocking::Client>>,
+    auth: Option<Auth>,
+    url: Url,
+    headers: HeaderMap,
+    body: Option<Body>,
+    method: Method,
+    /// The first header that could not be built, returned when sending.
+    error: Option<Error>,
+}
+
+impl WevDAVRequestBuilder {
+    pub fn new(
+        client: Arc<InnerClient<reqwest::blocking::Client>>,
+        url: Url,
+        method: Method,
+    ) -> Self {
+        Self {
+            client,
+            auth: None,
+            url,
+            headers: HeaderMap::new(),
+            body: None,
+            method,
+            error: None,
+        }
+    }
+    pub fn basic_auth(self, username: &str, password: &str) -> Self {
+        self.auth(Auth::basic(username, password))
+    }
+    /// Overrides the client's credentials for this request.
+    pub fn auth(self, auth: Auth) -> Self {
+        Self {
+            auth: Some(auth),
+            ..self
+        }
+    }
+    pub fn body(self, body: impl Into<Body>) -> Self {
+        Self {
+            body: Some(body.into()),
+            ..self
+        }
+    }
+    pub fn range(self, start: usize, end: usize) -> Self {
+        let range = HeaderValue::from_str(&format!("bytes={start}-{end}")).unwrap();
+        self.header(RANGE, range)
+    }
+    /// Applies the request only if the resource's entity tag is `etag`.
+    ///
+    /// An `etag` that is no valid header value fails the request with
+    /// [`Error::InvalidHeader`].
+    pub fn if_match(self, etag: &str) -> Self {
+        self.try_header(IF_MATCH, conditional::entity_tag(IF_MATCH, etag))
+    }
+    /// Applies the request only if the resource does not exist, e.g. for a
+    /// create-only upload.
+    pub fn if_none_match_any(self) -> Self {
+        self.header(IF_NONE_MATCH, HeaderValue::from_static("*"))
+    }
+    /// Applies the request only if the resource's entity tag is not `etag`.
+    pub fn if_none_match(self, etag: &str) -> Self {
+        self.try_header(IF_NONE_MATCH, conditional::entity_tag(IF_NONE_MATCH, etag))
+    }
+    /// A `time` before 1970 or after 9999 fails the request with
+    /// [`Error::InvalidHeader`].
+    pub fn if_modified_since(self, time: SystemTime) -> Self {
+        let value = conditional::http_date(IF_MODIFIED_SINCE, time);
+        self.try_header(IF_MODIFIED_SINCE, value)
+    }
+    /// A `time` before 1970 or after 9999 fails the request with
+    /// [`Error::InvalidHeader`].
+    pub fn if_unmodified_since(self, time: SystemTime) -> Self {
+        let value = conditional::http_date(IF_UNMODIFIED_SINCE, time);
+        self.try_header(IF_UNMODIFIED_SINCE, value)
+    }
+    /// Submits lock tokens or entity tags in an `If` header, e.g. a [`Lock`].
+    ///
+    /// An entity tag that is no valid header value fails the request with
+    /// [`Error::InvalidHeader`].
+    pub fn if_header(self, header: impl Into<IfHeader>) -> Self {
+        self.try_header(IF, header.into().to_header_value())
+    }
+    pub fn header(mut self, key: HeaderName, val: HeaderValue) -> Self {
+        self.headers.insert(key, val);
+        self
+    }
+    /// Sets a header, or keeps the error for [`Self::send`] to return.
+    fn try_header(self, key: HeaderName, val: Result<HeaderValue, Error>) -> Self {
+        match val {
+            Ok(val) => self.header(key, val),
+            Err(err) => Self {
+                error: self.error.or(Some(err)),
+                ..self
+            },
+        }
+    }
+    pub fn headers(mut self, headers: HeaderMap) -> Self {
+        self.headers.extend(headers);
+        self
+    }
+
+    /// The request with the client's credentials, to be sent by reqwest.
+    ///
+    /// Neither redirects nor retries are handled for it: the client's HTTP
+    /// client does not follow redirects, which
+    /// [`WevDAVRequestBuilder::send`] does instead, so a redirect response
+    /// is returned as it is.
+    pub fn build(self) -> Result<RequestBuilder, Error> {
+        let client = self.client.clone();
+        let mut dispatch = self.into_dispatch()?.preemptive();
+        let credentials = dispatch.credentials(&client)?;
+        dispatch_request(&client, &mut dispatch, credentials)
+    }
+    fn into_dispatch(self) -> Result<Dispatch<Body>, Error> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        Ok(Dispatch::new(
+            &self.client,
+            self.method,
+            self.url,
+            self.headers,
+            self.auth,
+            self.body,
+        ))
+    }
+    /// Sends a request carrying conditional headers.
+    ///
+    /// `304 Not Modified` is returned as [`Conditional::NotModified`], and
+    /// `412 Precondition Failed` as [`Error::PreconditionFailed`].
+    pub fn send_conditional(self) -> Result<Conditional, Error> {
+        let method = self.method.clone();
+        let response = self.send()?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(Conditional::NotModified(response));
+        }
+        check(method, response).map(Conditional::Applied)
+    }
+    pub fn into_lazy_reader(self) -> LazyResponseReader {
+        LazyResponseReader::new(self)
+    }
+    /// Sends the request like [`crate::client::WevDAVRequestBuilder::send`],
+    /// blocking the current thread for retry delays.
+    pub fn send(self) -> Result<Response, Error> {
+        let client = self.client.clone();
+        let mut dispatch = self.into_dispatch()?;
+        loop {
+            let credentials = dispatch.credentials(&client)?;
+            let result = dispatch_request(&client, &mut dispatch, credentials)?.send();
+            let mut step = match &result {
+                Ok(response) => {
+                    dispatch.on_response(&client, response.status(), response.headers())?
+                }
+                Err(err) => dispatch.on_error(&client, err),
+            };
+            if let (Step::Refresh, Ok(response)) = (&step, &result) {
+                let refreshed = client.refresh()?;
+                step =
+                    dispatch.on_refresh(&client, refreshed, response.status(), response.headers());
+            }
+            match step {
+                Step::Return | Step::Refresh => return result.map_err(Into::into),
+                Step::Resend => continue,
+                Step::Wait(delay) => {
+                    drop(result);
+                    std::thread::sleep(delay);
+                }
+                Step::NotReplayable => {
+                    let method = dispatch.method.convert();
+                    let err = match result {
+                        Ok(response) => Error::from_blocking_response(method, response),
+                        Err(err) => err.into(),
+                    };
+                    return Err(Error::NotReplayable(Box::new(err)));
+                }
+            }
+        }
+    }
+}
+
+/// The next try of `dispatch`.
+fn dispatch_request(
+    client: &InnerClient<reqwest::blocking::Client>,
+    dispatch: &mut Dispatch<Body>,
+    credentials: Option<Auth>,
+) -> Result<RequestBuilder, Error> {
+    let (headers, body) = dispatch.next(client, credentials)?;
+    let builder = client
+        .inner
+        .request(dispatch.method.clone().convert(), dispatch.url.clone())
+        .headers(headers);
+    Ok(match body {
+        Some(body) => builder.body(body),
+        None => builder,
+    })
+}
@@ -0,0 +1,53 @@
+use std::io::{self, Read};
+
+use reqwest::blocking::Response;
+
+use super::WevDAVRequestBuilder;
+
+/// Sends a request on the first read and reads its response body.
+///
+/// An unsuccessful response is reported as an error by the first read.
+pub struct LazyResponseReader {
+    request: Option<WevDAVRequestBuilder>,
+    response: Option<Response>,
+}
+
+impl LazyResponseReader {
+    pub fn new(request: WevDAVRequestBuilder) -> Self {
+        Self {
+            request: Some(request),
+            response: None,
+        }
+    }
+}
+
+impl From<WevDAVRequestBuilder> for LazyResponseReader {
+    fn from(value: WevDAVRequestBuilder) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<Response> for LazyResponseReader {
+    fn from(value: Response) -> Self {
+        Self {
+            request: None,
+            response: Some(value),
+        }
+    }
+}
+
+impl Read for LazyResponseReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(request) = self.request.take() {
+            let response = request.send().map_err(io::Error::other)?;
+            if !response.status().is_success() {
+                return Err(io::Error::other(response.status().to_string()));
+            }
+            self.response = Some(response);
+        }
+        match &mut self.response {
+            Some(response) => response.read(buf),
+            None => Ok(0),
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::{
+    header::{HeaderMap, HeaderValue, AUTHORIZATION},
+    Url,
+};
+
+use super::InnerClient;
+
+/// The credentials a [`super::WebDAVClient`] authenticates with.
+///
+/// `Auth` is also the [`super::CredentialProvider`] for static credentials.
+#[derive(Clone)]
+pub enum Auth {
+    /// HTTP Basic, sent with every request.
+    Basic { username: String, password: String },
+    /// HTTP Digest (RFC 7616), sent once the server has issued a challenge.
+    ///
+    /// `qop=auth` with MD5 and SHA-256 (and their `-sess` variants) is
+    /// supported. The nonce is reused for later requests until the server
+    /// marks it stale.
+    Digest { username: String, password: String },
+    /// An `Authorization: Bearer` token, e.g. an OAuth2 access token.
+    Bearer(String),
+    /// Arbitrary headers, e.g. an API key or a proxy's session header.
+    Headers(HeaderMap),
+    /// No credentials; no `Authorization` header is sent. Used for public
+    /// shares.
+    Anonymous,
+}
+
+impl Auth {
+    pub fn basic(username: &str, password: &str) -> Self {
+        Self::Basic {
+            username: username.to_owned(),
+            password: password.to_owned(),
+        }
+    }
+    pub fn digest(username: &str, password: &str) -> Self {
+        Self::Digest {
+            username: username.to_owned(),
+            password: password.to_owned(),
+        }
+    }
+    pub fn bearer(token: &str) -> Self {
+        Self::Bearer(token.to_owned())
+    }
+
+    pub(crate) fn is_digest(&self) -> bool {
+        matches!(self, Self::Digest { .. })
+    }
+
+    /// The headers that authenticate a request with `method` to `url`.
+    pub(crate) fn headers<C>(
+        &self,
+        client: &InnerClient<C>,
+        method: &reqwest::Method,
+        url: &Url,
+    ) -> HeaderMap {
+        let authorization = match self {
+            Self::Basic { username, password } => {
+                let credentials = STANDARD.encode(format!("{username}:{password}"));
+                HeaderValue::from_str(&format!("Basic {credentials}")).ok()
+            }
+            Self::Digest { username, password } => {
+                client.digest_authorization(method, url, username, password)
+            }
+            Self::Bearer(token) => HeaderValue::from_str(&format!("Bearer {token}")).ok(),
+            Self::Headers(headers) => return headers.clone(),
+            Self::Anonymous => None,
+        };
+        let mut headers = HeaderMap::new();
+        if let Some(mut authorization) = authorization {
+            authorization.set_sensitive(true);
+            headers.insert(AUTHORIZATION, authorization);
+        }
+        headers
+    }
+}
+
+impl std::fmt::Debug for Auth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .finish_non_exhaustive(),
+            Self::Digest { username, .. } => f
+                .debug_struct("Digest")
+                .field("username", username)
+                .finish_non_exhaustive(),
+            Self::Bearer(_) => f.write_str("Bearer(..)"),
+            Self::Headers(headers) => f.debug_list().entries(headers.keys()).finish(),
+            Self::Anonymous => f.write_str("Anonymous"),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::sync::Arc;
+
+use super::{
+    inner::{http_client, Credentials},
+    redirect::DEFAULT_MAX_REDIRECTS,
+    Auth, CredentialProvider, InnerClient, RetryPolicy, WebDAVClient,
+};
+
+/// Configures a [`WebDAVClient`].
+///
+/// # Usage
+///
+/// ```
+/// use webdav_request::{client::RetryPolicy, WebDAVClient};
+///
+/// let client = WebDAVClient::builder()
+///     .basic_auth("name", "password")
+///     .retry(RetryPolicy::new().max_attempts(4))
+///     .build()
+///     .unwrap();
+/// ```
+pub struct WebDAVClientBuilder {
+    auth: Option<Credentials>,
+    retry: Option<RetryPolicy>,
+    max_redirects: usize,
+    preemptive_auth: bool,
+    #[cfg(feature = "cookies")]
+    cookies: Option<super::CookieJar>,
+}
+
+impl Default for WebDAVClientBuilder {
+    fn default() -> Self {
+        Self {
+            auth: None,
+            retry: None,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            preemptive_auth: true,
+            #[cfg(feature = "cookies")]
+            cookies: None,
+        }
+    }
+}
+
+impl WebDAVClientBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn auth(self, auth: Auth) -> Self {
+        Self {
+            auth: Some(Credentials::Fixed(auth)),
+            ..self
+        }
+    }
+    /// Asks `provider` for the credentials of every request.
+    pub fn credentials(self, provider: impl CredentialProvider + 'static) -> Self {
+        Self {
+            auth: Some(Credentials::Provider(Arc::new(provider))),
+            ..self
+        }
+    }
+    pub fn basic_auth(self, username: &str, password: &str) -> Self {
+        self.auth(Auth::basic(username, password))
+    }
+    pub fn digest_auth(self, username: &str, password: &str) -> Self {
+        self.auth(Auth::digest(username, password))
+    }
+    /// Retries failed requests according to `policy`. Requests are not
+    /// retried by default.
+    pub fn retry(self, policy: RetryPolicy) -> Self {
+        Self {
+            retry: Some(policy),
+            ..self
+        }
+    }
+    /// The number of redirects followed for one request, 10 by default.
+    /// `0` returns redirect responses as they are.
+    ///
+    /// Redirects are followed by [`super::WevDAVRequestBuilder::send`]; a
+    /// request taken out with [`super::WevDAVRequestBuilder::build`] is not
+    /// redirected.
+    pub fn max_redirects(self, max_redirects: usize) -> Self {
+        Self {
+            max_redirects,
+            ..self
+        }
+    }
+    /// Whether credentials are sent before the server asks for them, `true`
+    /// by default. When disabled, a request is first sent without them and
+    /// repeated with them after a `401`, so that a session cookie can
+    /// authenticate instead.
+    pub fn preemptive_auth(self, preemptive_auth: bool) -> Self {
+        Self {
+            preemptive_auth,
+            ..self
+        }
+    }
+    /// Stores cookies in `jar` and sends them with later requests.
+    #[cfg(feature = "cookies")]
+    pub fn cookie_jar(self, jar: super::CookieJar) -> Self {
+        Self {
+            cookies: Some(jar),
+            ..self
+        }
+    }
+    pub fn build(self) -> Result<WebDAVClient, reqwest::Error> {
+        #[allow(unused_mut)]
+        let mut http = http_client();
+        #[cfg(feature = "cookies")]
+        if let Some(jar) = &self.cookies {
+            http = http.cookie_provider(jar.0.clone());
+        }
+        Ok(WebDAVClient {
+            inner: Arc::new(self.into_inner(http.build()?)),
+        })
+    }
+    /// Builds a [`crate::blocking::WebDAVClient`] instead.
+    #[cfg(feature = "blocking")]
+    pub fn build_blocking(self) -> Result<crate::blocking::WebDAVClient, reqwest::Error> {
+        #[allow(unused_mut)]
+        let mut http = super::inner::blocking_http_client();
+        #[cfg(feature = "cookies")]
+        if let Some(jar) = &self.cookies {
+            http = http.cookie_provider(jar.0.clone());
+        }
+        Ok(crate::blocking::WebDAVClient {
+            inner: Arc::new(self.into_inner(http.build()?)),
+        })
+    }
+    fn into_inner<C>(self, inner: C) -> InnerClient<C> {
+        InnerClient {
+            auth: self.auth,
+            retry: self.retry,
+            max_redirects: self.max_redirects,
+            preemptive_auth: self.preemptive_auth,
+            ..InnerClient::with_client(inner)
+        }
+    }
+}
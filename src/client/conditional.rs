@@ -0,0 +1,53 @@
+use std::time::{Duration, SystemTime};
+
+use reqwest::{
+    header::{HeaderName, HeaderValue},
+    Response,
+};
+
+use crate::error::{Error, Result};
+
+/// `10000-01-01T00:00:00Z`, after which a time has no HTTP date.
+const MAX_HTTP_DATE: Duration = Duration::from_secs(253_402_300_800);
+
+/// The outcome of a request sent with conditional headers.
+#[derive(Debug)]
+pub enum Conditional {
+    /// The request was applied; the response is successful.
+    Applied(Response),
+    /// `304 Not Modified`: the cached representation is still current.
+    NotModified(Response),
+}
+
+impl Conditional {
+    pub fn is_not_modified(&self) -> bool {
+        matches!(self, Self::NotModified(_))
+    }
+    pub fn into_response(self) -> Response {
+        match self {
+            Self::Applied(response) | Self::NotModified(response) => response,
+        }
+    }
+}
+
+/// The value of the `name` header matching `etag`.
+pub(crate) fn entity_tag(name: HeaderName, etag: &str) -> Result<HeaderValue> {
+    HeaderValue::from_str(&crate::if_header::quote_etag(etag))
+        .map_err(|_| Error::InvalidHeader(name, format!("invalid entity tag {etag:?}")))
+}
+
+/// The value of the `name` header holding `time`, which must lie between
+/// 1970 and 9999.
+pub(crate) fn http_date(name: HeaderName, time: SystemTime) -> Result<HeaderValue> {
+    let out_of_range = || {
+        Error::InvalidHeader(
+            name.clone(),
+            format!("{time:?} is out of the range of HTTP dates"),
+        )
+    };
+    let since_epoch = time.duration_since(SystemTime::UNIX_EPOCH);
+    if !since_epoch.is_ok_and(|since_epoch| since_epoch < MAX_HTTP_DATE) {
+        return Err(out_of_range());
+    }
+    HeaderValue::from_str(&httpdate::fmt_http_date(time)).map_err(|_| out_of_range())
+}
@@ -0,0 +1,55 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    path::Path,
+    sync::Arc,
+};
+
+use reqwest_cookie_store::CookieStoreMutex;
+
+/// A cookie store shared by a client and its owner, e.g. to keep the session
+/// cookie of a Nextcloud or SharePoint login across runs.
+///
+/// # Usage
+///
+/// ```no_run
+/// use webdav_request::{client::CookieJar, WebDAVClient};
+///
+/// let jar = CookieJar::load("cookies.json").unwrap_or_default();
+/// let client = WebDAVClient::builder()
+///     .basic_auth("name", "password")
+///     .cookie_jar(jar.clone())
+///     .preemptive_auth(false)
+///     .build()
+///     .unwrap();
+/// // ...
+/// jar.save("cookies.json").unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CookieJar(pub(crate) Arc<CookieStoreMutex>);
+
+impl CookieJar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the cookies written by [`CookieJar::save`], skipping expired
+    /// ones.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let store = cookie_store::serde::json::load(reader).map_err(io::Error::other)?;
+        Ok(Self(Arc::new(CookieStoreMutex::new(store))))
+    }
+
+    /// Writes all cookies to `path`, including session cookies.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let store = self.0.lock().unwrap();
+        cookie_store::serde::json::save_incl_expired_and_nonpersistent(&store, &mut writer)
+            .map_err(io::Error::other)
+    }
+
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
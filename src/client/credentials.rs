@@ -0,0 +1,248 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+use reqwest::Url;
+
+use super::Auth;
+use crate::error::{Error, Result};
+
+/// Supplies the credentials of every request.
+///
+/// The provider is asked before each request, so it may hand out rotating
+/// tokens. When the server still answers `401 Unauthorized`,
+/// [`CredentialProvider::refresh`] is called and the request is sent once
+/// more if it returns `true`.
+///
+/// The async client calls the provider on a blocking thread of the tokio
+/// runtime, so it may block, e.g. to fetch a token over the network.
+///
+/// # Usage
+///
+/// ```
+/// use std::sync::Mutex;
+/// use webdav_request::{
+///     client::{Auth, CredentialProvider},
+///     error::Result,
+///     Url, WebDAVClient,
+/// };
+///
+/// struct TokenStore {
+///     token: Mutex<String>,
+/// }
+///
+/// impl CredentialProvider for TokenStore {
+///     fn credentials(&self, _url: &Url) -> Result<Auth> {
+///         Ok(Auth::bearer(&self.token.lock().unwrap()))
+///     }
+///     fn refresh(&self) -> Result<bool> {
+///         *self.token.lock().unwrap() = "new-token".to_owned();
+///         Ok(true)
+///     }
+/// }
+///
+/// let client = WebDAVClient::builder()
+///     .credentials(TokenStore { token: Mutex::new("token".to_owned()) })
+///     .build()
+///     .unwrap();
+/// ```
+pub trait CredentialProvider: Send + Sync {
+    /// The credentials for a request to `url`.
+    fn credentials(&self, url: &Url) -> Result<Auth>;
+
+    /// Renews the credentials after they were rejected. Returns whether the
+    /// request should be sent again.
+    fn refresh(&self) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+impl CredentialProvider for Auth {
+    fn credentials(&self, _url: &Url) -> Result<Auth> {
+        Ok(self.clone())
+    }
+}
+
+/// Reads credentials from environment variables on every request.
+///
+/// A token variable (`WEBDAV_TOKEN` by default) selects Bearer auth;
+/// otherwise the username and password variables (`WEBDAV_USERNAME` and
+/// `WEBDAV_PASSWORD`) are sent with Basic auth.
+#[derive(Debug, Clone)]
+pub struct EnvCredentials {
+    username: String,
+    password: String,
+    token: String,
+}
+
+impl Default for EnvCredentials {
+    fn default() -> Self {
+        Self {
+            username: "WEBDAV_USERNAME".to_owned(),
+            password: "WEBDAV_PASSWORD".to_owned(),
+            token: "WEBDAV_TOKEN".to_owned(),
+        }
+    }
+}
+
+impl EnvCredentials {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn username_var(self, name: &str) -> Self {
+        Self {
+            username: name.to_owned(),
+            ..self
+        }
+    }
+    pub fn password_var(self, name: &str) -> Self {
+        Self {
+            password: name.to_owned(),
+            ..self
+        }
+    }
+    pub fn token_var(self, name: &str) -> Self {
+        Self {
+            token: name.to_owned(),
+            ..self
+        }
+    }
+}
+
+impl CredentialProvider for EnvCredentials {
+    fn credentials(&self, _url: &Url) -> Result<Auth> {
+        if let Ok(token) = env::var(&self.token) {
+            return Ok(Auth::Bearer(token));
+        }
+        let var = |name: &str| {
+            env::var(name).map_err(|_| Error::Credentials(format!("`{name}` is not set").into()))
+        };
+        Ok(Auth::Basic {
+            username: var(&self.username)?,
+            password: var(&self.password)?,
+        })
+    }
+}
+
+/// Credentials from a `.netrc` file, matched by the host of each request.
+#[derive(Debug, Clone, Default)]
+pub struct Netrc {
+    entries: Vec<NetrcEntry>,
+}
+
+#[derive(Debug, Clone)]
+struct NetrcEntry {
+    /// `None` for the `default` entry.
+    machine: Option<String>,
+    login: String,
+    password: String,
+}
+
+impl Netrc {
+    /// Loads the file named by `$NETRC`, or `~/.netrc` (`~/_netrc` on
+    /// Windows).
+    pub fn from_default() -> std::io::Result<Self> {
+        let path = match env::var_os("NETRC") {
+            Some(path) => PathBuf::from(path),
+            None => {
+                let home = env::var_os("HOME")
+                    .or_else(|| env::var_os("USERPROFILE"))
+                    .ok_or_else(|| std::io::Error::other("cannot find the home directory"))?;
+                let name = if cfg!(windows) { "_netrc" } else { ".netrc" };
+                PathBuf::from(home).join(name)
+            }
+        };
+        Self::from_file(path)
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?))
+    }
+
+    /// # Usage
+    ///
+    /// ```
+    /// use webdav_request::client::Netrc;
+    ///
+    /// let netrc = Netrc::parse("machine dav.example.com login alice password secret");
+    /// assert_eq!(netrc.login("dav.example.com"), Some(("alice", "secret")));
+    /// assert_eq!(netrc.login("other.example.com"), None);
+    /// ```
+    pub fn parse(content: &str) -> Self {
+        let mut entries: Vec<NetrcEntry> = Vec::new();
+        let mut tokens = tokens(content).into_iter();
+        while let Some(token) = tokens.next() {
+            match token.as_str() {
+                "machine" => entries.push(NetrcEntry {
+                    machine: tokens.next(),
+                    login: String::new(),
+                    password: String::new(),
+                }),
+                "default" => entries.push(NetrcEntry {
+                    machine: None,
+                    login: String::new(),
+                    password: String::new(),
+                }),
+                "login" | "password" | "account" => {
+                    let (Some(entry), Some(value)) = (entries.last_mut(), tokens.next()) else {
+                        continue;
+                    };
+                    match token.as_str() {
+                        "login" => entry.login = value,
+                        "password" => entry.password = value,
+                        _ => (),
+                    }
+                }
+                _ => (),
+            }
+        }
+        Self { entries }
+    }
+
+    /// The login and password for `host`, falling back to the `default`
+    /// entry.
+    pub fn login(&self, host: &str) -> Option<(&str, &str)> {
+        self.entries
+            .iter()
+            .find(|entry| entry.machine.as_deref() == Some(host))
+            .or_else(|| self.entries.iter().find(|entry| entry.machine.is_none()))
+            .map(|entry| (entry.login.as_str(), entry.password.as_str()))
+    }
+}
+
+/// Splits a netrc file into tokens, skipping `macdef` bodies, which run
+/// until the next empty line.
+fn tokens(content: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut in_macro = false;
+    for line in content.lines() {
+        if in_macro {
+            in_macro = !line.trim().is_empty();
+            continue;
+        }
+        for word in line.split_whitespace() {
+            if word.starts_with('#') {
+                break;
+            }
+            if word == "macdef" {
+                in_macro = true;
+                break;
+            }
+            tokens.push(word.to_owned());
+        }
+    }
+    tokens
+}
+
+impl CredentialProvider for Netrc {
+    fn credentials(&self, url: &Url) -> Result<Auth> {
+        let host = url.host_str().unwrap_or_default();
+        match self.login(host) {
+            Some((username, password)) => Ok(Auth::basic(username, password)),
+            None => Err(Error::Credentials(
+                format!("no .netrc entry for `{host}`").into(),
+            )),
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! HTTP Digest access authentication (RFC 7616).
+
+use md5::Md5;
+use reqwest::{
+    header::{HeaderMap, HeaderValue, WWW_AUTHENTICATE},
+    Url,
+};
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Algorithm {
+    Md5,
+    Md5Sess,
+    Sha256,
+    Sha256Sess,
+}
+
+impl Algorithm {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_uppercase().as_str() {
+            "MD5" => Self::Md5,
+            "MD5-SESS" => Self::Md5Sess,
+            "SHA-256" => Self::Sha256,
+            "SHA-256-SESS" => Self::Sha256Sess,
+            _ => return None,
+        })
+    }
+    fn name(self) -> &'static str {
+        match self {
+            Self::Md5 => "MD5",
+            Self::Md5Sess => "MD5-sess",
+            Self::Sha256 => "SHA-256",
+            Self::Sha256Sess => "SHA-256-sess",
+        }
+    }
+    fn hash(self, data: &str) -> String {
+        let digest: Vec<u8> = match self {
+            Self::Md5 | Self::Md5Sess => Md5::digest(data.as_bytes()).to_vec(),
+            Self::Sha256 | Self::Sha256Sess => Sha256::digest(data.as_bytes()).to_vec(),
+        };
+        digest.iter().map(|b| format!("{b:02x}")).collect()
+    }
+    fn is_session(self) -> bool {
+        matches!(self, Self::Md5Sess | Self::Sha256Sess)
+    }
+}
+
+/// A `Digest` challenge and the number of requests sent with its nonce.
+#[derive(Debug, Clone)]
+pub(crate) struct DigestChallenge {
+    realm: String,
+    nonce: String,
+    opaque: Option<String>,
+    algorithm: Algorithm,
+    qop_auth: bool,
+    stale: bool,
+    nonce_count: u32,
+}
+
+impl DigestChallenge {
+    /// The strongest supported `Digest` challenge of a `401` response.
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        headers
+            .get_all(WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(split_challenges)
+            .filter_map(|challenge| Self::parse(&challenge))
+            .max_by_key(|challenge| {
+                matches!(
+                    challenge.algorithm,
+                    Algorithm::Sha256 | Algorithm::Sha256Sess
+                )
+            })
+    }
+
+    fn parse(challenge: &str) -> Option<Self> {
+        let (scheme, params) = challenge.trim().split_once(' ')?;
+        if !scheme.eq_ignore_ascii_case("digest") {
+            return None;
+        }
+        let params = parse_params(params);
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.clone())
+        };
+        let qop = param("qop");
+        // Without qop only the legacy RFC 2069 scheme is left, which is
+        // still accepted. A qop list without `auth` (e.g. only `auth-int`)
+        // is not supported.
+        let qop_auth = match &qop {
+            Some(qop) => qop.split(',').any(|qop| qop.trim() == "auth"),
+            None => false,
+        };
+        if qop.is_some() && !qop_auth {
+            return None;
+        }
+        Some(Self {
+            realm: param("realm").unwrap_or_default(),
+            nonce: param("nonce")?,
+            opaque: param("opaque"),
+            algorithm: match param("algorithm") {
+                Some(algorithm) => Algorithm::parse(&algorithm)?,
+                None => Algorithm::Md5,
+            },
+            qop_auth,
+            stale: param("stale").is_some_and(|stale| stale.eq_ignore_ascii_case("true")),
+            nonce_count: 0,
+        })
+    }
+
+    pub(crate) fn realm(&self) -> &str {
+        &self.realm
+    }
+
+    pub(crate) fn nonce(&self) -> &str {
+        &self.nonce
+    }
+
+    /// Whether the server rejected an outdated nonce rather than the
+    /// credentials.
+    pub(crate) fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    /// The `Authorization` header for the next request with this nonce.
+    pub(crate) fn authorize(
+        &mut self,
+        method: &str,
+        url: &Url,
+        username: &str,
+        password: &str,
+    ) -> Option<HeaderValue> {
+        self.nonce_count += 1;
+        let uri = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_owned(),
+        };
+        let algorithm = self.algorithm;
+        let cnonce = format!("{:016x}", super::random());
+        let nc = format!("{:08x}", self.nonce_count);
+        let mut ha1 = algorithm.hash(&format!("{username}:{}:{password}", self.realm));
+        if algorithm.is_session() {
+            ha1 = algorithm.hash(&format!("{ha1}:{}:{cnonce}", self.nonce));
+        }
+        let ha2 = algorithm.hash(&format!("{method}:{uri}"));
+        let response = if self.qop_auth {
+            algorithm.hash(&format!("{ha1}:{}:{nc}:{cnonce}:auth:{ha2}", self.nonce))
+        } else {
+            algorithm.hash(&format!("{ha1}:{}:{ha2}", self.nonce))
+        };
+        let mut header = format!(
+            r#"Digest username="{}", realm="{}", nonce="{}", uri="{}", algorithm={}, response="{}""#,
+            quote(username),
+            quote(&self.realm),
+            quote(&self.nonce),
+            quote(&uri),
+            algorithm.name(),
+            response
+        );
+        if self.qop_auth {
+            header.push_str(&format!(r#", qop=auth, nc={nc}, cnonce="{cnonce}""#));
+        }
+        if let Some(opaque) = &self.opaque {
+            header.push_str(&format!(r#", opaque="{}""#, quote(opaque)));
+        }
+        HeaderValue::from_str(&header).ok()
+    }
+}
+
+fn quote(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Splits a header value into challenges, e.g. `Basic realm="a", Digest
+/// realm="b", nonce="c"` into the `Basic` and `Digest` parts.
+fn split_challenges(value: &str) -> Vec<String> {
+    let mut challenges: Vec<String> = Vec::new();
+    for part in split_list(value) {
+        let starts_challenge = match part.split_once(' ') {
+            Some((token, rest)) => !token.contains('=') && !rest.trim_start().starts_with('='),
+            None => !part.contains('='),
+        };
+        match challenges.last_mut() {
+            Some(last) if !starts_challenge => {
+                last.push_str(", ");
+                last.push_str(&part);
+            }
+            _ => challenges.push(part),
+        }
+    }
+    challenges
+}
+
+/// Splits on commas outside of quoted strings.
+fn split_list(value: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut escaped = false;
+    for c in value.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                parts.push(std::mem::take(&mut current).trim().to_owned());
+                continue;
+            }
+            _ => (),
+        }
+        current.push(c);
+    }
+    parts.push(current.trim().to_owned());
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+fn parse_params(params: &str) -> Vec<(String, String)> {
+    split_list(params)
+        .into_iter()
+        .filter_map(|param| {
+            let (key, value) = param.split_once('=')?;
+            let value = value.trim();
+            let value = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+                Some(quoted) => unquote(quoted),
+                None => value.to_owned(),
+            };
+            Some((key.trim().to_owned(), value))
+        })
+        .collect()
+}
+
+fn unquote(value: &str) -> String {
+    let mut unquoted = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unquoted.extend(chars.next()),
+            c => unquoted.push(c),
+        }
+    }
+    unquoted
+}
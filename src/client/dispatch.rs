@@ -0,0 +1,218 @@
+use std::time::Duration;
+
+use reqwest::{header::HeaderMap, StatusCode, Url};
+
+use super::{
+    payload::{Payload, RequestBody},
+    redirect, Auth, InnerClient,
+};
+use crate::Method;
+
+/// What to do after a try of a request.
+pub(crate) enum Step {
+    /// Return the response or error as it is.
+    Return,
+    /// Send the request again right away.
+    Resend,
+    /// Send the request again after a delay.
+    Wait(Duration),
+    /// The request should be sent again, but its body was a stream that
+    /// is already consumed.
+    NotReplayable,
+    /// Ask the client's provider to renew the rejected credentials, then
+    /// call [`Dispatch::on_refresh`].
+    Refresh,
+}
+
+/// The redirect, authentication and retry bookkeeping of one request,
+/// shared by the async and the blocking client. The caller sends the
+/// request and reports the outcome of every try.
+pub(crate) struct Dispatch<B> {
+    pub(crate) method: Method,
+    pub(crate) url: Url,
+    headers: HeaderMap,
+    auth: Option<Auth>,
+    payload: Payload<B>,
+    send_auth: bool,
+    lazy_auth: bool,
+    digest: bool,
+    attempt: u32,
+    redirects: usize,
+    challenges: u32,
+    refreshed: bool,
+}
+
+impl<B: RequestBody> Dispatch<B> {
+    pub(crate) fn new<C>(
+        client: &InnerClient<C>,
+        method: Method,
+        url: Url,
+        headers: HeaderMap,
+        auth: Option<Auth>,
+        body: Option<B>,
+    ) -> Self {
+        Self {
+            method,
+            url,
+            headers,
+            lazy_auth: auth.is_none() && !client.preemptive_auth,
+            auth,
+            payload: Payload::new(body),
+            send_auth: true,
+            digest: false,
+            attempt: 1,
+            redirects: 0,
+            challenges: 0,
+            refreshed: false,
+        }
+    }
+
+    /// Sends the credentials with the first try, regardless of
+    /// [`super::WebDAVClientBuilder::preemptive_auth`].
+    pub(crate) fn preemptive(self) -> Self {
+        Self {
+            lazy_auth: false,
+            ..self
+        }
+    }
+
+    /// Whether the next try sends the credentials of the client's provider,
+    /// which the caller passes to [`Self::next`].
+    pub(crate) fn needs_credentials(&self) -> bool {
+        self.auth.is_none() && !self.lazy_auth && self.send_auth
+    }
+
+    /// Asks the client's provider for the credentials of the next try on
+    /// the current thread, if it needs them.
+    pub(crate) fn credentials<C>(
+        &self,
+        client: &InnerClient<C>,
+    ) -> crate::error::Result<Option<Auth>> {
+        match self.needs_credentials() {
+            true => client.credentials(&self.url),
+            false => Ok(None),
+        }
+    }
+
+    /// The headers, including the credentials, and the body of the next try.
+    pub(crate) fn next<C>(
+        &mut self,
+        client: &InnerClient<C>,
+        credentials: Option<Auth>,
+    ) -> crate::error::Result<(HeaderMap, Option<B>)> {
+        let auth = match &self.auth {
+            Some(auth) => Some(auth.clone()),
+            None if self.lazy_auth => Some(Auth::Anonymous),
+            None => credentials,
+        };
+        let mut headers = if !self.send_auth {
+            HeaderMap::new()
+        } else if let Some(auth) = &auth {
+            auth.headers(client, &self.method.clone().convert(), &self.url)
+        } else {
+            return Err(crate::error::Error::MissingAuth);
+        };
+        self.digest = auth.as_ref().is_some_and(Auth::is_digest);
+        headers.extend(self.headers.clone());
+        Ok((headers, self.payload.body()))
+    }
+
+    /// Decides what to do with a response. Follows a redirect by updating
+    /// the URL and headers of the next try.
+    pub(crate) fn on_response<C>(
+        &mut self,
+        client: &InnerClient<C>,
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) -> crate::error::Result<Step> {
+        let location = Some(client.max_redirects)
+            .filter(|max| *max > 0)
+            .and_then(|_| redirect::location(status, headers, &self.url));
+        if let Some(location) = location {
+            if self.redirects >= client.max_redirects {
+                return Err(crate::error::Error::TooManyRedirects(location));
+            }
+            if !self.payload.is_replayable() {
+                return Ok(Step::NotReplayable);
+            }
+            self.redirects += 1;
+            self.send_auth &= redirect::follow(&mut self.headers, &self.url, &location);
+            self.url = location;
+            return Ok(Step::Resend);
+        }
+        let unauthorized = status == StatusCode::UNAUTHORIZED && self.send_auth;
+        if unauthorized && self.lazy_auth {
+            self.lazy_auth = false;
+            return Ok(self.resend());
+        }
+        if unauthorized
+            && self.digest
+            && self.challenges < 2
+            && client.accept_digest_challenge(&self.url, headers)
+        {
+            self.challenges += 1;
+            return Ok(self.resend());
+        }
+        if let (true, None, Some(_), false) =
+            (unauthorized, &self.auth, &client.auth, self.refreshed)
+        {
+            self.refreshed = true;
+            return Ok(Step::Refresh);
+        }
+        Ok(self.on_refresh(client, false, status, headers))
+    }
+
+    /// Decides what to do with a response after [`Step::Refresh`], given
+    /// whether the provider renewed the credentials.
+    pub(crate) fn on_refresh<C>(
+        &mut self,
+        client: &InnerClient<C>,
+        refreshed: bool,
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) -> Step {
+        if refreshed {
+            return self.resend();
+        }
+        let delay = self
+            .retry_policy(client)
+            .and_then(|retry| retry.response_delay(self.attempt, status, headers));
+        self.retry(delay)
+    }
+
+    /// Decides what to do after a transport error.
+    pub(crate) fn on_error<C>(&mut self, client: &InnerClient<C>, err: &reqwest::Error) -> Step {
+        let delay = self
+            .retry_policy(client)
+            .and_then(|retry| retry.error_delay(self.attempt, err));
+        self.retry(delay)
+    }
+
+    fn retry_policy<'a, C>(&self, client: &'a InnerClient<C>) -> Option<&'a super::RetryPolicy> {
+        client
+            .retry
+            .as_ref()
+            .filter(|retry| retry.applies_to(&self.method))
+    }
+
+    /// Sends the request again with new credentials, if its body allows.
+    /// A consumed stream is reported, the credentials being kept for the
+    /// caller's next request.
+    fn resend(&self) -> Step {
+        match self.payload.is_replayable() {
+            true => Step::Resend,
+            false => Step::NotReplayable,
+        }
+    }
+
+    fn retry(&mut self, delay: Option<Duration>) -> Step {
+        match delay {
+            None => Step::Return,
+            Some(_) if !self.payload.is_replayable() => Step::NotReplayable,
+            Some(delay) => {
+                self.attempt += 1;
+                Step::Wait(delay)
+            }
+        }
+    }
+}
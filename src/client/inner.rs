@@ -1,20 +1,168 @@
-type Username = String;
-type Password = String;
-#[derive(Clone, Default)]
-pub struct InnerClient {
-    pub(crate) auth: Option<(Username, Password)>,
-    pub(crate) inner: reqwest::Client,
+use std::sync::{Arc, Mutex};
+
+use reqwest::{
+    header::{HeaderMap, HeaderValue},
+    Url,
+};
+use url::Origin;
+
+use super::{
+    digest::DigestChallenge, redirect::DEFAULT_MAX_REDIRECTS, Auth, CredentialProvider, RetryPolicy,
+};
+
+/// The state shared by the requests of a client: its configuration, the
+/// `Digest` challenges of the servers, and the HTTP client `C` that sends
+/// them.
+#[derive(Clone)]
+pub struct InnerClient<C = reqwest::Client> {
+    pub(crate) auth: Option<Credentials>,
+    pub(crate) retry: Option<RetryPolicy>,
+    pub(crate) max_redirects: usize,
+    pub(crate) preemptive_auth: bool,
+    /// The `Digest` challenges by origin and realm, the latest last.
+    pub(crate) digest: Arc<Mutex<Vec<(Origin, DigestChallenge)>>>,
+    pub(crate) inner: C,
+}
+
+impl Default for InnerClient {
+    fn default() -> Self {
+        Self::with_client(
+            http_client()
+                .build()
+                .expect("failed to build the http client"),
+        )
+    }
 }
 
 impl InnerClient {
     pub fn new(username: &str, password: &str) -> Result<Self, reqwest::Error> {
         Ok(Self {
-            auth: Some((username.to_owned(), password.to_owned())),
-            inner: reqwest::Client::builder().build()?,
+            auth: Some(Credentials::Fixed(Auth::basic(username, password))),
+            inner: http_client().build()?,
+            ..Default::default()
         })
     }
 }
 
-unsafe impl Send for InnerClient {}
+/// Where the credentials of a client come from.
+#[derive(Clone)]
+pub(crate) enum Credentials {
+    Fixed(Auth),
+    Provider(Arc<dyn CredentialProvider>),
+}
+
+impl<C> InnerClient<C> {
+    /// A client sending requests with `inner`.
+    pub(crate) fn with_client(inner: C) -> Self {
+        Self {
+            auth: None,
+            retry: None,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            preemptive_auth: true,
+            digest: Default::default(),
+            inner,
+        }
+    }
+
+    /// The credentials of the client's provider for a request to `url`.
+    pub(crate) fn credentials(&self, url: &Url) -> crate::error::Result<Option<Auth>> {
+        match &self.auth {
+            Some(Credentials::Fixed(auth)) => Ok(Some(auth.clone())),
+            Some(Credentials::Provider(provider)) => provider.credentials(url).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Asks the provider to renew rejected credentials. Returns whether the
+    /// request should be sent again.
+    pub(crate) fn refresh(&self) -> crate::error::Result<bool> {
+        match &self.auth {
+            Some(Credentials::Provider(provider)) => provider.refresh(),
+            _ => Ok(false),
+        }
+    }
+
+    /// The `Authorization` header for a request, if the origin of `url` has
+    /// already issued a `Digest` challenge. The latest realm of the origin is
+    /// used.
+    pub(crate) fn digest_authorization(
+        &self,
+        method: &reqwest::Method,
+        url: &Url,
+        username: &str,
+        password: &str,
+    ) -> Option<HeaderValue> {
+        let origin = url.origin();
+        let mut digest = self.digest.lock().unwrap();
+        let (_, challenge) = digest.iter_mut().rev().find(|(o, _)| *o == origin)?;
+        challenge.authorize(method.as_str(), url, username, password)
+    }
+
+    /// Stores the `Digest` challenge of a `401` response to a request to
+    /// `url`. Returns whether the request is worth sending again, i.e. the
+    /// challenge carries a new or stale nonce rather than rejecting the
+    /// credentials.
+    pub(crate) fn accept_digest_challenge(&self, url: &Url, headers: &HeaderMap) -> bool {
+        let Some(challenge) = DigestChallenge::from_headers(headers) else {
+            return false;
+        };
+        let origin = url.origin();
+        let mut digest = self.digest.lock().unwrap();
+        let current = digest
+            .iter()
+            .position(|(o, current)| *o == origin && current.realm() == challenge.realm())
+            .map(|index| digest.remove(index).1);
+        let retry = challenge.is_stale()
+            || current.is_none_or(|current| current.nonce() != challenge.nonce());
+        digest.push((origin, challenge));
+        retry
+    }
+}
+
+impl InnerClient {
+    /// Like [`InnerClient::credentials`], but asks a provider on a blocking
+    /// thread, as it may block.
+    pub(crate) async fn credentials_async(&self, url: &Url) -> crate::error::Result<Option<Auth>> {
+        match &self.auth {
+            Some(Credentials::Provider(provider)) => {
+                let (provider, url) = (provider.clone(), url.clone());
+                unblock(move || provider.credentials(&url)).await.map(Some)
+            }
+            _ => self.credentials(url),
+        }
+    }
 
-unsafe impl Sync for InnerClient {}
+    /// Like [`InnerClient::refresh`], but on a blocking thread.
+    pub(crate) async fn refresh_async(&self) -> crate::error::Result<bool> {
+        match &self.auth {
+            Some(Credentials::Provider(provider)) => {
+                let provider = provider.clone();
+                unblock(move || provider.refresh()).await
+            }
+            _ => self.refresh(),
+        }
+    }
+}
+
+/// Runs a call of a [`CredentialProvider`] on the blocking thread pool of
+/// the runtime.
+async fn unblock<T: Send + 'static>(
+    f: impl FnOnce() -> crate::error::Result<T> + Send + 'static,
+) -> crate::error::Result<T> {
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => result,
+        Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+        Err(err) => Err(crate::error::Error::Credentials(Box::new(err))),
+    }
+}
+
+/// Redirects are followed by [`super::WevDAVRequestBuilder::send`], which
+/// keeps the method and body that reqwest would drop.
+pub(crate) fn http_client() -> reqwest::ClientBuilder {
+    reqwest::Client::builder().redirect(reqwest::redirect::Policy::none())
+}
+
+#[cfg(feature = "blocking")]
+pub(crate) fn blocking_http_client() -> reqwest::blocking::ClientBuilder {
+    reqwest::blocking::Client::builder().redirect(reqwest::redirect::Policy::none())
+}
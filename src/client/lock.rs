@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Url,
+};
+use serde::Deserialize;
+
+use crate::error::Error;
+use crate::if_header::{IfHeader, IfList};
+
+pub(crate) const LOCK_TOKEN: HeaderName = HeaderName::from_static("lock-token");
+
+pub(crate) const LOCK_INFO: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+    <D:lockinfo xmlns:D="DAV:">
+        <D:lockscope><D:exclusive/></D:lockscope>
+        <D:locktype><D:write/></D:locktype>
+    </D:lockinfo>
+"#;
+
+/// An exclusive write lock, returned by [`super::WebDAVClient::lock`].
+///
+/// Requests that modify the locked resource must submit the lock token,
+/// e.g. with `.if_header(&lock)`.
+#[derive(Debug, Clone)]
+pub struct Lock {
+    pub(crate) url: Url,
+    pub(crate) token: String,
+    pub(crate) timeout: Option<Duration>,
+}
+
+impl Lock {
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+    /// The lock token, e.g. `urn:uuid:...`.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+    /// The time the server keeps the lock for; `None` if infinite.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+    /// An `If` header submitting the lock token for the locked resource.
+    pub fn if_header(&self) -> IfHeader {
+        let list = IfList::new().token(&self.token);
+        IfHeader::Tagged(vec![(self.url.to_string(), vec![list])])
+    }
+}
+
+impl From<&Lock> for IfHeader {
+    fn from(value: &Lock) -> Self {
+        value.if_header()
+    }
+}
+
+/// The `Lock-Token` header of `UNLOCK`. The token may have been read from
+/// the body of the `LOCK` response, so it can be an invalid header value.
+pub(crate) fn lock_token_header(lock: &Lock) -> Result<HeaderValue, Error> {
+    HeaderValue::from_str(&format!("<{}>", lock.token)).map_err(|_| {
+        Error::InvalidHeader(LOCK_TOKEN, format!("invalid lock token {:?}", lock.token))
+    })
+}
+
+pub(crate) fn timeout_header(timeout: Option<Duration>) -> HeaderValue {
+    match timeout {
+        Some(timeout) => HeaderValue::from_str(&format!("Second-{}", timeout.as_secs())).unwrap(),
+        None => HeaderValue::from_static("Infinite"),
+    }
+}
+
+/// Parses `Second-n` or `Infinite`.
+fn parse_timeout(timeout: &str) -> Option<Duration> {
+    let seconds = timeout.trim().strip_prefix("Second-")?;
+    seconds.parse().ok().map(Duration::from_secs)
+}
+
+/// The `DAV:prop` body of a `LOCK` response.
+#[derive(Debug, Deserialize, Default)]
+struct LockProp {
+    #[serde(default)]
+    lockdiscovery: LockDiscovery,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct LockDiscovery {
+    #[serde(default)]
+    activelock: Vec<ActiveLock>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ActiveLock {
+    #[serde(default)]
+    timeout: Option<String>,
+    #[serde(default)]
+    locktoken: Option<LockToken>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LockToken {
+    href: String,
+}
+
+impl LockProp {
+    /// The lock with `token`, or the only active lock if `token` is unknown.
+    fn into_lock(self, url: Url, token: Option<String>) -> Option<Lock> {
+        let active = self.lockdiscovery.activelock.into_iter().find(|lock| {
+            token.is_none() || lock.locktoken.as_ref().map(|t| t.href.trim()) == token.as_deref()
+        });
+        let token = token.or_else(|| {
+            let active = active.as_ref()?;
+            Some(active.locktoken.as_ref()?.href.trim().to_owned())
+        })?;
+        Some(Lock {
+            url,
+            token,
+            timeout: active
+                .and_then(|lock| lock.timeout)
+                .and_then(|timeout| parse_timeout(&timeout)),
+        })
+    }
+}
+
+/// The lock granted by a `LOCK` response with `headers` and body `xml`.
+pub(crate) fn parse_lock(
+    url: Url,
+    headers: &HeaderMap,
+    xml: &str,
+) -> Result<Lock, crate::error::Error> {
+    let token = headers
+        .get("lock-token")
+        .and_then(|token| token.to_str().ok())
+        .map(|token| {
+            token
+                .trim()
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .to_owned()
+        });
+    // The body only adds the timeout, so a malformed one is not an error.
+    let prop: LockProp = quick_xml::de::from_str(xml).unwrap_or_default();
+    prop.into_lock(url, token)
+        .ok_or_else(|| std::io::Error::other("the LOCK response has no lock token").into())
+}
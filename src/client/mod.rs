@@ -1,17 +1,60 @@
+mod auth;
+mod builder;
+pub(crate) mod conditional;
+#[cfg(feature = "cookies")]
+mod cookies;
+mod credentials;
+mod digest;
+pub(crate) mod dispatch;
 mod inner;
-use std::future::Future;
+pub(crate) mod lock;
+mod payload;
+mod redirect;
+mod report;
+mod retry;
+pub(crate) mod sync_collection;
+#[cfg(feature = "filter")]
+pub(crate) mod walk;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
+use crate::depth::{Depth, DEPTH};
+use crate::if_header::{IfHeader, IF};
 use crate::method::Method;
 use crate::reader::LazyResponseReader;
+use crate::res::acl::{self, AccessControl, Ace};
+use crate::res::principal::{self, Principal, PrincipalMultiStatus, PrincipalSearch};
+use crate::res::quota::{self, QuotaInfo};
 use crate::res::Collection;
+use crate::res::DResponse;
+#[cfg(doc)]
+use crate::res::DavCondition;
 use crate::res::MultiStatus;
+use crate::res::Resource;
+use crate::search::SearchQuery;
 use crate::{header::HeaderMap, Body};
+pub use auth::Auth;
+pub use builder::WebDAVClientBuilder;
+pub use conditional::Conditional;
+#[cfg(feature = "cookies")]
+pub use cookies::CookieJar;
+pub use credentials::{CredentialProvider, EnvCredentials, Netrc};
+use dispatch::{Dispatch, Step};
 pub use inner::InnerClient;
-use reqwest::header::{HeaderName, HeaderValue, CONTENT_TYPE};
+pub use lock::Lock;
+pub use report::Report;
+use reqwest::header::{
+    HeaderName, HeaderValue, CONTENT_TYPE, IF_MATCH, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+    IF_UNMODIFIED_SINCE,
+};
 use reqwest::IntoUrl;
 use reqwest::Response;
+use reqwest::StatusCode;
 use reqwest::Url;
+pub use retry::RetryPolicy;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+pub use sync_collection::SyncChanges;
 
 macro_rules! header_value {
     ($arg:expr) => {
@@ -24,7 +67,7 @@ macro_rules! header_name {
     };
 }
 
-const ALL_DROP: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+pub(crate) const ALL_DROP: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
     <D:propfind xmlns:D="DAV:">
         <D:allprop/>
     </D:propfind>
@@ -37,6 +80,14 @@ unsafe impl Send for WebDAVClient {}
 
 unsafe impl Sync for WebDAVClient {}
 
+/// A random number for backoff jitter and client nonces.
+pub(crate) fn random() -> u64 {
+    use std::hash::{BuildHasher, Hasher};
+    std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish()
+}
+
 macro_rules! into_url {
     ($url:expr) => {
         match $url.into_url() {
@@ -52,6 +103,13 @@ impl WebDAVClient {
             inner: Arc::new(InnerClient::new(username, password)?),
         })
     }
+    /// A client that sends no credentials, e.g. for public read-only shares.
+    pub fn anonymous() -> Result<Self, reqwest::Error> {
+        Self::builder().auth(Auth::Anonymous).build()
+    }
+    pub fn builder() -> WebDAVClientBuilder {
+        WebDAVClientBuilder::new()
+    }
     pub fn request(&self, method: Method, url: impl IntoUrl) -> WevDAVRequestBuilder {
         WevDAVRequestBuilder::new(self.inner.clone(), into_url!(url), method)
     }
@@ -65,6 +123,134 @@ impl WebDAVClient {
         self.request(Method::PUT, url)
     }
 
+    pub fn delete(&self, url: impl IntoUrl) -> WevDAVRequestBuilder {
+        self.request(Method::DELETE, url)
+    }
+
+    /// Creates the collection at `url`. Its parent must exist.
+    pub async fn mkcol(&self, url: impl IntoUrl) -> Result<(), crate::error::Error> {
+        let response = self.request(Method::MKCOL, url).send().await?;
+        match check(Method::MKCOL, response).await {
+            Ok(_) => Ok(()),
+            Err(err) => Err(self.with_quota(err).await),
+        }
+    }
+
+    /// Copies the resource at `from` to `to`, with all its members,
+    /// replacing an existing one only if `overwrite` is set.
+    pub async fn copy_to(
+        &self,
+        from: impl IntoUrl,
+        to: impl IntoUrl,
+        overwrite: bool,
+    ) -> Result<(), crate::error::Error> {
+        let to = to.into_url()?;
+        let overwrite = if overwrite { "T" } else { "F" };
+        let response = self
+            .request(Method::COPY, from)
+            .header(header_name!("destination"), header_value!(to.as_str()))
+            .header(header_name!("overwrite"), header_value!(overwrite))
+            .send()
+            .await?;
+        check(Method::COPY, response).await.map(drop)
+    }
+
+    /// Moves the resource at `from` to `to`, replacing an existing one only
+    /// if `overwrite` is set.
+    pub async fn move_to(
+        &self,
+        from: impl IntoUrl,
+        to: impl IntoUrl,
+        overwrite: bool,
+    ) -> Result<(), crate::error::Error> {
+        let to = to.into_url()?;
+        let overwrite = if overwrite { "T" } else { "F" };
+        let response = self
+            .request(Method::MOVE, from)
+            .header(header_name!("destination"), header_value!(to.as_str()))
+            .header(header_name!("overwrite"), header_value!(overwrite))
+            .send()
+            .await?;
+        check(Method::MOVE, response).await.map(drop)
+    }
+
+    /// Uploads `body` only if nothing exists at `url` yet.
+    ///
+    /// Fails with [`crate::error::Error::PreconditionFailed`] otherwise.
+    pub async fn put_if_absent(
+        &self,
+        url: impl IntoUrl,
+        body: impl Into<Body>,
+    ) -> Result<Response, crate::error::Error> {
+        let response = self.put(url).if_none_match_any().body(body).send().await?;
+        match check(Method::PUT, response).await {
+            Err(err) => Err(self.with_quota(err).await),
+            response => response,
+        }
+    }
+
+    /// Overwrites `url` only if its entity tag is still `etag`.
+    ///
+    /// Fails with [`crate::error::Error::PreconditionFailed`] if the
+    /// resource has changed since.
+    pub async fn put_if_unchanged(
+        &self,
+        url: impl IntoUrl,
+        etag: &str,
+        body: impl Into<Body>,
+    ) -> Result<Response, crate::error::Error> {
+        let response = self.put(url).if_match(etag).body(body).send().await?;
+        match check(Method::PUT, response).await {
+            Err(err) => Err(self.with_quota(err).await),
+            response => response,
+        }
+    }
+
+    /// Takes an exclusive write lock on `url` for `timeout`, or without a
+    /// time limit if `None`. The server may grant a different timeout.
+    pub async fn lock(
+        &self,
+        url: impl IntoUrl,
+        timeout: Option<Duration>,
+    ) -> Result<Lock, crate::error::Error> {
+        let url = url.into_url()?;
+        let response = self
+            .request(Method::LOCK, url.clone())
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/xml"))
+            .header(header_name!("timeout"), lock::timeout_header(timeout))
+            .body(lock::LOCK_INFO)
+            .send()
+            .await?;
+        lock_response(url, check(Method::LOCK, response).await?).await
+    }
+
+    /// Extends the timeout of `lock`.
+    pub async fn refresh_lock(
+        &self,
+        lock: &Lock,
+        timeout: Option<Duration>,
+    ) -> Result<Lock, crate::error::Error> {
+        let response = self
+            .request(Method::LOCK, lock.url.clone())
+            .if_header(lock)
+            .header(header_name!("timeout"), lock::timeout_header(timeout))
+            .send()
+            .await?;
+        let response = check(Method::LOCK, response).await?;
+        let mut refreshed = lock_response(lock.url.clone(), response).await?;
+        refreshed.token = lock.token.clone();
+        Ok(refreshed)
+    }
+
+    pub async fn unlock(&self, lock: &Lock) -> Result<(), crate::error::Error> {
+        let response = self
+            .request(Method::UNLOCK, lock.url.clone())
+            .try_header(lock::LOCK_TOKEN, lock::lock_token_header(lock))
+            .send()
+            .await?;
+        check(Method::UNLOCK, response).await.map(drop)
+    }
+
     pub async fn list(&self, url: impl IntoUrl) -> Result<Collection, crate::error::Error> {
         let response = self.all_propfind(url).await?;
         if response.status().is_success() {
@@ -72,9 +258,265 @@ impl WebDAVClient {
             let multi_status = MultiStatus::parse(&xml)?;
             Ok(Collection::from(multi_status))
         } else {
-            Err(crate::error::Error::ResponseError(response.status()))
+            Err(crate::error::Error::from_response(Method::PROPFIND.convert(), response).await)
+        }
+    }
+    /// Sends `query` with the `SEARCH` method to `url`, e.g. the DAV root
+    /// `https://cloud.example.com/remote.php/dav/` on Nextcloud, and returns
+    /// the matching resources in the order the server sorted them.
+    pub async fn search(
+        &self,
+        url: impl IntoUrl,
+        query: &SearchQuery,
+    ) -> Result<Vec<Resource>, crate::error::Error> {
+        let response = self
+            .request(Method::SEARCH, url.into_url()?)
+            .header(CONTENT_TYPE, HeaderValue::from_static("text/xml"))
+            .body(query.to_xml()?)
+            .send()
+            .await?;
+        let xml = check(Method::SEARCH, response).await?.text().await?;
+        let multi_status = MultiStatus::parse(&xml)?;
+        Ok(multi_status
+            .response
+            .into_iter()
+            .map(Resource::from)
+            .collect())
+    }
+    /// Reads the owner, group, ACL, supported privileges and principal
+    /// collections of `url`.
+    pub async fn access_control(
+        &self,
+        url: impl IntoUrl,
+    ) -> Result<AccessControl, crate::error::Error> {
+        let response = self
+            .request(Method::PROPFIND, url.into_url()?)
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/xml"))
+            .header(DEPTH, HeaderValue::from(Depth::Zero))
+            .body(acl::ACL_PROPFIND)
+            .send()
+            .await?;
+        let xml = check(Method::PROPFIND, response).await?.text().await?;
+        Ok(AccessControl::parse(&xml)?)
+    }
+    /// Replaces the ACL of `url` with the `ACL` method.
+    ///
+    /// Inherited entries are left out. Protected entries must be passed on
+    /// unchanged, e.g. from [`AccessControl::acl`]; otherwise the server
+    /// fails with [`DavCondition::NoProtectedAceConflict`].
+    ///
+    /// # Usage
+    ///
+    /// ```no_run
+    /// use webdav_request::res::acl::{Ace, AcePrincipal};
+    /// use webdav_request::res::privilege::PrivilegeName;
+    ///
+    /// # async fn run() -> Result<(), webdav_request::error::Error> {
+    /// let client = webdav_request::WebDAVClient::new("user", "password")?;
+    /// let url = "https://dav.example.com/docs/";
+    /// let mut acl = client.access_control(url).await?.acl;
+    /// acl.push(Ace::grant(
+    ///     AcePrincipal::Href("/principals/bob".to_owned()),
+    ///     [PrivilegeName::dav("read")],
+    /// ));
+    /// acl.push(Ace::deny(AcePrincipal::Unauthenticated, [PrivilegeName::dav("all")]));
+    /// client.set_acl(url, &acl).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn set_acl(
+        &self,
+        url: impl IntoUrl,
+        aces: &[Ace],
+    ) -> Result<(), crate::error::Error> {
+        let response = self
+            .request(Method::ACL, url.into_url()?)
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/xml"))
+            .body(acl::acl_xml(aces)?)
+            .send()
+            .await?;
+        check(Method::ACL, response).await.map(drop)
+    }
+    /// Returns the quota of the collection at `url` (RFC 4331).
+    ///
+    /// # Usage
+    ///
+    /// ```no_run
+    /// # async fn run() -> Result<(), webdav_request::error::Error> {
+    /// let client = webdav_request::WebDAVClient::new("user", "password")?;
+    /// let url = "https://cloud.example.com/remote.php/dav/files/user/";
+    /// let quota = client.quota(url).await?;
+    /// if !quota.fits(10 << 20) {
+    ///     println!("{:?} bytes available", quota.available);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn quota(&self, url: impl IntoUrl) -> Result<QuotaInfo, crate::error::Error> {
+        let response = self
+            .request(Method::PROPFIND, url.into_url()?)
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/xml"))
+            .header(DEPTH, HeaderValue::from(Depth::Zero))
+            .body(quota::QUOTA_PROPFIND)
+            .send()
+            .await?;
+        let xml = check(Method::PROPFIND, response).await?.text().await?;
+        let multi_status = MultiStatus::parse(&xml)?;
+        let prop = multi_status
+            .response
+            .into_iter()
+            .next()
+            .map(DResponse::into_prop);
+        Ok(prop.map(|prop| prop.quota()).unwrap_or_default())
+    }
+    /// Adds the quota of the target collection to an
+    /// [`crate::error::Error::InsufficientStorage`].
+    pub(crate) async fn with_quota(&self, err: crate::error::Error) -> crate::error::Error {
+        match err {
+            crate::error::Error::InsufficientStorage(response, None) => {
+                let collection = quota::parent_collection(response.url());
+                let quota = self.quota(collection).await.ok();
+                crate::error::Error::InsufficientStorage(response, quota)
+            }
+            err => err,
         }
     }
+
+    /// Returns the principal of the authenticated user, as reported by
+    /// `url`, e.g. the DAV root. `None` if the server does not know the
+    /// user.
+    pub async fn current_user_principal(
+        &self,
+        url: impl IntoUrl,
+    ) -> Result<Option<Principal>, crate::error::Error> {
+        let url = url.into_url()?;
+        let response = self
+            .request(Method::PROPFIND, url.clone())
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/xml"))
+            .header(DEPTH, HeaderValue::from(Depth::Zero))
+            .body(principal::CURRENT_USER_PRINCIPAL)
+            .send()
+            .await?;
+        let xml = check(Method::PROPFIND, response).await?.text().await?;
+        match PrincipalMultiStatus::parse(&xml)?.current_user_principal() {
+            Some(href) => self.principal(url.join(&href)?).await.map(Some),
+            None => Ok(None),
+        }
+    }
+    /// Reads the principal at `url`.
+    pub async fn principal(&self, url: impl IntoUrl) -> Result<Principal, crate::error::Error> {
+        let url = url.into_url()?;
+        let response = self
+            .request(Method::PROPFIND, url.clone())
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/xml"))
+            .header(DEPTH, HeaderValue::from(Depth::Zero))
+            .body(principal::PRINCIPAL_PROPFIND)
+            .send()
+            .await?;
+        let xml = check(Method::PROPFIND, response).await?.text().await?;
+        let principals = PrincipalMultiStatus::parse(&xml)?.into_principals();
+        Ok(principals.into_iter().next().unwrap_or_else(|| Principal {
+            href: url.path().to_owned(),
+            ..Default::default()
+        }))
+    }
+    /// Finds the principals matching `search` with the
+    /// `principal-property-search` REPORT.
+    pub async fn principal_property_search(
+        &self,
+        url: impl IntoUrl,
+        search: &PrincipalSearch,
+    ) -> Result<Vec<Principal>, crate::error::Error> {
+        let multi_status: PrincipalMultiStatus = self.report(url, &search.report()).await?;
+        Ok(multi_status.into_principals())
+    }
+    /// Returns the changes to the members of the collection at `url` since
+    /// `sync_token`, or all members if `None`, with the `sync-collection`
+    /// REPORT (RFC 6578). Follows the server's truncation until all changes
+    /// are read.
+    ///
+    /// If the server truncates a page without advancing the token, the
+    /// changes read so far are returned with [`SyncChanges::truncated`]
+    /// still set; sync again later with their token to read the rest.
+    ///
+    /// Fails with [`DavCondition::ValidSyncToken`] once the server no longer
+    /// knows `sync_token`; sync again with `None` then.
+    ///
+    /// # Usage
+    ///
+    /// ```no_run
+    /// # async fn run() -> Result<(), webdav_request::error::Error> {
+    /// let client = webdav_request::WebDAVClient::new("user", "password")?;
+    /// let url = "https://dav.example.com/docs/";
+    /// let mut changes = client.sync_collection(url, None).await?;
+    /// loop {
+    ///     // Watch for the next changes.
+    ///     changes = client.sync_collection(url, Some(&changes.sync_token)).await?;
+    ///     for resource in &changes.changed {
+    ///         println!("changed: {}", resource.href);
+    ///     }
+    ///     for href in &changes.removed {
+    ///         println!("removed: {href}");
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub async fn sync_collection(
+        &self,
+        url: impl IntoUrl,
+        sync_token: Option<&str>,
+    ) -> Result<SyncChanges, crate::error::Error> {
+        let url = url.into_url()?;
+        let mut changes = self
+            .sync_collection_page(url.clone(), sync_token, None)
+            .await?;
+        while changes.truncated {
+            let token = changes.sync_token.clone();
+            let next = self.sync_collection_page(url.clone(), Some(&token), None);
+            let next = next.await?;
+            // A token that does not advance would never finish.
+            let stalled = next.sync_token == token;
+            changes.merge(next);
+            if stalled {
+                break;
+            }
+        }
+        Ok(changes)
+    }
+    /// Requests at most `limit` changes since `sync_token`. If
+    /// [`SyncChanges::truncated`], the rest follow with the returned token.
+    ///
+    /// Servers that cannot limit the results fail with `507 Insufficient
+    /// Storage`.
+    pub async fn sync_collection_page(
+        &self,
+        url: impl IntoUrl,
+        sync_token: Option<&str>,
+        limit: Option<u32>,
+    ) -> Result<SyncChanges, crate::error::Error> {
+        let body = sync_collection::SyncCollection::new(sync_token, limit);
+        let multi_status: MultiStatus = self.report(url, &Report::new(body)).await?;
+        Ok(SyncChanges::from(multi_status))
+    }
+    /// Sends the `REPORT` method with `report` as body and parses the
+    /// response, usually a [`MultiStatus`] or a type of the same shape.
+    ///
+    /// Response elements are matched by local name, without their prefix.
+    pub async fn report<Req: Serialize, Resp: DeserializeOwned>(
+        &self,
+        url: impl IntoUrl,
+        report: &Report<Req>,
+    ) -> Result<Resp, crate::error::Error> {
+        let response = self
+            .request(Method::REPORT, url.into_url()?)
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/xml"))
+            .header(DEPTH, HeaderValue::from(report.depth))
+            .body(report.to_xml()?)
+            .send()
+            .await?;
+        let xml = check(Method::REPORT, response).await?.text().await?;
+        Ok(quick_xml::de::from_str(&xml)?)
+    }
     #[inline(always)]
     pub async fn all_propfind(&self, url: impl IntoUrl) -> Result<Response, crate::error::Error> {
         self.request(Method::PROPFIND, url.into_url()?)
@@ -83,33 +525,59 @@ impl WebDAVClient {
             .body(ALL_DROP)
             .send()
             .await
-            .map_err(Into::into)
     }
 }
 
+/// Turns an unsuccessful response into an error.
+pub(crate) async fn check(
+    method: Method,
+    response: Response,
+) -> Result<Response, crate::error::Error> {
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        Err(crate::error::Error::from_response(method.convert(), response).await)
+    }
+}
+
+async fn lock_response(url: Url, response: Response) -> Result<Lock, crate::error::Error> {
+    let headers = response.headers().clone();
+    let xml = response.text().await?;
+    lock::parse_lock(url, &headers, &xml)
+}
+
 pub struct WevDAVRequestBuilder {
     client: Arc<InnerClient>,
-    basic_auth: Option<(String, String)>,
+    auth: Option<Auth>,
     url: Url,
     headers: HeaderMap,
     body: Option<Body>,
     method: Method,
+    /// The first header that could not be built, returned when sending.
+    error: Option<crate::error::Error>,
+    preemptive: bool,
 }
 
 impl WevDAVRequestBuilder {
     pub fn new(client: Arc<InnerClient>, url: Url, method: Method) -> Self {
         Self {
             client,
-            basic_auth: None,
+            auth: None,
             headers: HeaderMap::new(),
             url,
             method,
             body: None,
+            error: None,
+            preemptive: false,
         }
     }
     pub fn basic_auth(self, username: &str, password: &str) -> Self {
+        self.auth(Auth::basic(username, password))
+    }
+    /// Overrides the client's credentials for this request.
+    pub fn auth(self, auth: Auth) -> Self {
         Self {
-            basic_auth: Some((username.to_owned(), password.to_owned())),
+            auth: Some(auth),
             ..self
         }
     }
@@ -126,35 +594,179 @@ impl WevDAVRequestBuilder {
             header_value!(format!("bytes={}-{}", start, end)),
         )
     }
+    /// Applies the request only if the resource's entity tag is `etag`.
+    ///
+    /// An `etag` that is no valid header value fails the request with
+    /// [`crate::error::Error::InvalidHeader`].
+    pub fn if_match(self, etag: &str) -> Self {
+        self.try_header(IF_MATCH, conditional::entity_tag(IF_MATCH, etag))
+    }
+    /// Applies the request only if the resource does not exist, e.g. for a
+    /// create-only upload.
+    pub fn if_none_match_any(self) -> Self {
+        self.header(IF_NONE_MATCH, HeaderValue::from_static("*"))
+    }
+    /// Applies the request only if the resource's entity tag is not `etag`.
+    pub fn if_none_match(self, etag: &str) -> Self {
+        self.try_header(IF_NONE_MATCH, conditional::entity_tag(IF_NONE_MATCH, etag))
+    }
+    /// A `time` before 1970 or after 9999 fails the request with
+    /// [`crate::error::Error::InvalidHeader`].
+    pub fn if_modified_since(self, time: SystemTime) -> Self {
+        let value = conditional::http_date(IF_MODIFIED_SINCE, time);
+        self.try_header(IF_MODIFIED_SINCE, value)
+    }
+    /// A `time` before 1970 or after 9999 fails the request with
+    /// [`crate::error::Error::InvalidHeader`].
+    pub fn if_unmodified_since(self, time: SystemTime) -> Self {
+        let value = conditional::http_date(IF_UNMODIFIED_SINCE, time);
+        self.try_header(IF_UNMODIFIED_SINCE, value)
+    }
+    /// Submits lock tokens or entity tags in an `If` header, e.g. a [`Lock`].
+    ///
+    /// An entity tag that is no valid header value fails the request with
+    /// [`crate::error::Error::InvalidHeader`].
+    pub fn if_header(self, header: impl Into<IfHeader>) -> Self {
+        self.try_header(IF, header.into().to_header_value())
+    }
     pub fn header(mut self, key: HeaderName, val: HeaderValue) -> Self {
         self.headers.insert(key, val);
         self
     }
+    /// Sets a header, or keeps the error for [`Self::send`] to return.
+    fn try_header(self, key: HeaderName, val: Result<HeaderValue, crate::error::Error>) -> Self {
+        match val {
+            Ok(val) => self.header(key, val),
+            Err(err) => Self {
+                error: self.error.or(Some(err)),
+                ..self
+            },
+        }
+    }
     pub fn headers(mut self, headers: HeaderMap) -> Self {
         self.headers.extend(headers);
         self
     }
 
-    pub fn build(self) -> crate::RequestBuilder {
-        let builder = self.client.inner.request(self.method.convert(), self.url);
-        let builder = if let Some(body) = self.body {
-            builder.body(body)
-        } else {
-            builder
-        };
-        if let Some((usr, pass)) = &self.basic_auth {
-            builder.basic_auth(usr, Some(pass))
-        } else if let Some((usr, psw)) = &self.client.auth {
-            builder.basic_auth(usr, Some(psw))
-        } else {
-            panic!("Missing basic auth!")
+    /// The request with the client's credentials, to be sent by reqwest.
+    ///
+    /// Neither redirects nor retries are handled for it: the client's HTTP
+    /// client does not follow redirects, which
+    /// [`WevDAVRequestBuilder::send`] does instead, so a redirect response
+    /// is returned as it is. A [`CredentialProvider`] is asked on the
+    /// current thread.
+    pub fn build(self) -> Result<crate::RequestBuilder, crate::error::Error> {
+        let client = self.client.clone();
+        let mut dispatch = self.into_dispatch()?.preemptive();
+        let credentials = dispatch.credentials(&client)?;
+        dispatch_request(&client, &mut dispatch, credentials)
+    }
+    /// Sends the credentials with the first try, regardless of
+    /// [`WebDAVClientBuilder::preemptive_auth`], e.g. for a stream body
+    /// that cannot be sent again after a challenge.
+    #[cfg(feature = "transfer")]
+    pub(crate) fn preemptive(self) -> Self {
+        Self {
+            preemptive: true,
+            ..self
+        }
+    }
+    fn into_dispatch(self) -> Result<Dispatch<Body>, crate::error::Error> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        let dispatch = Dispatch::new(
+            &self.client,
+            self.method,
+            self.url,
+            self.headers,
+            self.auth,
+            self.body,
+        );
+        Ok(match self.preemptive {
+            true => dispatch.preemptive(),
+            false => dispatch,
+        })
+    }
+    /// Sends a request carrying conditional headers.
+    ///
+    /// `304 Not Modified` is returned as [`Conditional::NotModified`], and
+    /// `412 Precondition Failed` as
+    /// [`crate::error::Error::PreconditionFailed`].
+    pub async fn send_conditional(self) -> Result<Conditional, crate::error::Error> {
+        let method = self.method.clone();
+        let response = self.send().await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(Conditional::NotModified(response));
         }
-        .headers(self.headers)
+        check(method, response).await.map(Conditional::Applied)
     }
     pub fn into_lazy_reader(self) -> LazyResponseReader {
-        LazyResponseReader::new(self.build())
+        LazyResponseReader::pending(Box::pin(self.send()))
     }
-    pub fn send(self) -> impl Future<Output = Result<Response, reqwest::Error>> {
-        self.build().send()
+    /// Sends the request, retrying it according to the client's
+    /// [`RetryPolicy`].
+    ///
+    /// Unless [`WebDAVClientBuilder::preemptive_auth`] is disabled, the
+    /// credentials are sent with the first try.
+    ///
+    /// `301`, `302`, `307` and `308` redirects are followed with the same
+    /// method, body and headers; [`Response::url`] is the final URL.
+    pub async fn send(self) -> Result<Response, crate::error::Error> {
+        let client = self.client.clone();
+        let mut dispatch = self.into_dispatch()?;
+        loop {
+            let credentials = match dispatch.needs_credentials() {
+                true => client.credentials_async(&dispatch.url).await?,
+                false => None,
+            };
+            let result = dispatch_request(&client, &mut dispatch, credentials)?
+                .send()
+                .await;
+            let mut step = match &result {
+                Ok(response) => {
+                    dispatch.on_response(&client, response.status(), response.headers())?
+                }
+                Err(err) => dispatch.on_error(&client, err),
+            };
+            if let (Step::Refresh, Ok(response)) = (&step, &result) {
+                let refreshed = client.refresh_async().await?;
+                step =
+                    dispatch.on_refresh(&client, refreshed, response.status(), response.headers());
+            }
+            match step {
+                Step::Return | Step::Refresh => return result.map_err(Into::into),
+                Step::Resend => continue,
+                Step::Wait(delay) => {
+                    drop(result);
+                    tokio::time::sleep(delay).await;
+                }
+                Step::NotReplayable => {
+                    let method = dispatch.method.convert();
+                    let err = match result {
+                        Ok(response) => crate::error::Error::from_response(method, response).await,
+                        Err(err) => err.into(),
+                    };
+                    return Err(crate::error::Error::NotReplayable(Box::new(err)));
+                }
+            }
+        }
     }
 }
+
+/// The next try of `dispatch`.
+fn dispatch_request(
+    client: &InnerClient,
+    dispatch: &mut Dispatch<Body>,
+    credentials: Option<Auth>,
+) -> Result<crate::RequestBuilder, crate::error::Error> {
+    let (headers, body) = dispatch.next(client, credentials)?;
+    let builder = client
+        .inner
+        .request(dispatch.method.clone().convert(), dispatch.url.clone())
+        .headers(headers);
+    Ok(match body {
+        Some(body) => builder.body(body),
+        None => builder,
+    })
+}
@@ -0,0 +1,61 @@
+use bytes::Bytes;
+use reqwest::Body;
+
+/// The request body types of the async and the blocking client.
+pub(crate) trait RequestBody: Sized {
+    /// The body's content if it is buffered.
+    fn buffered(&self) -> Option<&[u8]>;
+    fn from_bytes(bytes: Bytes) -> Self;
+}
+
+impl RequestBody for Body {
+    fn buffered(&self) -> Option<&[u8]> {
+        self.as_bytes()
+    }
+    fn from_bytes(bytes: Bytes) -> Self {
+        Body::from(bytes)
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl RequestBody for reqwest::blocking::Body {
+    fn buffered(&self) -> Option<&[u8]> {
+        self.as_bytes()
+    }
+    fn from_bytes(bytes: Bytes) -> Self {
+        reqwest::blocking::Body::from(bytes)
+    }
+}
+
+/// A request body that can be sent more than once when it is buffered.
+pub(crate) enum Payload<B = Body> {
+    Empty,
+    Bytes(Bytes),
+    Stream(Option<B>),
+}
+
+impl<B: RequestBody> Payload<B> {
+    pub(crate) fn new(body: Option<B>) -> Self {
+        match body {
+            None => Self::Empty,
+            Some(body) => match body.buffered() {
+                Some(bytes) => Self::Bytes(Bytes::copy_from_slice(bytes)),
+                None => Self::Stream(Some(body)),
+            },
+        }
+    }
+
+    /// Whether [`Payload::body`] can still produce the body.
+    pub(crate) fn is_replayable(&self) -> bool {
+        !matches!(self, Self::Stream(None))
+    }
+
+    /// The body for the next attempt. A stream body is only produced once.
+    pub(crate) fn body(&mut self) -> Option<B> {
+        match self {
+            Self::Empty => None,
+            Self::Bytes(bytes) => Some(B::from_bytes(bytes.clone())),
+            Self::Stream(body) => body.take(),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use reqwest::{
+    header::{HeaderMap, HeaderValue, AUTHORIZATION, COOKIE, LOCATION, PROXY_AUTHORIZATION},
+    StatusCode, Url,
+};
+
+/// The default number of redirects followed for one request.
+pub(crate) const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+const DESTINATION: &str = "destination";
+
+/// The target of a redirect that keeps the request method, or `None`.
+///
+/// `303 See Other` is not followed, since it asks for a `GET` of another
+/// resource rather than a replay of the request.
+pub(crate) fn location(status: StatusCode, headers: &HeaderMap, url: &Url) -> Option<Url> {
+    if !matches!(
+        status,
+        StatusCode::MOVED_PERMANENTLY
+            | StatusCode::FOUND
+            | StatusCode::TEMPORARY_REDIRECT
+            | StatusCode::PERMANENT_REDIRECT
+    ) {
+        return None;
+    }
+    let location = headers.get(LOCATION)?.to_str().ok()?;
+    url.join(location).ok()
+}
+
+/// Adjusts the request headers for a redirect from `from` to `to`.
+///
+/// An absolute `Destination` on the old origin is moved to the new origin,
+/// and credentials are dropped when the redirect leaves the origin. An
+/// upgrade from `http` to `https` on the same host and the default ports
+/// keeps them. Returns whether credentials may still be sent.
+pub(crate) fn follow(headers: &mut HeaderMap, from: &Url, to: &Url) -> bool {
+    if from.origin() != to.origin() {
+        let destination = headers
+            .get(DESTINATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| Url::parse(value).ok())
+            .filter(|destination| destination.origin() == from.origin());
+        if let Some(mut destination) = destination {
+            let _ = destination.set_scheme(to.scheme());
+            let _ = destination.set_host(to.host_str());
+            let _ = destination.set_port(to.port());
+            if let Ok(value) = HeaderValue::from_str(destination.as_str()) {
+                headers.insert(DESTINATION, value);
+            }
+        }
+    }
+    let upgrade = from.scheme() == "http"
+        && to.scheme() == "https"
+        && from.host_str() == to.host_str()
+        && from.port().is_none()
+        && to.port().is_none();
+    let trusted = from.origin() == to.origin() || upgrade;
+    if !trusted {
+        headers.remove(AUTHORIZATION);
+        headers.remove(COOKIE);
+        headers.remove(PROXY_AUTHORIZATION);
+    }
+    trusted
+}
@@ -0,0 +1,111 @@
+use quick_xml::events::{BytesDecl, Event};
+use quick_xml::{DeError, Reader, Writer};
+use serde::Serialize;
+
+use crate::depth::Depth;
+use crate::error::Error;
+
+/// The body of a `REPORT` request (RFC 3253, section 3.6), sent by
+/// [`super::WebDAVClient::report`].
+///
+/// `body` is written as the root element, named after its type or its
+/// `#[serde(rename)]`. The `d` prefix is bound to `DAV:`; bind others with
+/// [`Report::namespace`].
+///
+/// # Usage
+///
+/// ```
+/// use serde::Serialize;
+/// use webdav_request::client::Report;
+///
+/// #[derive(Serialize)]
+/// #[serde(rename = "oc:filter-files")]
+/// struct FilterFiles {
+///     #[serde(rename = "d:prop")]
+///     prop: Prop,
+///     #[serde(rename = "oc:filter-rules")]
+///     rules: Rules,
+/// }
+/// #[derive(Serialize)]
+/// struct Prop {
+///     #[serde(rename = "d:displayname")]
+///     display_name: (),
+/// }
+/// #[derive(Serialize)]
+/// struct Rules {
+///     #[serde(rename = "oc:favorite")]
+///     favorite: u8,
+/// }
+///
+/// let report = Report::new(FilterFiles {
+///     prop: Prop { display_name: () },
+///     rules: Rules { favorite: 1 },
+/// })
+/// .namespace("oc", "http://owncloud.org/ns");
+/// assert_eq!(
+///     report.to_xml()?,
+///     r#"<?xml version="1.0" encoding="utf-8"?><oc:filter-files xmlns:d="DAV:" xmlns:oc="http://owncloud.org/ns"><d:prop><d:displayname/></d:prop><oc:filter-rules><oc:favorite>1</oc:favorite></oc:filter-rules></oc:filter-files>"#
+/// );
+/// # Ok::<(), webdav_request::error::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Report<Req> {
+    pub(crate) body: Req,
+    pub(crate) depth: Depth,
+    namespaces: Vec<(String, String)>,
+}
+
+impl<Req: Serialize> Report<Req> {
+    pub fn new(body: Req) -> Self {
+        Self {
+            body,
+            depth: Depth::Zero,
+            namespaces: vec![(String::from("d"), String::from("DAV:"))],
+        }
+    }
+    /// The `Depth` header, `0` by default.
+    pub fn depth(self, depth: Depth) -> Self {
+        Self { depth, ..self }
+    }
+    /// Binds `prefix` to `namespace` on the root element, e.g. `oc` to
+    /// `http://owncloud.org/ns`.
+    pub fn namespace(mut self, prefix: &str, namespace: &str) -> Self {
+        self.namespaces.retain(|(bound, _)| bound != prefix);
+        self.namespaces
+            .push((prefix.to_owned(), namespace.to_owned()));
+        self
+    }
+
+    /// Writes the XML document.
+    pub fn to_xml(&self) -> Result<String, Error> {
+        let body = quick_xml::se::to_string(&self.body)?;
+        let mut reader = Reader::from_str(&body);
+        let (mut root, empty) = match reader.read_event().map_err(DeError::from)? {
+            Event::Start(start) => (start.into_owned(), false),
+            Event::Empty(start) => (start.into_owned(), true),
+            _ => return Err(DeError::Custom("no root element".to_owned()).into()),
+        };
+        let members = &body[reader.buffer_position() as usize..];
+        for (prefix, namespace) in &self.namespaces {
+            let key = format!("xmlns:{prefix}");
+            // Declared by the body itself.
+            let declared = root
+                .attributes()
+                .flatten()
+                .any(|attribute| attribute.key.as_ref() == key.as_bytes());
+            if !declared {
+                root.push_attribute((key.as_str(), namespace.as_str()));
+            }
+        }
+        let mut writer = Writer::new(Vec::new());
+        writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("utf-8"), None)))?;
+        writer.write_event(if empty {
+            Event::Empty(root)
+        } else {
+            Event::Start(root)
+        })?;
+        let mut xml = String::from_utf8_lossy(&writer.into_inner()).into_owned();
+        xml.push_str(members);
+        Ok(xml)
+    }
+}
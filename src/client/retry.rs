@@ -0,0 +1,164 @@
+use std::time::{Duration, SystemTime};
+
+use reqwest::{
+    header::{HeaderMap, RETRY_AFTER},
+    StatusCode,
+};
+
+use crate::Method;
+
+/// Decides whether and when a failed request is sent again.
+///
+/// Only idempotent methods (see [`Method::is_idempotent`]) are retried
+/// unless [`RetryPolicy::retry_non_idempotent`] is enabled.
+///
+/// # Usage
+///
+/// ```
+/// use std::time::Duration;
+/// use webdav_request::{client::RetryPolicy, StatusCode};
+///
+/// let policy = RetryPolicy::new()
+///     .max_attempts(5)
+///     .backoff(Duration::from_millis(100), Duration::from_secs(5))
+///     .statuses([StatusCode::TOO_MANY_REQUESTS, StatusCode::SERVICE_UNAVAILABLE]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+    statuses: Vec<StatusCode>,
+    retry_error: fn(&reqwest::Error) -> bool,
+    respect_retry_after: bool,
+    non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(10),
+            jitter: true,
+            statuses: vec![
+                StatusCode::REQUEST_TIMEOUT,
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+            retry_error: |err| err.is_connect() || err.is_timeout(),
+            respect_retry_after: true,
+            non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// The total number of attempts, including the first one.
+    pub fn max_attempts(self, max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            ..self
+        }
+    }
+    /// The delay before the first retry, doubled on every further retry up
+    /// to `max`.
+    pub fn backoff(self, base: Duration, max: Duration) -> Self {
+        Self {
+            base_delay: base,
+            max_delay: max.max(base),
+            ..self
+        }
+    }
+    /// Randomizes every delay between half and all of its computed value.
+    pub fn jitter(self, jitter: bool) -> Self {
+        Self { jitter, ..self }
+    }
+    /// The response statuses that are retried.
+    pub fn statuses(self, statuses: impl IntoIterator<Item = StatusCode>) -> Self {
+        Self {
+            statuses: statuses.into_iter().collect(),
+            ..self
+        }
+    }
+    /// Decides which transport errors are retried. Connection failures and
+    /// timeouts are retried by default.
+    pub fn retry_error(self, retry_error: fn(&reqwest::Error) -> bool) -> Self {
+        Self {
+            retry_error,
+            ..self
+        }
+    }
+    /// Waits as long as the `Retry-After` header asks for. A response asking
+    /// for more than the maximum backoff is returned without retrying.
+    pub fn respect_retry_after(self, respect_retry_after: bool) -> Self {
+        Self {
+            respect_retry_after,
+            ..self
+        }
+    }
+    /// Retries methods such as `POST`, `MOVE` or `LOCK` as well.
+    pub fn retry_non_idempotent(self, non_idempotent: bool) -> Self {
+        Self {
+            non_idempotent,
+            ..self
+        }
+    }
+
+    pub(crate) fn applies_to(&self, method: &Method) -> bool {
+        self.non_idempotent || method.is_idempotent()
+    }
+
+    /// The delay before retrying a request whose `attempt`-th try got a
+    /// response with `status`, or `None` to give up.
+    pub(crate) fn response_delay(
+        &self,
+        attempt: u32,
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts || !self.statuses.contains(&status) {
+            return None;
+        }
+        if self.respect_retry_after {
+            if let Some(delay) = retry_after(headers) {
+                return (delay <= self.max_delay).then_some(delay);
+            }
+        }
+        Some(self.backoff_delay(attempt))
+    }
+
+    pub(crate) fn error_delay(&self, attempt: u32, err: &reqwest::Error) -> Option<Duration> {
+        (attempt < self.max_attempts && (self.retry_error)(err))
+            .then(|| self.backoff_delay(attempt))
+    }
+
+    fn backoff_delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        if self.jitter {
+            delay / 2 + delay.mul_f64((super::random() % 1000) as f64 / 2000.0)
+        } else {
+            delay
+        }
+    }
+}
+
+/// Parses `Retry-After` as either delay seconds or an HTTP date.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
@@ -0,0 +1,120 @@
+use serde::Serialize;
+
+use crate::res::{MultiStatus, Resource};
+
+/// The body of the `sync-collection` REPORT.
+#[derive(Serialize)]
+#[serde(rename = "d:sync-collection")]
+pub(crate) struct SyncCollection<'a> {
+    #[serde(rename = "d:sync-token")]
+    sync_token: &'a str,
+    #[serde(rename = "d:sync-level")]
+    sync_level: u8,
+    #[serde(rename = "d:limit", skip_serializing_if = "Option::is_none")]
+    limit: Option<Limit>,
+    #[serde(rename = "d:prop")]
+    prop: PropNames,
+}
+
+impl<'a> SyncCollection<'a> {
+    pub(crate) fn new(sync_token: Option<&'a str>, limit: Option<u32>) -> Self {
+        Self {
+            sync_token: sync_token.unwrap_or_default(),
+            sync_level: 1,
+            limit: limit.map(|nresults| Limit { nresults }),
+            prop: PropNames::default(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Limit {
+    #[serde(rename = "d:nresults")]
+    nresults: u32,
+}
+
+/// The properties [`Resource`] is built from.
+#[derive(Serialize, Default)]
+struct PropNames {
+    #[serde(rename = "d:displayname")]
+    display_name: (),
+    #[serde(rename = "d:resourcetype")]
+    resource_type: (),
+    #[serde(rename = "d:getcontentlength")]
+    content_length: (),
+    #[serde(rename = "d:getcontenttype")]
+    content_type: (),
+    #[serde(rename = "d:getlastmodified")]
+    last_modified: (),
+    #[serde(rename = "d:getetag")]
+    etag: (),
+}
+
+/// The changes to the members of a collection since a sync token, returned
+/// by [`super::WebDAVClient::sync_collection`].
+#[derive(Debug, Clone, Default)]
+pub struct SyncChanges {
+    /// The members created or changed.
+    pub changed: Vec<Resource>,
+    /// The hrefs of the members removed, decoded like [`Resource::href`].
+    pub removed: Vec<String>,
+    /// The token to pass to the next sync.
+    pub sync_token: String,
+    /// Whether the server has more changes, to be requested with
+    /// `sync_token`. Also set on the result of
+    /// [`crate::WebDAVClient::sync_collection`] when the server truncated
+    /// a page without advancing the token.
+    pub truncated: bool,
+}
+
+impl From<MultiStatus> for SyncChanges {
+    fn from(multi_status: MultiStatus) -> Self {
+        let mut changes = SyncChanges {
+            sync_token: multi_status.sync_token.unwrap_or_default(),
+            ..Default::default()
+        };
+        for response in multi_status.response {
+            let code = response
+                .status
+                .as_deref()
+                .and_then(|status| status.split_whitespace().nth(1))
+                .map(str::to_owned);
+            match code.as_deref() {
+                None => changes.changed.push(Resource::from(response)),
+                #[cfg(feature = "decode_url")]
+                Some("404") => changes.removed.push(
+                    percent_encoding::percent_decode_str(&response.href)
+                        .decode_utf8()
+                        .map(|s| s.to_string())
+                        .unwrap_or(response.href),
+                ),
+                #[cfg(not(feature = "decode_url"))]
+                Some("404") => changes.removed.push(response.href),
+                // Reported for the collection itself when the server stops
+                // early.
+                Some("507") => changes.truncated = true,
+                Some(_) => (),
+            }
+        }
+        changes
+    }
+}
+
+impl SyncChanges {
+    /// Appends the changes of the next page; later changes of a member
+    /// replace earlier ones.
+    pub(crate) fn merge(&mut self, next: SyncChanges) {
+        for resource in next.changed {
+            self.changed.retain(|changed| changed.href != resource.href);
+            self.removed.retain(|removed| *removed != resource.href);
+            self.changed.push(resource);
+        }
+        for href in next.removed {
+            self.changed.retain(|changed| changed.href != href);
+            self.removed.retain(|removed| *removed != href);
+            self.removed.push(href);
+        }
+        self.sync_token = next.sync_token;
+        self.truncated = next.truncated;
+    }
+}
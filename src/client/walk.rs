@@ -0,0 +1,89 @@
+use std::ffi::OsStr;
+use std::path::{Component, Path};
+
+use reqwest::Url;
+
+use super::WebDAVClient;
+use crate::error::Error;
+use crate::filter::Filter;
+use crate::res::Resource;
+
+/// The path of `href`, listed in `base`, relative to `root`, e.g.
+/// `docs/a.txt`. `None` unless it is a [plain path](is_plain_path) once
+/// decoded, e.g. for `..%2Fevil.txt`.
+pub(crate) fn relative_path(root: &Url, base: &Url, href: &str) -> Option<String> {
+    let url = base.join(href).ok()?;
+    let path = url.path().strip_prefix(root.path())?.trim_end_matches('/');
+    let path = percent_encoding::percent_decode_str(path)
+        .decode_utf8()
+        .ok()?;
+    is_plain_path(&path).then(|| path.into_owned())
+}
+
+/// Whether the relative `path` is made of plain names, e.g. `docs/a.txt`,
+/// so that it stays below the directory or collection it is joined to: no
+/// empty names, `.`, `..`, `\`, roots or drive prefixes.
+pub(crate) fn is_plain_path(path: &str) -> bool {
+    path.split('/').all(|name| {
+        let mut components = Path::new(name).components();
+        !name.contains('\\')
+            && matches!(components.next(), Some(Component::Normal(normal)) if normal == OsStr::new(name))
+            && components.next().is_none()
+    })
+}
+
+/// The collection URL of `url`, ending with `/`.
+pub(crate) fn collection_url(mut url: Url) -> Url {
+    if !url.path().ends_with('/') {
+        url.set_path(&format!("{}/", url.path()));
+    }
+    url
+}
+
+/// Lists the tree below `root` with one `PROPFIND` per collection, calling
+/// `visit` with the relative path of each member. Collections for which it
+/// returns `false` are not entered.
+pub(crate) async fn walk(
+    client: &WebDAVClient,
+    root: &Url,
+    mut visit: impl FnMut(String, Resource) -> bool,
+) -> Result<(), Error> {
+    let mut pending = vec![root.clone()];
+    while let Some(url) = pending.pop() {
+        let collection = client.list(url.clone()).await?;
+        for child in collection.children {
+            let Some(path) = relative_path(root, &url, &child.href) else {
+                continue;
+            };
+            let next = child.is_collection.then(|| url.join(&child.href));
+            if visit(path, child) {
+                if let Some(Ok(next)) = next {
+                    pending.push(collection_url(next));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+impl WebDAVClient {
+    /// Lists every resource below the collection at `url` that `filter`
+    /// accepts, without entering excluded collections.
+    pub async fn walk(
+        &self,
+        url: impl reqwest::IntoUrl,
+        filter: &Filter,
+    ) -> Result<Vec<Resource>, Error> {
+        let root = collection_url(url.into_url()?);
+        let mut resources = Vec::new();
+        walk(self, &root, |path, resource| {
+            let accepted = filter.accepts_resource(&path, &resource);
+            if accepted {
+                resources.push(resource);
+            }
+            accepted
+        })
+        .await?;
+        Ok(resources)
+    }
+}
@@ -0,0 +1,33 @@
+//! The WebDAV `Depth` request header (RFC 4918, section 10.2).
+
+use reqwest::header::{HeaderName, HeaderValue};
+
+pub const DEPTH: HeaderName = HeaderName::from_static("depth");
+
+/// How deep below the request URL a method applies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Depth {
+    /// The resource itself.
+    Zero,
+    /// The resource and its members.
+    One,
+    #[default]
+    Infinity,
+}
+
+impl Depth {
+    /// `0`, `1` or `infinity`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Zero => "0",
+            Self::One => "1",
+            Self::Infinity => "infinity",
+        }
+    }
+}
+
+impl From<Depth> for HeaderValue {
+    fn from(value: Depth) -> Self {
+        HeaderValue::from_static(value.as_str())
+    }
+}
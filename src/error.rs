@@ -1,14 +1,45 @@
-use reqwest::StatusCode;
+use crate::res::quota::QuotaInfo;
+use crate::res::DavCondition;
+use reqwest::{Method, Response, StatusCode, Url};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// The maximum number of response body bytes kept in a [`ResponseError`].
+const MAX_BODY_LEN: usize = 4096;
+
+#[non_exhaustive]
 pub enum Error {
     StdError(std::io::Error),
     RequestError(reqwest::Error),
     DeError(quick_xml::DeError),
-    ResponseError(StatusCode),
+    /// A request body could not be written as XML.
+    SeError(quick_xml::SeError),
+    ResponseError(Box<ResponseError>),
+    /// `412 Precondition Failed`: a conditional header did not match.
+    PreconditionFailed(Box<ResponseError>),
+    /// `507 Insufficient Storage` on `PUT`, `MKCOL` or `COPY`, with the
+    /// quota of the target collection if the client could read it.
+    InsufficientStorage(Box<ResponseError>, Option<QuotaInfo>),
     Utf8Error(std::str::Utf8Error),
     UrlError(url::ParseError),
+    /// A request had to be sent again but its streaming body was consumed.
+    NotReplayable(Box<Error>),
+    /// The redirect limit was exceeded; holds the last redirect target.
+    TooManyRedirects(reqwest::Url),
+    /// A [`crate::client::CredentialProvider`] could not supply credentials.
+    Credentials(Box<dyn std::error::Error + Send + Sync>),
+    /// The client has no credentials. Use [`crate::client::Auth::Anonymous`]
+    /// to send requests without them.
+    MissingAuth,
+    /// A header of a request could not be built from the given value, e.g.
+    /// an entity tag with a line break.
+    InvalidHeader(reqwest::header::HeaderName, String),
+    /// The name of a property or privilege is not a valid XML name, e.g.
+    /// `a b`.
+    InvalidXmlName(String),
+    /// An invalid pattern of a [`crate::filter::Filter`].
+    #[cfg(feature = "filter")]
+    Glob(globset::Error),
 }
 
 impl Error {
@@ -22,13 +53,188 @@ impl Error {
     pub fn is_de_err(&self) -> bool {
         matches!(self, Self::DeError(_))
     }
+    pub fn is_se_err(&self) -> bool {
+        matches!(self, Self::SeError(_))
+    }
     pub fn is_response_err(&self) -> bool {
-        matches!(self, Self::ResponseError(_))
+        matches!(
+            self,
+            Self::ResponseError(_) | Self::PreconditionFailed(_) | Self::InsufficientStorage(..)
+        )
     }
 
     pub fn is_invalid_utf8_err(&self) -> bool {
         matches!(self, Self::Utf8Error(_))
     }
+
+    pub fn is_url_err(&self) -> bool {
+        matches!(self, Self::UrlError(_))
+    }
+
+    pub fn is_not_replayable_err(&self) -> bool {
+        matches!(self, Self::NotReplayable(_))
+    }
+
+    pub fn is_too_many_redirects_err(&self) -> bool {
+        matches!(self, Self::TooManyRedirects(_))
+    }
+
+    pub fn is_credentials_err(&self) -> bool {
+        matches!(self, Self::Credentials(_))
+    }
+
+    pub fn is_missing_auth_err(&self) -> bool {
+        matches!(self, Self::MissingAuth)
+    }
+
+    pub fn is_invalid_header_err(&self) -> bool {
+        matches!(self, Self::InvalidHeader(..))
+    }
+
+    pub fn is_invalid_xml_name_err(&self) -> bool {
+        matches!(self, Self::InvalidXmlName(_))
+    }
+
+    #[cfg(feature = "filter")]
+    pub fn is_glob_err(&self) -> bool {
+        matches!(self, Self::Glob(_))
+    }
+
+    /// The HTTP status that caused this error, if any.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::ResponseError(err)
+            | Self::PreconditionFailed(err)
+            | Self::InsufficientStorage(err, _) => Some(err.status),
+            Self::RequestError(err) => err.status(),
+            Self::NotReplayable(err) => err.status(),
+            _ => None,
+        }
+    }
+
+    /// `404 Not Found`
+    pub fn is_not_found(&self) -> bool {
+        self.status() == Some(StatusCode::NOT_FOUND)
+    }
+    /// `409 Conflict`, e.g. a missing parent collection.
+    pub fn is_conflict(&self) -> bool {
+        self.status() == Some(StatusCode::CONFLICT)
+    }
+    /// `412 Precondition Failed`
+    pub fn is_precondition_failed(&self) -> bool {
+        self.status() == Some(StatusCode::PRECONDITION_FAILED)
+    }
+    /// `423 Locked`
+    pub fn is_locked(&self) -> bool {
+        self.status() == Some(StatusCode::LOCKED)
+    }
+    /// `507 Insufficient Storage`
+    pub fn is_insufficient_storage(&self) -> bool {
+        self.status() == Some(StatusCode::INSUFFICIENT_STORAGE)
+    }
+
+    /// The `DAV:error` conditions reported by the server, if any.
+    pub fn conditions(&self) -> &[DavCondition] {
+        match self {
+            Self::ResponseError(err)
+            | Self::PreconditionFailed(err)
+            | Self::InsufficientStorage(err, _) => &err.conditions,
+            Self::NotReplayable(err) => err.conditions(),
+            _ => &[],
+        }
+    }
+    /// The quota of an [`Error::InsufficientStorage`], if known.
+    pub fn quota(&self) -> Option<&QuotaInfo> {
+        match self {
+            Self::InsufficientStorage(_, quota) => quota.as_ref(),
+            Self::NotReplayable(err) => err.quota(),
+            _ => None,
+        }
+    }
+
+    /// Builds a [`ResponseError`] from an unsuccessful response, keeping at
+    /// most the first 4 KiB of its body. `412` becomes
+    /// [`Error::PreconditionFailed`], `507` on `PUT`, `MKCOL` or `COPY`
+    /// [`Error::InsufficientStorage`] without a quota.
+    pub async fn from_response(method: Method, mut response: Response) -> Self {
+        let status = response.status();
+        let url = response.url().clone();
+        let mut body = Vec::new();
+        while body.len() < MAX_BODY_LEN {
+            match response.chunk().await {
+                Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+                _ => break,
+            }
+        }
+        ResponseError::new(method, url, status, &body).into()
+    }
+
+    /// [`Error::from_response`] for a response of the blocking client.
+    #[cfg(feature = "blocking")]
+    pub fn from_blocking_response(method: Method, response: reqwest::blocking::Response) -> Self {
+        use std::io::Read;
+
+        let status = response.status();
+        let url = response.url().clone();
+        let mut body = Vec::new();
+        let _ = response.take(MAX_BODY_LEN as u64).read_to_end(&mut body);
+        ResponseError::new(method, url, status, &body).into()
+    }
+}
+
+/// An unsuccessful response together with the request that caused it.
+#[derive(Debug)]
+pub struct ResponseError {
+    method: Method,
+    url: Url,
+    status: StatusCode,
+    body: Option<String>,
+    conditions: Vec<DavCondition>,
+}
+
+impl ResponseError {
+    pub fn new(method: Method, url: Url, status: StatusCode, body: &[u8]) -> Self {
+        Self {
+            method,
+            url,
+            status,
+            body: truncate_body(body),
+            conditions: DavCondition::parse_all(&String::from_utf8_lossy(body)),
+        }
+    }
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+    /// The beginning of the response body, if the server sent one.
+    pub fn body(&self) -> Option<&str> {
+        self.body.as_deref()
+    }
+    /// The conditions parsed from a `DAV:error` response body.
+    pub fn conditions(&self) -> &[DavCondition] {
+        &self.conditions
+    }
+}
+
+impl std::fmt::Display for ResponseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} failed with {}",
+            self.method, self.url, self.status
+        )
+    }
+}
+
+fn truncate_body(body: &[u8]) -> Option<String> {
+    let body = String::from_utf8_lossy(&body[..body.len().min(MAX_BODY_LEN)]);
+    let body = body.trim();
+    (!body.is_empty()).then(|| body.to_owned())
 }
 
 impl From<std::io::Error> for Error {
@@ -46,6 +252,12 @@ impl From<quick_xml::DeError> for Error {
         Self::DeError(value)
     }
 }
+impl From<quick_xml::SeError> for Error {
+    fn from(value: quick_xml::SeError) -> Self {
+        Self::SeError(value)
+    }
+}
+
 impl From<std::str::Utf8Error> for Error {
     fn from(value: std::str::Utf8Error) -> Self {
         Self::Utf8Error(value)
@@ -58,15 +270,49 @@ impl From<url::ParseError> for Error {
     }
 }
 
+impl From<ResponseError> for Error {
+    fn from(value: ResponseError) -> Self {
+        let writes = matches!(value.method.as_str(), "PUT" | "MKCOL" | "COPY");
+        if value.status == StatusCode::PRECONDITION_FAILED {
+            Self::PreconditionFailed(Box::new(value))
+        } else if value.status == StatusCode::INSUFFICIENT_STORAGE && writes {
+            Self::InsufficientStorage(Box::new(value), None)
+        } else {
+            Self::ResponseError(Box::new(value))
+        }
+    }
+}
+
 impl std::fmt::Debug for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::StdError(arg0) => arg0.fmt(f),
             Self::RequestError(arg0) => arg0.fmt(f),
             Self::DeError(arg0) => arg0.fmt(f),
+            Self::SeError(arg) => arg.fmt(f),
             Self::ResponseError(arg) => arg.fmt(f),
+            Self::PreconditionFailed(arg) => {
+                f.debug_tuple("PreconditionFailed").field(arg).finish()
+            }
+            Self::InsufficientStorage(arg, quota) => f
+                .debug_tuple("InsufficientStorage")
+                .field(arg)
+                .field(quota)
+                .finish(),
             Error::Utf8Error(arg) => arg.fmt(f),
             Error::UrlError(parse_error) => parse_error.fmt(f),
+            Error::NotReplayable(arg) => f.debug_tuple("NotReplayable").field(arg).finish(),
+            Error::TooManyRedirects(url) => f.debug_tuple("TooManyRedirects").field(url).finish(),
+            Error::Credentials(arg) => f.debug_tuple("Credentials").field(arg).finish(),
+            Error::MissingAuth => f.write_str("MissingAuth"),
+            Error::InvalidHeader(name, reason) => f
+                .debug_tuple("InvalidHeader")
+                .field(name)
+                .field(reason)
+                .finish(),
+            Error::InvalidXmlName(name) => f.debug_tuple("InvalidXmlName").field(name).finish(),
+            #[cfg(feature = "filter")]
+            Error::Glob(err) => f.debug_tuple("Glob").field(err).finish(),
         }
     }
 }
@@ -77,10 +323,48 @@ impl std::fmt::Display for Error {
             Self::StdError(arg0) => arg0.fmt(f),
             Self::RequestError(arg0) => arg0.fmt(f),
             Self::DeError(arg0) => arg0.fmt(f),
-            Self::ResponseError(arg) => arg.fmt(f),
+            Self::SeError(arg) => arg.fmt(f),
+            Self::ResponseError(arg) | Self::PreconditionFailed(arg) => arg.fmt(f),
+            Self::InsufficientStorage(arg, quota) => {
+                arg.fmt(f)?;
+                match quota.and_then(|quota| quota.available.bytes()) {
+                    Some(available) => write!(f, ", {available} bytes available"),
+                    None => Ok(()),
+                }
+            }
             Self::Utf8Error(arg) => arg.fmt(f),
             Self::UrlError(arg) => arg.fmt(f),
+            Self::NotReplayable(arg) => {
+                write!(f, "cannot retry a request with a streaming body: {arg}")
+            }
+            Self::TooManyRedirects(url) => write!(f, "too many redirects, last to {url}"),
+            Self::Credentials(arg) => write!(f, "failed to get credentials: {arg}"),
+            Self::MissingAuth => f.write_str("missing credentials"),
+            Self::InvalidHeader(name, reason) => write!(f, "invalid `{name}` header: {reason}"),
+            Self::InvalidXmlName(name) => write!(f, "invalid XML name `{name}`"),
+            #[cfg(feature = "filter")]
+            Self::Glob(err) => write!(f, "invalid pattern: {err}"),
+        }
+    }
+}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::StdError(err) => Some(err),
+            Self::RequestError(err) => Some(err),
+            Self::DeError(err) => Some(err),
+            Self::SeError(err) => Some(err),
+            Self::ResponseError(_)
+            | Self::PreconditionFailed(_)
+            | Self::InsufficientStorage(..) => None,
+            Self::Utf8Error(err) => Some(err),
+            Self::UrlError(err) => Some(err),
+            Self::NotReplayable(err) => Some(err.as_ref()),
+            Self::TooManyRedirects(_) => None,
+            Self::Credentials(err) => Some(err.as_ref()),
+            Self::MissingAuth | Self::InvalidHeader(..) | Self::InvalidXmlName(_) => None,
+            #[cfg(feature = "filter")]
+            Self::Glob(err) => Some(err),
         }
     }
 }
-impl std::error::Error for Error {}
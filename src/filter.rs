@@ -0,0 +1,206 @@
+//! Include and exclude rules for recursive operations.
+//!
+//! Exclude rules follow `.gitignore`: a pattern without a `/` matches a name
+//! at any depth, a pattern with one is anchored at the root, a trailing `/`
+//! matches directories only, `!` re-includes, and the last matching rule
+//! wins. An excluded directory excludes everything below it.
+//!
+//! Include rules, size and modification time limits only apply to files, so
+//! that walks still descend into every directory.
+//!
+//! # Usage
+//!
+//! ```
+//! use webdav_request::filter::Filter;
+//!
+//! let filter = Filter::new()
+//!     .exclude(".DS_Store")?
+//!     .exclude("node_modules/")?
+//!     .exclude(".sync_*")?
+//!     .gitignore("*.log\n!keep.log\n")?
+//!     .max_size(100 << 20);
+//! assert!(filter.accepts("docs/a.txt", false, 10, None));
+//! assert!(!filter.accepts("web/node_modules/x/index.js", false, 10, None));
+//! assert!(!filter.accepts("logs/debug.log", false, 10, None));
+//! assert!(filter.accepts("logs/keep.log", false, 10, None));
+//! # Ok::<(), webdav_request::error::Error>(())
+//! ```
+use std::path::Path;
+use std::time::SystemTime;
+
+use globset::{GlobBuilder, GlobMatcher};
+
+use crate::error::Error;
+use crate::res::Resource;
+
+/// A compiled `.gitignore`-style pattern.
+#[derive(Debug, Clone)]
+struct Rule {
+    glob: GlobMatcher,
+    negated: bool,
+    dir_only: bool,
+}
+
+impl Rule {
+    fn parse(pattern: &str) -> Result<Option<Self>, Error> {
+        let pattern = pattern.trim_end();
+        if pattern.is_empty() || pattern.starts_with('#') {
+            return Ok(None);
+        }
+        let (negated, pattern) = match pattern.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern.strip_prefix('\\').unwrap_or(pattern)),
+        };
+        let (dir_only, pattern) = match pattern.strip_suffix('/') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern),
+        };
+        let glob = match pattern.strip_prefix('/') {
+            Some(anchored) => anchored.to_owned(),
+            None if pattern.contains('/') => pattern.to_owned(),
+            None => format!("**/{pattern}"),
+        };
+        let glob = GlobBuilder::new(&glob)
+            .literal_separator(true)
+            .backslash_escape(true)
+            .build()
+            .map_err(Error::Glob)?
+            .compile_matcher();
+        Ok(Some(Self {
+            glob,
+            negated,
+            dir_only,
+        }))
+    }
+
+    fn is_match(&self, path: &str, is_dir: bool) -> bool {
+        (is_dir || !self.dir_only) && self.glob.is_match(path)
+    }
+}
+
+/// Rules selecting the files and directories of a recursive operation.
+///
+/// Paths are relative to the root of the operation and separated by `/`,
+/// e.g. `docs/a.txt`.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    excludes: Vec<Rule>,
+    includes: Vec<Rule>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    modified_after: Option<SystemTime>,
+    modified_before: Option<SystemTime>,
+}
+
+impl Filter {
+    /// A filter accepting everything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Excludes paths matching the glob, e.g. `*.tmp` or `build/`.
+    pub fn exclude(mut self, pattern: &str) -> Result<Self, Error> {
+        self.excludes.extend(Rule::parse(pattern)?);
+        Ok(self)
+    }
+    /// Only accepts files matching one of the include globs, e.g. `*.md`.
+    pub fn include(mut self, pattern: &str) -> Result<Self, Error> {
+        self.includes.extend(Rule::parse(pattern)?);
+        Ok(self)
+    }
+    /// Adds the rules of a `.gitignore` file's content.
+    pub fn gitignore(mut self, content: &str) -> Result<Self, Error> {
+        for line in content.lines() {
+            self.excludes.extend(Rule::parse(line)?);
+        }
+        Ok(self)
+    }
+    /// Adds the rules of the `.gitignore`-style file at `path`.
+    pub fn gitignore_file(self, path: impl AsRef<Path>) -> Result<Self, Error> {
+        let content = std::fs::read_to_string(path)?;
+        self.gitignore(&content)
+    }
+    /// Only accepts files of at least `len` bytes.
+    pub fn min_size(self, len: u64) -> Self {
+        Self {
+            min_size: Some(len),
+            ..self
+        }
+    }
+    /// Only accepts files of at most `len` bytes.
+    pub fn max_size(self, len: u64) -> Self {
+        Self {
+            max_size: Some(len),
+            ..self
+        }
+    }
+    /// Only accepts files modified after `time`.
+    pub fn modified_after(self, time: SystemTime) -> Self {
+        Self {
+            modified_after: Some(time),
+            ..self
+        }
+    }
+    /// Only accepts files modified before `time`.
+    pub fn modified_before(self, time: SystemTime) -> Self {
+        Self {
+            modified_before: Some(time),
+            ..self
+        }
+    }
+
+    /// Whether the exclude rules exclude `path` or one of its parents.
+    pub fn excludes(&self, path: &str, is_dir: bool) -> bool {
+        let path = path.trim_matches('/');
+        let mut parents = path.match_indices('/').map(|(i, _)| &path[..i]);
+        parents.any(|parent| self.excluded(parent, true)) || self.excluded(path, is_dir)
+    }
+
+    fn excluded(&self, path: &str, is_dir: bool) -> bool {
+        self.excludes
+            .iter()
+            .rev()
+            .find(|rule| rule.is_match(path, is_dir))
+            .is_some_and(|rule| !rule.negated)
+    }
+
+    /// Whether `path` passes every rule. `modified` is only checked if
+    /// known.
+    pub fn accepts(
+        &self,
+        path: &str,
+        is_dir: bool,
+        len: u64,
+        modified: Option<SystemTime>,
+    ) -> bool {
+        if self.excludes(path, is_dir) {
+            return false;
+        }
+        if is_dir {
+            return true;
+        }
+        let path = path.trim_matches('/');
+        let included = self.includes.is_empty()
+            || self
+                .includes
+                .iter()
+                .any(|rule| !rule.negated && rule.is_match(path, false));
+        let modified_ok = modified.is_none_or(|modified| {
+            self.modified_after.is_none_or(|after| modified > after)
+                && self.modified_before.is_none_or(|before| modified < before)
+        });
+        included
+            && self.min_size.is_none_or(|min| len >= min)
+            && self.max_size.is_none_or(|max| len <= max)
+            && modified_ok
+    }
+
+    /// [`Filter::accepts`] for a listed resource at `path`.
+    pub fn accepts_resource(&self, path: &str, resource: &Resource) -> bool {
+        self.accepts(
+            path,
+            resource.is_collection,
+            resource.len,
+            resource.modified(),
+        )
+    }
+}
@@ -0,0 +1,233 @@
+//! The WebDAV `If` request header (RFC 4918, section 10.4).
+
+use std::fmt::{Display, Write};
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use reqwest::header::{HeaderName, HeaderValue};
+
+use crate::error::Error;
+
+pub const IF: HeaderName = HeaderName::from_static("if");
+
+/// The characters percent-encoded in the URLs of a header, besides
+/// non-ASCII ones.
+const URL: &AsciiSet = &CONTROLS.add(b' ').add(b'"').add(b'<').add(b'>');
+
+/// One condition of an [`IfList`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IfCondition {
+    /// A state token such as a lock token, e.g. `urn:uuid:...`.
+    StateToken { token: String, not: bool },
+    /// An entity tag, quoted as sent in an `ETag` header.
+    ETag { etag: String, not: bool },
+}
+
+/// A parenthesized list of conditions, all of which must hold.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IfList {
+    conditions: Vec<IfCondition>,
+}
+
+impl IfList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn token(self, token: &str) -> Self {
+        self.condition(IfCondition::StateToken {
+            token: token.to_owned(),
+            not: false,
+        })
+    }
+    pub fn not_token(self, token: &str) -> Self {
+        self.condition(IfCondition::StateToken {
+            token: token.to_owned(),
+            not: true,
+        })
+    }
+    pub fn etag(self, etag: &str) -> Self {
+        self.condition(IfCondition::ETag {
+            etag: etag.to_owned(),
+            not: false,
+        })
+    }
+    pub fn not_etag(self, etag: &str) -> Self {
+        self.condition(IfCondition::ETag {
+            etag: etag.to_owned(),
+            not: true,
+        })
+    }
+    pub fn condition(mut self, condition: IfCondition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+    /// Whether the list has no conditions, which is not a valid list.
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+}
+
+impl Display for IfList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_char('(')?;
+        for (i, condition) in self.conditions.iter().enumerate() {
+            if i > 0 {
+                f.write_char(' ')?;
+            }
+            let (not, value) = match condition {
+                IfCondition::StateToken { token, not } => (*not, coded_url(token)),
+                IfCondition::ETag { etag, not } => (*not, format!("[{}]", quote_etag(etag))),
+            };
+            if not {
+                f.write_str("Not ")?;
+            }
+            f.write_str(&value)?;
+        }
+        f.write_char(')')
+    }
+}
+
+/// The value of an `If` header: either lists that apply to the request URL,
+/// or lists tagged with the resource they apply to. The header holds if any
+/// list holds.
+///
+/// # Usage
+///
+/// ```
+/// use webdav_request::if_header::{IfHeader, IfList};
+///
+/// let header = IfHeader::new(IfList::new().token("urn:uuid:181d4fae").etag("\"a1\""))
+///     .or(IfList::new().not_etag("W/\"b2\""));
+/// assert_eq!(
+///     header.to_string(),
+///     r#"(<urn:uuid:181d4fae> ["a1"]) (Not [W/"b2"])"#
+/// );
+///
+/// let header = IfHeader::tagged("/locked/", IfList::new().token("urn:uuid:181d4fae"))?
+///     .tag("/other files/", IfList::new().etag("c3"))?;
+/// assert_eq!(
+///     header.to_string(),
+///     r#"</locked/> (<urn:uuid:181d4fae>) </other%20files/> (["c3"])"#
+/// );
+/// # Ok::<(), webdav_request::error::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IfHeader {
+    NoTag(Vec<IfList>),
+    Tagged(Vec<(String, Vec<IfList>)>),
+}
+
+impl IfHeader {
+    /// An untagged header, applying to the request URL.
+    pub fn new(list: IfList) -> Self {
+        Self::NoTag(vec![list])
+    }
+    /// A header whose list applies to `resource`, an absolute URL or path.
+    /// Fails with [`Error::InvalidHeader`] if `resource` is empty.
+    pub fn tagged(resource: &str, list: IfList) -> Result<Self, Error> {
+        Self::Tagged(Vec::new()).tag(resource, list)
+    }
+    /// Adds an alternative list. In a tagged header it applies to the last
+    /// resource; a tagged header without resources is rejected by
+    /// [`Self::to_header_value`].
+    pub fn or(mut self, list: IfList) -> Self {
+        match &mut self {
+            Self::NoTag(lists) => lists.push(list),
+            Self::Tagged(resources) => match resources.last_mut() {
+                Some((_, lists)) => lists.push(list),
+                None => resources.push((String::new(), vec![list])),
+            },
+        }
+        self
+    }
+    /// Adds a list for `resource`. Fails with [`Error::InvalidHeader`] if
+    /// the header is untagged, since the two forms cannot be mixed, or if
+    /// `resource` is empty.
+    pub fn tag(mut self, resource: &str, list: IfList) -> Result<Self, Error> {
+        let Self::Tagged(resources) = &mut self else {
+            return Err(invalid("cannot tag a list of an untagged header"));
+        };
+        if resource.is_empty() {
+            return Err(invalid("empty resource tag"));
+        }
+        match resources.iter_mut().find(|(tag, _)| tag == resource) {
+            Some((_, lists)) => lists.push(list),
+            None => resources.push((resource.to_owned(), vec![list])),
+        }
+        Ok(self)
+    }
+
+    /// The header value. Resources and state tokens are percent-encoded;
+    /// an entity tag with a control character, an empty resource, list or
+    /// header fail with [`Error::InvalidHeader`].
+    pub fn to_header_value(&self) -> Result<HeaderValue, Error> {
+        let empty = match self {
+            Self::NoTag(lists) => lists.is_empty() || lists.iter().any(IfList::is_empty),
+            Self::Tagged(resources) => {
+                resources.is_empty()
+                    || resources.iter().any(|(resource, lists)| {
+                        resource.is_empty()
+                            || lists.is_empty()
+                            || lists.iter().any(IfList::is_empty)
+                    })
+            }
+        };
+        if empty {
+            return Err(invalid("empty resource tag or list"));
+        }
+        HeaderValue::from_str(&self.to_string())
+            .map_err(|_| invalid(&format!("invalid entity tag in {:?}", self.to_string())))
+    }
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidHeader(IF, reason.to_owned())
+}
+
+impl Display for IfHeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut first = true;
+        let mut separate = |f: &mut std::fmt::Formatter<'_>| {
+            if !std::mem::take(&mut first) {
+                f.write_char(' ')?;
+            }
+            Ok(())
+        };
+        match self {
+            Self::NoTag(lists) => {
+                for list in lists {
+                    separate(f)?;
+                    list.fmt(f)?;
+                }
+            }
+            Self::Tagged(resources) => {
+                for (resource, lists) in resources {
+                    separate(f)?;
+                    write!(f, "<{}>", utf8_percent_encode(resource, URL))?;
+                    for list in lists {
+                        write!(f, " {list}")?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn coded_url(token: &str) -> String {
+    let token = token.trim();
+    let token = token
+        .strip_prefix('<')
+        .and_then(|token| token.strip_suffix('>'))
+        .unwrap_or(token);
+    format!("<{}>", utf8_percent_encode(token, URL))
+}
+
+/// Quotes a bare entity tag; quoted and weak (`W/"..."`) tags are kept.
+pub(crate) fn quote_etag(etag: &str) -> String {
+    let etag = etag.trim();
+    if etag.starts_with('"') || etag.starts_with("W/") {
+        etag.to_owned()
+    } else {
+        format!("\"{etag}\"")
+    }
+}
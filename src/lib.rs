@@ -1,8 +1,23 @@
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub mod client;
+pub mod depth;
 pub mod error;
+#[cfg(feature = "filter")]
+pub mod filter;
+pub mod if_header;
 pub mod method;
 pub mod reader;
 pub mod res;
+pub mod search;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "sync")]
+pub mod sync;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "transfer")]
+pub mod transfer;
 pub mod url;
 pub use client::WebDAVClient;
 pub use method::Method;
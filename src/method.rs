@@ -1,35 +1,74 @@
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 enum Inner {
     Get,
+    Head,
     Post,
     Put,
     Delete,
     Patch,
     Propfind,
+    Lock,
+    Unlock,
+    Mkcol,
+    Copy,
+    Move,
+    Search,
+    Report,
+    Acl,
     Custom(reqwest::Method),
 }
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Method(Inner);
 
 impl Method {
     pub const GET: Method = Method(Inner::Get);
+    pub const HEAD: Method = Method(Inner::Head);
     pub const POST: Method = Method(Inner::Post);
     pub const PUT: Method = Method(Inner::Put);
     pub const DELETE: Method = Method(Inner::Delete);
     pub const PATCH: Method = Method(Inner::Patch);
     pub const PROPFIND: Method = Method(Inner::Propfind);
+    pub const LOCK: Method = Method(Inner::Lock);
+    pub const UNLOCK: Method = Method(Inner::Unlock);
+    pub const MKCOL: Method = Method(Inner::Mkcol);
+    pub const COPY: Method = Method(Inner::Copy);
+    pub const MOVE: Method = Method(Inner::Move);
+    pub const SEARCH: Method = Method(Inner::Search);
+    pub const REPORT: Method = Method(Inner::Report);
+    pub const ACL: Method = Method(Inner::Acl);
     pub(crate) fn convert(self) -> reqwest::Method {
         use reqwest::Method as RMethod;
         match self.0 {
             Inner::Get => RMethod::GET,
+            Inner::Head => RMethod::HEAD,
             Inner::Post => RMethod::POST,
             Inner::Put => RMethod::PUT,
             Inner::Delete => RMethod::DELETE,
             Inner::Patch => RMethod::PATCH,
             Inner::Propfind => RMethod::from_bytes("PROPFIND".as_bytes()).unwrap(),
+            Inner::Lock => RMethod::from_bytes("LOCK".as_bytes()).unwrap(),
+            Inner::Unlock => RMethod::from_bytes("UNLOCK".as_bytes()).unwrap(),
+            Inner::Mkcol => RMethod::from_bytes("MKCOL".as_bytes()).unwrap(),
+            Inner::Copy => RMethod::from_bytes("COPY".as_bytes()).unwrap(),
+            Inner::Move => RMethod::from_bytes("MOVE".as_bytes()).unwrap(),
+            Inner::Search => RMethod::from_bytes("SEARCH".as_bytes()).unwrap(),
+            Inner::Report => RMethod::from_bytes("REPORT".as_bytes()).unwrap(),
+            Inner::Acl => RMethod::from_bytes("ACL".as_bytes()).unwrap(),
             Inner::Custom(method) => method,
         }
     }
+    /// Whether repeating the request has the same effect as sending it once.
+    ///
+    /// Besides the idempotent HTTP methods this includes `PROPFIND`,
+    /// `PROPPATCH`, `REPORT` and `SEARCH`.
+    pub fn is_idempotent(&self) -> bool {
+        let method = self.clone().convert();
+        method.is_idempotent()
+            || matches!(
+                method.as_str(),
+                "PROPFIND" | "PROPPATCH" | "REPORT" | "SEARCH"
+            )
+    }
     pub fn from_bytes(src: &[u8]) -> Result<Self, String> {
         match reqwest::Method::from_bytes(src) {
             Ok(m) => Ok(Self(Inner::Custom(m))),
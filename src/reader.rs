@@ -6,9 +6,10 @@ use std::{
     task::Poll,
 };
 type ResponseStream = Box<dyn Unpin + Future<Output = reqwest::Result<Response>>>;
+type PendingResponse = Pin<Box<dyn Future<Output = crate::error::Result<Response>>>>;
 pub struct LazyResponseReader {
     request: Option<RequestBuilder>,
-    buf: Option<ResponseStream>,
+    buf: Option<PendingResponse>,
     reader: Option<ResponseReader>,
 }
 
@@ -19,11 +20,7 @@ impl From<RequestBuilder> for LazyResponseReader {
 }
 impl From<ResponseStream> for LazyResponseReader {
     fn from(value: ResponseStream) -> Self {
-        Self {
-            request: None,
-            buf: Some(value),
-            reader: None,
-        }
+        Self::pending(Box::pin(async move { value.await.map_err(Into::into) }))
     }
 }
 impl LazyResponseReader {
@@ -34,6 +31,13 @@ impl LazyResponseReader {
             reader: None,
         }
     }
+    pub(crate) fn pending(response: PendingResponse) -> Self {
+        Self {
+            request: None,
+            buf: Some(response),
+            reader: None,
+        }
+    }
 }
 impl Unpin for LazyResponseReader {}
 impl tokio::io::AsyncRead for LazyResponseReader {
@@ -44,27 +48,23 @@ impl tokio::io::AsyncRead for LazyResponseReader {
     ) -> std::task::Poll<io::Result<()>> {
         let this = self.get_mut();
         if let Some(request) = this.request.take() {
-            this.buf = Some(Box::new(request.send()));
+            this.buf = Some(Box::pin(
+                async move { request.send().await.map_err(Into::into) },
+            ));
         }
         if let Some(send) = &mut this.buf {
-            match Future::poll(Pin::new(send), cx) {
+            match Future::poll(send.as_mut(), cx) {
                 Poll::Ready(data) => match data {
                     Ok(response) => {
                         if !response.status().is_success() {
-                            return Poll::Ready(Err(io::Error::new(
-                                io::ErrorKind::Other,
+                            return Poll::Ready(Err(io::Error::other(
                                 response.status().to_string(),
                             )));
                         }
                         this.buf = None;
                         this.reader = Some(ResponseReader::new(response))
                     }
-                    Err(e) => {
-                        return Poll::Ready(Err(io::Error::new(
-                            io::ErrorKind::Other,
-                            e.to_string(),
-                        )))
-                    }
+                    Err(e) => return Poll::Ready(Err(io::Error::other(e.to_string()))),
                 },
                 Poll::Pending => return Poll::Pending,
             }
@@ -131,10 +131,7 @@ impl tokio::io::AsyncRead for ResponseReader {
                         }
                     }
                     Poll::Ready(Err(err)) => {
-                        return Poll::Ready(Err(io::Error::new(
-                            std::io::ErrorKind::Other,
-                            err.to_string(),
-                        )));
+                        return Poll::Ready(Err(io::Error::other(err.to_string())));
                     }
                     Poll::Pending => return Poll::Pending,
                 }
@@ -0,0 +1,307 @@
+//! Access control lists (RFC 3744).
+
+use std::fmt::Write;
+
+use quick_xml::escape::escape;
+
+use super::element::{write_empty, Element, DAV};
+use super::privilege::PrivilegeName;
+use crate::error::Error;
+
+/// Asks for the access control properties of a resource.
+pub(crate) const ACL_PROPFIND: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+    <D:propfind xmlns:D="DAV:">
+        <D:prop>
+            <D:owner/>
+            <D:group/>
+            <D:acl/>
+            <D:supported-privilege-set/>
+            <D:principal-collection-set/>
+        </D:prop>
+    </D:propfind>
+"#;
+
+/// The principals an [`Ace`] applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AcePrincipal {
+    /// The principal at the href, e.g. `/principals/users/alice`.
+    Href(String),
+    /// Every user.
+    All,
+    /// Authenticated users.
+    Authenticated,
+    /// Unauthenticated users.
+    Unauthenticated,
+    /// The principal the property of the resource names, e.g. `owner` in
+    /// `DAV:`.
+    Property { namespace: String, name: String },
+    /// The resource itself, if it is a principal.
+    SelfPrincipal,
+}
+
+impl AcePrincipal {
+    fn parse(element: &Element) -> Option<Self> {
+        let child = element.children.first()?;
+        if child.namespace != DAV {
+            return None;
+        }
+        Some(match child.name.as_str() {
+            "href" => Self::Href(child.text.trim().to_owned()),
+            "all" => Self::All,
+            "authenticated" => Self::Authenticated,
+            "unauthenticated" => Self::Unauthenticated,
+            "self" => Self::SelfPrincipal,
+            "property" => {
+                let property = child.children.first()?;
+                Self::Property {
+                    namespace: property.namespace.clone(),
+                    name: property.name.clone(),
+                }
+            }
+            _ => return None,
+        })
+    }
+
+    fn write(&self, xml: &mut String) -> Result<(), Error> {
+        xml.push_str("<D:principal>");
+        match self {
+            Self::Href(href) => {
+                let _ = write!(xml, "<D:href>{}</D:href>", escape(href.as_str()));
+            }
+            Self::All => xml.push_str("<D:all/>"),
+            Self::Authenticated => xml.push_str("<D:authenticated/>"),
+            Self::Unauthenticated => xml.push_str("<D:unauthenticated/>"),
+            Self::Property { namespace, name } => {
+                xml.push_str("<D:property>");
+                write_empty(xml, "D", namespace, name)?;
+                xml.push_str("</D:property>");
+            }
+            Self::SelfPrincipal => xml.push_str("<D:self/>"),
+        }
+        xml.push_str("</D:principal>");
+        Ok(())
+    }
+}
+
+/// An access control entry, granting or denying privileges to principals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ace {
+    pub principal: AcePrincipal,
+    /// Whether the entry applies to every principal except `principal`.
+    pub invert: bool,
+    /// Whether `privileges` are denied rather than granted.
+    pub deny: bool,
+    pub privileges: Vec<PrivilegeName>,
+    /// Set by the server on entries that cannot be changed.
+    pub protected: bool,
+    /// The href of the resource the entry is inherited from.
+    pub inherited: Option<String>,
+}
+
+impl Ace {
+    /// Grants `privileges` to `principal`.
+    pub fn grant<I>(principal: AcePrincipal, privileges: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<PrivilegeName>,
+    {
+        Self {
+            principal,
+            invert: false,
+            deny: false,
+            privileges: privileges.into_iter().map(Into::into).collect(),
+            protected: false,
+            inherited: None,
+        }
+    }
+    /// Denies `privileges` to `principal`.
+    pub fn deny<I>(principal: AcePrincipal, privileges: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<PrivilegeName>,
+    {
+        Self {
+            deny: true,
+            ..Self::grant(principal, privileges)
+        }
+    }
+    /// Applies the entry to every principal except its principal.
+    pub fn invert(self) -> Self {
+        Self {
+            invert: true,
+            ..self
+        }
+    }
+
+    fn parse(element: &Element) -> Option<Self> {
+        let (principal, invert) = match element.child("invert") {
+            Some(invert) => (invert.child("principal")?, true),
+            None => (element.child("principal")?, false),
+        };
+        let (privileges, deny) = match element.child("deny") {
+            Some(deny) => (deny, true),
+            None => (element.child("grant")?, false),
+        };
+        Some(Self {
+            principal: AcePrincipal::parse(principal)?,
+            invert,
+            deny,
+            privileges: privilege_names(privileges),
+            protected: element.child("protected").is_some(),
+            inherited: element
+                .child("inherited")
+                .and_then(|inherited| inherited.hrefs().into_iter().next()),
+        })
+    }
+
+    fn write(&self, xml: &mut String) -> Result<(), Error> {
+        xml.push_str("<D:ace>");
+        if self.invert {
+            xml.push_str("<D:invert>");
+            self.principal.write(xml)?;
+            xml.push_str("</D:invert>");
+        } else {
+            self.principal.write(xml)?;
+        }
+        let kind = if self.deny { "deny" } else { "grant" };
+        let _ = write!(xml, "<D:{kind}>");
+        for privilege in &self.privileges {
+            xml.push_str("<D:privilege>");
+            write_empty(xml, "D", &privilege.namespace, &privilege.name)?;
+            xml.push_str("</D:privilege>");
+        }
+        let _ = write!(xml, "</D:{kind}>");
+        // Marks the entry as passed on unchanged.
+        if self.protected {
+            xml.push_str("<D:protected/>");
+        }
+        xml.push_str("</D:ace>");
+        Ok(())
+    }
+}
+
+/// The `DAV:acl` body of the `ACL` method. Inherited entries are left out,
+/// since only the resource's own entries can be set. Fails with
+/// [`Error::InvalidXmlName`] for a property or privilege name that is not
+/// a valid XML name.
+pub(crate) fn acl_xml(aces: &[Ace]) -> Result<String, Error> {
+    let mut xml = String::from(r#"<?xml version="1.0" encoding="utf-8" ?><D:acl xmlns:D="DAV:">"#);
+    for ace in aces.iter().filter(|ace| ace.inherited.is_none()) {
+        ace.write(&mut xml)?;
+    }
+    xml.push_str("</D:acl>");
+    Ok(xml)
+}
+
+/// A privilege the server supports, with the privileges it aggregates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SupportedPrivilege {
+    pub privilege: PrivilegeName,
+    /// Whether the privilege cannot be granted or denied on its own.
+    pub is_abstract: bool,
+    pub description: Option<String>,
+    pub aggregates: Vec<SupportedPrivilege>,
+}
+
+impl SupportedPrivilege {
+    fn parse(element: &Element) -> Option<Self> {
+        let privilege = element.child("privilege")?.children.first()?;
+        Some(Self {
+            privilege: PrivilegeName::new(&privilege.namespace, &privilege.name),
+            is_abstract: element.child("abstract").is_some(),
+            description: element
+                .child("description")
+                .map(|description| description.text.trim().to_owned()),
+            aggregates: element
+                .children("supported-privilege")
+                .filter_map(Self::parse)
+                .collect(),
+        })
+    }
+}
+
+/// The access control properties of a resource, returned by
+/// [`crate::WebDAVClient::access_control`]. Properties the server does not
+/// report are empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccessControl {
+    /// The href of the owning principal.
+    pub owner: Option<String>,
+    /// The href of the group principal.
+    pub group: Option<String>,
+    pub acl: Vec<Ace>,
+    pub supported_privileges: Vec<SupportedPrivilege>,
+    /// The hrefs of the collections holding principals.
+    pub principal_collections: Vec<String>,
+}
+
+impl AccessControl {
+    /// Parses the `DAV:multistatus` response to a `PROPFIND` of the access
+    /// control properties.
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// use webdav_request::res::acl::{AccessControl, AcePrincipal};
+    /// use webdav_request::res::privilege::PrivilegeName;
+    ///
+    /// let xml = r#"<?xml version="1.0" encoding="utf-8" ?>
+    ///     <D:multistatus xmlns:D="DAV:">
+    ///         <D:response>
+    ///             <D:href>/docs/</D:href>
+    ///             <D:propstat>
+    ///                 <D:prop>
+    ///                     <D:owner><D:href>/principals/alice</D:href></D:owner>
+    ///                     <D:acl>
+    ///                         <D:ace>
+    ///                             <D:principal><D:authenticated/></D:principal>
+    ///                             <D:grant><D:privilege><D:read/></D:privilege></D:grant>
+    ///                             <D:protected/>
+    ///                         </D:ace>
+    ///                     </D:acl>
+    ///                 </D:prop>
+    ///                 <D:status>HTTP/1.1 200 OK</D:status>
+    ///             </D:propstat>
+    ///         </D:response>
+    ///     </D:multistatus>"#;
+    ///
+    /// let access_control = AccessControl::parse(xml).unwrap();
+    /// assert_eq!(access_control.owner.as_deref(), Some("/principals/alice"));
+    /// let ace = &access_control.acl[0];
+    /// assert_eq!(ace.principal, AcePrincipal::Authenticated);
+    /// assert_eq!(ace.privileges, vec![PrivilegeName::dav("read")]);
+    /// assert!(ace.protected && !ace.deny);
+    /// ```
+    pub fn parse(xml: &str) -> Result<Self, quick_xml::DeError> {
+        let root = Element::parse(xml)?;
+        let mut access_control = Self::default();
+        let Some((_, props)) = root.found_props().into_iter().next() else {
+            return Ok(access_control);
+        };
+        for prop in props.into_iter().filter(|prop| prop.namespace == DAV) {
+            match prop.name.as_str() {
+                "owner" => access_control.owner = prop.hrefs().into_iter().next(),
+                "group" => access_control.group = prop.hrefs().into_iter().next(),
+                "acl" => access_control.acl = prop.children("ace").filter_map(Ace::parse).collect(),
+                "supported-privilege-set" => {
+                    access_control.supported_privileges = prop
+                        .children("supported-privilege")
+                        .filter_map(SupportedPrivilege::parse)
+                        .collect()
+                }
+                "principal-collection-set" => access_control.principal_collections = prop.hrefs(),
+                _ => (),
+            }
+        }
+        Ok(access_control)
+    }
+}
+
+/// The privileges of the `DAV:privilege` children of `element`.
+fn privilege_names(element: &Element) -> Vec<PrivilegeName> {
+    element
+        .children("privilege")
+        .filter_map(|privilege| privilege.children.first())
+        .map(|privilege| PrivilegeName::new(&privilege.namespace, &privilege.name))
+        .collect()
+}
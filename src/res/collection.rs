@@ -1,12 +1,22 @@
-use super::{multistatus::MultiStatus, privilege::Privilege};
+use super::{
+    multistatus::{DResponse, MultiStatus},
+    privilege::Privilege,
+};
 
 #[derive(Default, Debug)]
 pub struct Collection {
     pub href: String,
-    pub display_name: String,
+    pub display_name: Option<String>,
     pub children: Vec<Resource>,
 }
 
+impl Collection {
+    /// The `displayname` reported by the server, or the last segment of `href`.
+    pub fn name(&self) -> String {
+        display_name_or_href(self.display_name.as_deref(), &self.href)
+    }
+}
+
 impl From<MultiStatus> for Collection {
     fn from(value: MultiStatus) -> Self {
         if value.response.is_empty() {
@@ -23,30 +33,7 @@ impl From<MultiStatus> for Collection {
             #[cfg(not(feature = "decode_url"))]
             href: collection.href,
             display_name: collection.prop_stat.prop.display_name,
-            children: iter
-                .map(|node| {
-                    let href = node.href;
-                    let prop = node.prop_stat.prop;
-                    Resource {
-                        is_collection: prop.is_collection(),
-                        #[cfg(feature = "decode_url")]
-                        href: percent_encoding::percent_decode_str(&href)
-                            .decode_utf8()
-                            .map(|s| s.to_string())
-                            .unwrap_or(href),
-                        #[cfg(not(feature = "decode_url"))]
-                        href,
-                        display_name: prop.display_name,
-                        last_modified: prop.last_modified,
-                        len: prop.content_length,
-                        content_type: prop.content_type,
-                        privilege: prop
-                            .current_user_privilege_set
-                            .unwrap_or_default()
-                            .privilege(),
-                    }
-                })
-                .collect(),
+            children: iter.map(Resource::from).collect(),
         }
     }
 }
@@ -54,9 +41,79 @@ impl From<MultiStatus> for Collection {
 pub struct Resource {
     pub is_collection: bool,
     pub href: String,
-    pub display_name: String,
+    pub display_name: Option<String>,
     pub last_modified: String,
+    pub etag: Option<String>,
     pub len: u64,
     pub content_type: String,
     pub privilege: Privilege,
 }
+
+impl From<DResponse> for Resource {
+    fn from(value: DResponse) -> Self {
+        let href = value.href;
+        let prop = value.prop_stat.prop;
+        Resource {
+            is_collection: prop.is_collection(),
+            #[cfg(feature = "decode_url")]
+            href: percent_encoding::percent_decode_str(&href)
+                .decode_utf8()
+                .map(|s| s.to_string())
+                .unwrap_or(href),
+            #[cfg(not(feature = "decode_url"))]
+            href,
+            display_name: prop.display_name,
+            last_modified: prop.last_modified,
+            etag: prop.etag,
+            len: prop.content_length,
+            content_type: prop.content_type,
+            privilege: prop
+                .current_user_privilege_set
+                .unwrap_or_default()
+                .privilege(),
+        }
+    }
+}
+
+impl Resource {
+    /// The `displayname` reported by the server, or the last segment of `href`.
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// use webdav_request::res::Resource;
+    ///
+    /// let resource = Resource {
+    ///     href: "/dav/my%20notes/".to_owned(),
+    ///     ..Default::default()
+    /// };
+    /// # #[cfg(not(feature = "decode_url"))]
+    /// assert_eq!(resource.name(), "my notes");
+    /// ```
+    pub fn name(&self) -> String {
+        display_name_or_href(self.display_name.as_deref(), &self.href)
+    }
+    /// `last_modified` parsed as an HTTP date.
+    pub fn modified(&self) -> Option<std::time::SystemTime> {
+        httpdate::parse_http_date(&self.last_modified).ok()
+    }
+}
+
+fn display_name_or_href(display_name: Option<&str>, href: &str) -> String {
+    if let Some(name) = display_name.filter(|name| !name.is_empty()) {
+        return name.to_owned();
+    }
+    let segment = href
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or_default();
+    if cfg!(feature = "decode_url") {
+        // `href` has already been decoded while building the collection.
+        segment.to_owned()
+    } else {
+        percent_encoding::percent_decode_str(segment)
+            .decode_utf8_lossy()
+            .into_owned()
+    }
+}
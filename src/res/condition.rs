@@ -0,0 +1,130 @@
+use super::element::{Element, DAV};
+
+const SABRE: &str = "http://sabredav.org/ns";
+
+/// A precondition or postcondition reported in a `DAV:error` response body
+/// (RFC 4918, section 16).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DavCondition {
+    /// `lock-token-matches-request-uri`
+    LockTokenMatchesRequestUri,
+    /// `lock-token-submitted`, with the hrefs of the locked resources.
+    LockTokenSubmitted(Vec<String>),
+    /// `no-conflicting-lock`, with the roots of the conflicting locks.
+    NoConflictingLock(Vec<String>),
+    /// `no-external-entities`
+    NoExternalEntities,
+    /// `preserved-live-properties`
+    PreservedLiveProperties,
+    /// `propfind-finite-depth`
+    PropfindFiniteDepth,
+    /// `cannot-modify-protected-property`
+    CannotModifyProtectedProperty,
+    /// `valid-sync-token`: the sync token is unknown or expired.
+    ValidSyncToken,
+    /// `number-of-matches-within-limits`
+    NumberOfMatchesWithinLimits,
+    /// `need-privileges`: the current user lacks a privilege.
+    NeedPrivileges,
+    /// `no-ace-conflict`
+    NoAceConflict,
+    /// `no-protected-ace-conflict`: a protected ACE was changed or removed.
+    NoProtectedAceConflict,
+    /// `no-inherited-ace-conflict`
+    NoInheritedAceConflict,
+    /// `not-supported-privilege`
+    NotSupportedPrivilege,
+    /// `recognized-principal`
+    RecognizedPrincipal,
+    /// The `s:exception`/`s:message` pair sent by SabreDAV based servers
+    /// such as Nextcloud and ownCloud.
+    ServerException {
+        exception: Option<String>,
+        message: Option<String>,
+    },
+    /// Any other element, identified by its namespace and local name.
+    Other { namespace: String, name: String },
+}
+
+impl DavCondition {
+    fn new(namespace: &str, name: &str) -> Self {
+        if namespace == DAV {
+            match name {
+                "lock-token-matches-request-uri" => return Self::LockTokenMatchesRequestUri,
+                "lock-token-submitted" => return Self::LockTokenSubmitted(Vec::new()),
+                "no-conflicting-lock" => return Self::NoConflictingLock(Vec::new()),
+                "no-external-entities" => return Self::NoExternalEntities,
+                "preserved-live-properties" => return Self::PreservedLiveProperties,
+                "propfind-finite-depth" => return Self::PropfindFiniteDepth,
+                "cannot-modify-protected-property" => return Self::CannotModifyProtectedProperty,
+                "valid-sync-token" => return Self::ValidSyncToken,
+                "number-of-matches-within-limits" => return Self::NumberOfMatchesWithinLimits,
+                "need-privileges" => return Self::NeedPrivileges,
+                "no-ace-conflict" => return Self::NoAceConflict,
+                "no-protected-ace-conflict" => return Self::NoProtectedAceConflict,
+                "no-inherited-ace-conflict" => return Self::NoInheritedAceConflict,
+                "not-supported-privilege" => return Self::NotSupportedPrivilege,
+                "recognized-principal" => return Self::RecognizedPrincipal,
+                _ => (),
+            }
+        }
+        Self::Other {
+            namespace: namespace.to_owned(),
+            name: name.to_owned(),
+        }
+    }
+
+    /// Parses the conditions of a `DAV:error` body.
+    ///
+    /// Returns an empty list if `xml` is not a `DAV:error` element.
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// use webdav_request::res::DavCondition;
+    ///
+    /// let xml = r#"<?xml version="1.0" encoding="utf-8" ?>
+    ///     <D:error xmlns:D="DAV:">
+    ///         <D:lock-token-submitted>
+    ///             <D:href>/locked/</D:href>
+    ///         </D:lock-token-submitted>
+    ///     </D:error>"#;
+    ///
+    /// assert_eq!(
+    ///     DavCondition::parse_all(xml),
+    ///     vec![DavCondition::LockTokenSubmitted(vec!["/locked/".to_owned()])]
+    /// );
+    /// ```
+    pub fn parse_all(xml: &str) -> Vec<DavCondition> {
+        let Ok(error) = Element::parse(xml) else {
+            return Vec::new();
+        };
+        if !error.is("error") {
+            return Vec::new();
+        }
+        let mut conditions = Vec::new();
+        let mut exception = None;
+        let mut message = None;
+        for child in &error.children {
+            if child.namespace == SABRE {
+                let text = Some(child.text.clone()).filter(|text| !text.is_empty());
+                match child.name.as_str() {
+                    "exception" => exception = text,
+                    "message" => message = text,
+                    _ => (),
+                }
+                continue;
+            }
+            conditions.push(match DavCondition::new(&child.namespace, &child.name) {
+                Self::LockTokenSubmitted(_) => Self::LockTokenSubmitted(child.hrefs()),
+                Self::NoConflictingLock(_) => Self::NoConflictingLock(child.hrefs()),
+                condition => condition,
+            });
+        }
+        if exception.is_some() || message.is_some() {
+            conditions.push(DavCondition::ServerException { exception, message });
+        }
+        conditions
+    }
+}
@@ -0,0 +1,154 @@
+use std::fmt::Write;
+
+use quick_xml::{
+    escape::escape,
+    events::Event,
+    name::{Namespace, ResolveResult},
+    NsReader,
+};
+
+pub(crate) const DAV: &str = "DAV:";
+
+/// An XML element with its namespace resolved, for properties whose
+/// elements are identified by namespace, such as privileges.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Element {
+    pub(crate) namespace: String,
+    pub(crate) name: String,
+    pub(crate) text: String,
+    pub(crate) children: Vec<Element>,
+}
+
+impl Element {
+    /// Parses the root element of `xml`.
+    pub(crate) fn parse(xml: &str) -> Result<Self, quick_xml::DeError> {
+        let mut reader = NsReader::from_str(xml);
+        reader.config_mut().trim_text(true);
+        let mut stack: Vec<Element> = Vec::new();
+        loop {
+            let (namespace, event) = reader.read_resolved_event()?;
+            let namespace = match namespace {
+                ResolveResult::Bound(Namespace(namespace)) => {
+                    String::from_utf8_lossy(namespace).into_owned()
+                }
+                _ => String::new(),
+            };
+            let element = |name: &[u8]| Element {
+                namespace: namespace.clone(),
+                name: String::from_utf8_lossy(name).into_owned(),
+                ..Default::default()
+            };
+            let closed = match event {
+                Event::Start(start) => {
+                    stack.push(element(start.local_name().as_ref()));
+                    continue;
+                }
+                Event::Empty(start) => element(start.local_name().as_ref()),
+                Event::Text(text) => {
+                    if let Some(parent) = stack.last_mut() {
+                        parent.text.push_str(&text.unescape()?);
+                    }
+                    continue;
+                }
+                Event::CData(data) => {
+                    if let Some(parent) = stack.last_mut() {
+                        parent.text.push_str(&String::from_utf8_lossy(&data));
+                    }
+                    continue;
+                }
+                Event::End(_) => stack.pop().unwrap_or_default(),
+                Event::Eof => return Err(quick_xml::DeError::Custom("no root element".to_owned())),
+                _ => continue,
+            };
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(closed),
+                None => return Ok(closed),
+            }
+        }
+    }
+
+    /// Whether this is the `DAV:` element `name`.
+    pub(crate) fn is(&self, name: &str) -> bool {
+        self.namespace == DAV && self.name == name
+    }
+    /// The first `DAV:` child element `name`.
+    pub(crate) fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.is(name))
+    }
+    /// The `DAV:` child elements `name`.
+    pub(crate) fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.is(name))
+    }
+    /// The text of the `DAV:href` children.
+    pub(crate) fn hrefs(&self) -> Vec<String> {
+        self.children("href")
+            .map(|href| href.text.trim().to_owned())
+            .collect()
+    }
+
+    /// The text and child elements as XML, each element declaring its
+    /// namespace. Text is written before the elements, since their order
+    /// is not kept.
+    #[cfg(feature = "server")]
+    pub(crate) fn content(&self) -> String {
+        let mut xml = escape(self.text.as_str()).into_owned();
+        for child in &self.children {
+            let _ = write!(
+                xml,
+                r#"<{name} xmlns="{}">{}</{name}>"#,
+                escape(child.namespace.as_str()),
+                child.content(),
+                name = child.name,
+            );
+        }
+        xml
+    }
+
+    /// The properties of the successful `propstat`s of each response of a
+    /// `DAV:multistatus`, with the response's href.
+    pub(crate) fn found_props(&self) -> Vec<(String, Vec<&Element>)> {
+        let responses = self.children("response").map(|response| {
+            let href = response.hrefs().into_iter().next().unwrap_or_default();
+            let props = response
+                .children("propstat")
+                .filter(|prop_stat| {
+                    prop_stat.child("status").is_none_or(|status| {
+                        let code = status.text.split_whitespace().nth(1);
+                        code.is_some_and(|code| code.starts_with('2'))
+                    })
+                })
+                .filter_map(|prop_stat| prop_stat.child("prop"))
+                .flat_map(|prop| &prop.children)
+                .collect();
+            (href, props)
+        });
+        responses.collect()
+    }
+}
+
+/// Whether `name` is an XML NCName, and so can be written as the local
+/// name of an element.
+fn is_nc_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c == '_' || c.is_alphabetic())
+        && chars.all(|c| matches!(c, '-' | '.' | '_' | '\u{b7}') || c.is_alphanumeric())
+}
+
+/// Writes the empty element `name` in `namespace`, using `dav` as the
+/// prefix of the `DAV:` namespace.
+pub(crate) fn write_empty(
+    xml: &mut String,
+    dav: &str,
+    namespace: &str,
+    name: &str,
+) -> Result<(), crate::error::Error> {
+    if !is_nc_name(name) {
+        return Err(crate::error::Error::InvalidXmlName(name.to_owned()));
+    }
+    if namespace == DAV {
+        let _ = write!(xml, "<{dav}:{name}/>");
+    } else {
+        let _ = write!(xml, r#"<{name} xmlns="{}"/>"#, escape(namespace));
+    }
+    Ok(())
+}
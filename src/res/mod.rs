@@ -1,5 +1,11 @@
+pub mod acl;
 mod collection;
+mod condition;
+pub(crate) mod element;
+pub mod principal;
 pub mod privilege;
+pub mod quota;
 pub use collection::*;
+pub use condition::*;
 mod multistatus;
 pub use multistatus::*;
@@ -1,24 +1,123 @@
 use super::privilege::CurrentUserPrivilegeSet;
-use serde::Deserialize;
+use super::quota::QuotaInfo;
+use serde::{ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
 
 #[derive(Deserialize, Debug)]
 pub struct MultiStatus {
     #[serde(default)]
     pub response: Vec<DResponse>,
+    /// The token of the `sync-collection` REPORT.
+    #[serde(default, rename = "sync-token")]
+    pub sync_token: Option<String>,
 }
 
 impl MultiStatus {
     pub fn parse(s: &str) -> Result<Self, quick_xml::DeError> {
         quick_xml::de::from_str(s)
     }
+    /// Writes the `DAV:multistatus` document, e.g. for a `207` response.
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// use webdav_request::res::{DResponse, MultiStatus, Prop, PropStat};
+    ///
+    /// let multi_status = MultiStatus {
+    ///     response: vec![DResponse {
+    ///         href: "/file.txt".to_owned(),
+    ///         prop_stat: PropStat {
+    ///             prop: Prop {
+    ///                 content_length: 5,
+    ///                 ..Default::default()
+    ///             },
+    ///             status: "HTTP/1.1 200 OK".to_owned(),
+    ///         },
+    ///         status: None,
+    ///     }],
+    ///     sync_token: None,
+    /// };
+    /// let xml = multi_status.to_xml().unwrap();
+    /// let parsed = MultiStatus::parse(&xml).unwrap();
+    /// assert_eq!(parsed.response[0].href, "/file.txt");
+    /// assert_eq!(parsed.response[0].prop_stat.prop.content_length, 5);
+    /// ```
+    pub fn to_xml(&self) -> Result<String, quick_xml::SeError> {
+        let xml = quick_xml::se::to_string(self)?;
+        Ok(format!(r#"<?xml version="1.0" encoding="utf-8"?>{xml}"#))
+    }
+}
+
+impl Serialize for MultiStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut multi_status = serializer.serialize_struct("d:multistatus", 3)?;
+        multi_status.serialize_field("@xmlns:d", "DAV:")?;
+        multi_status.serialize_field("d:response", &self.response)?;
+        match &self.sync_token {
+            Some(sync_token) => multi_status.serialize_field("d:sync-token", sync_token)?,
+            None => multi_status.skip_field("d:sync-token")?,
+        }
+        multi_status.end()
+    }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct DResponse {
-    #[serde(rename = "href")]
+    #[serde(rename(serialize = "d:href", deserialize = "href"))]
     pub href: String,
-    #[serde(rename = "propstat")]
+    /// The properties found; servers report missing ones in a separate
+    /// `propstat`, which is skipped.
+    #[serde(
+        default,
+        rename(serialize = "d:propstat", deserialize = "propstat"),
+        deserialize_with = "found"
+    )]
     pub prop_stat: PropStat,
+    /// The status of a response without properties, e.g. `HTTP/1.1 404 Not
+    /// Found` for a member removed since a sync token.
+    #[serde(
+        default,
+        rename(serialize = "d:status", deserialize = "status"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub status: Option<String>,
+}
+
+/// Picks the `2xx` `propstat` of a response, or else the first.
+fn found<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PropStat, D::Error> {
+    let mut prop_stats = Vec::<PropStat>::deserialize(deserializer)?;
+    let found = prop_stats.iter().position(|prop_stat| {
+        let code = prop_stat.status.split_whitespace().nth(1);
+        code.is_some_and(|code| code.starts_with('2'))
+    });
+    match found {
+        Some(i) => Ok(prop_stats.swap_remove(i)),
+        None => Ok(prop_stats.into_iter().next().unwrap_or_default()),
+    }
+}
+
+/// Reads an empty element, as in a `404` `propstat`, as `0`.
+fn lenient_u64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    match value.trim() {
+        "" => Ok(0),
+        value => value.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+/// Reads an empty element as `None`. Some servers write large values as
+/// floating point numbers.
+fn lenient_i64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i64>, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    match value.trim() {
+        "" => Ok(None),
+        value => match value.parse() {
+            Ok(value) => Ok(Some(value)),
+            Err(err) => match value.parse::<f64>() {
+                Ok(value) => Ok(Some(value as i64)),
+                Err(_) => Err(serde::de::Error::custom(err)),
+            },
+        },
+    }
 }
 
 impl DResponse {
@@ -27,21 +126,25 @@ impl DResponse {
     }
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
 pub struct PropStat {
+    #[serde(rename(serialize = "d:prop"))]
     pub prop: Prop,
+    #[serde(rename(serialize = "d:status"))]
     pub status: String,
 }
 
 #[derive(Debug, Deserialize, Default, Clone)]
 pub struct Prop {
-    #[serde(rename = "displayname")]
-    pub display_name: String,
+    #[serde(default, rename = "displayname")]
+    pub display_name: Option<String>,
     #[serde(default, rename = "getcontenttype")]
     pub content_type: String,
     #[serde(default, rename = "getlastmodified")]
     pub last_modified: String,
-    #[serde(rename = "getcontentlength", default)]
+    #[serde(default, rename = "getetag")]
+    pub etag: Option<String>,
+    #[serde(rename = "getcontentlength", default, deserialize_with = "lenient_u64")]
     pub content_length: u64,
     #[serde(alias = "iscollection", default)]
     pub collection: bool,
@@ -49,6 +152,60 @@ pub struct Prop {
     pub resource_type: Option<ResourceType>,
     #[serde(rename = "current-user-privilege-set", default)]
     pub current_user_privilege_set: Option<CurrentUserPrivilegeSet>,
+    /// Negative values are sentinels, see [`QuotaInfo`].
+    #[serde(
+        rename = "quota-available-bytes",
+        default,
+        deserialize_with = "lenient_i64"
+    )]
+    pub quota_available_bytes: Option<i64>,
+    #[serde(rename = "quota-used-bytes", default, deserialize_with = "lenient_i64")]
+    pub quota_used_bytes: Option<i64>,
+}
+
+/// Writes the properties that are set; `getcontentlength` only for files.
+impl Serialize for Prop {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut prop = serializer.serialize_struct("d:prop", 8)?;
+        match &self.display_name {
+            Some(display_name) => prop.serialize_field("d:displayname", display_name)?,
+            None => prop.skip_field("d:displayname")?,
+        }
+        let resource_type = match self.is_collection() {
+            true => ResourceType::collection(),
+            false => ResourceType::default(),
+        };
+        prop.serialize_field("d:resourcetype", &resource_type)?;
+        if self.is_collection() {
+            prop.skip_field("d:getcontentlength")?;
+        } else {
+            prop.serialize_field("d:getcontentlength", &self.content_length)?;
+        }
+        for (name, value) in [
+            ("d:getcontenttype", &self.content_type),
+            ("d:getlastmodified", &self.last_modified),
+        ] {
+            if value.is_empty() {
+                prop.skip_field(name)?;
+            } else {
+                prop.serialize_field(name, value)?;
+            }
+        }
+        match &self.etag {
+            Some(etag) => prop.serialize_field("d:getetag", etag)?,
+            None => prop.skip_field("d:getetag")?,
+        }
+        for (name, value) in [
+            ("d:quota-available-bytes", self.quota_available_bytes),
+            ("d:quota-used-bytes", self.quota_used_bytes),
+        ] {
+            match value {
+                Some(value) => prop.serialize_field(name, &value)?,
+                None => prop.skip_field(name)?,
+            }
+        }
+        prop.end()
+    }
 }
 
 impl Prop {
@@ -59,10 +216,25 @@ impl Prop {
                 .as_ref()
                 .is_some_and(|ty| ty.collection.is_some())
     }
+    pub fn quota(&self) -> QuotaInfo {
+        QuotaInfo::from_reported(self.quota_available_bytes, self.quota_used_bytes)
+    }
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
 pub struct ResourceType {
-    #[serde(default)]
+    #[serde(
+        default,
+        rename(serialize = "d:collection"),
+        skip_serializing_if = "Option::is_none"
+    )]
     collection: Option<String>,
 }
+
+impl ResourceType {
+    pub fn collection() -> Self {
+        Self {
+            collection: Some(String::new()),
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! Principals, the users and groups of a server (RFC 3744, section 2).
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::client::Report;
+
+const SABRE_NS: &str = "http://sabredav.org/ns";
+const CALDAV_NS: &str = "urn:ietf:params:xml:ns:caldav";
+
+/// Asks for the principal of the current user.
+pub(crate) const CURRENT_USER_PRINCIPAL: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+    <D:propfind xmlns:D="DAV:">
+        <D:prop>
+            <D:current-user-principal/>
+        </D:prop>
+    </D:propfind>
+"#;
+
+/// Asks for the properties [`Principal`] is built from.
+pub(crate) const PRINCIPAL_PROPFIND: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+    <D:propfind xmlns:D="DAV:" xmlns:S="http://sabredav.org/ns" xmlns:C="urn:ietf:params:xml:ns:caldav">
+        <D:prop>
+            <D:displayname/>
+            <S:email-address/>
+            <C:calendar-user-address-set/>
+            <D:group-membership/>
+        </D:prop>
+    </D:propfind>
+"#;
+
+/// A user or group of the server.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Principal {
+    /// The principal URL, e.g. `/remote.php/dav/principals/users/alice/`.
+    pub href: String,
+    pub display_name: Option<String>,
+    /// The `email-address` of SabreDAV based servers, or else the first
+    /// `mailto:` address of the CalDAV `calendar-user-address-set`.
+    pub email: Option<String>,
+    /// The hrefs of the groups the principal is a member of.
+    pub group_membership: Vec<String>,
+}
+
+/// The response to a `PROPFIND` or `REPORT` of principal properties.
+#[derive(Debug, Deserialize)]
+pub(crate) struct PrincipalMultiStatus {
+    #[serde(default)]
+    response: Vec<PrincipalResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PrincipalResponse {
+    href: String,
+    #[serde(default, rename = "propstat")]
+    prop_stats: Vec<PrincipalPropStat>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PrincipalPropStat {
+    #[serde(default)]
+    prop: PrincipalProp,
+    #[serde(default)]
+    status: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PrincipalProp {
+    #[serde(default, rename = "displayname")]
+    display_name: Option<String>,
+    #[serde(default, rename = "email-address")]
+    email_address: Option<String>,
+    #[serde(default, rename = "calendar-user-address-set")]
+    calendar_user_address_set: Option<Hrefs>,
+    #[serde(default, rename = "group-membership")]
+    group_membership: Option<Hrefs>,
+    #[serde(default, rename = "current-user-principal")]
+    current_user_principal: Option<Hrefs>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Hrefs {
+    #[serde(default)]
+    href: Vec<String>,
+}
+
+impl PrincipalResponse {
+    /// The properties of the successful `propstat`s.
+    fn found(self) -> impl Iterator<Item = PrincipalProp> {
+        self.prop_stats
+            .into_iter()
+            .filter(|prop_stat| {
+                let code = prop_stat.status.split_whitespace().nth(1);
+                code.is_some_and(|code| code.starts_with('2'))
+            })
+            .map(|prop_stat| prop_stat.prop)
+    }
+}
+
+impl From<PrincipalResponse> for Principal {
+    fn from(value: PrincipalResponse) -> Self {
+        let mut principal = Principal {
+            href: value.href.trim().to_owned(),
+            ..Default::default()
+        };
+        let mut addresses = Vec::new();
+        for prop in value.found() {
+            let non_empty = |value: Option<String>| value.filter(|value| !value.is_empty());
+            principal.display_name = non_empty(prop.display_name).or(principal.display_name);
+            principal.email = non_empty(prop.email_address).or(principal.email);
+            addresses.extend(prop.calendar_user_address_set.unwrap_or_default().href);
+            if let Some(groups) = prop.group_membership {
+                principal.group_membership.extend(groups.href);
+            }
+        }
+        if principal.email.is_none() {
+            principal.email = addresses.into_iter().find_map(|address| {
+                let address = address.trim();
+                let email = address
+                    .get(..7)
+                    .filter(|scheme| scheme.eq_ignore_ascii_case("mailto:"))
+                    .map(|_| &address[7..]);
+                email.map(str::to_owned)
+            });
+        }
+        principal
+    }
+}
+
+impl PrincipalMultiStatus {
+    pub(crate) fn parse(xml: &str) -> Result<Self, quick_xml::DeError> {
+        quick_xml::de::from_str(xml)
+    }
+    pub(crate) fn into_principals(self) -> Vec<Principal> {
+        self.response.into_iter().map(Principal::from).collect()
+    }
+    /// The href of `DAV:current-user-principal`; `None` if the user is
+    /// unauthenticated.
+    pub(crate) fn current_user_principal(self) -> Option<String> {
+        let response = self.response.into_iter().next()?;
+        response
+            .found()
+            .find_map(|prop| prop.current_user_principal?.href.into_iter().next())
+            .map(|href| href.trim().to_owned())
+    }
+}
+
+/// A `principal-property-search` REPORT (RFC 3744, section 9.4), sent by
+/// [`crate::WebDAVClient::principal_property_search`].
+///
+/// # Usage
+///
+/// ```no_run
+/// use webdav_request::res::principal::PrincipalSearch;
+///
+/// # async fn run() -> Result<(), webdav_request::error::Error> {
+/// let client = webdav_request::WebDAVClient::new("user", "password")?;
+/// let search = PrincipalSearch::new()
+///     .display_name("doe")
+///     .email("doe@")
+///     .apply_to_principal_collection_set();
+/// let url = "https://cloud.example.com/remote.php/dav/principals/";
+/// for principal in client.principal_property_search(url, &search).await? {
+///     println!("{} {:?}", principal.href, principal.display_name);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PrincipalSearch {
+    /// `(namespace, name, match)` of each searched property.
+    properties: Vec<(String, String, String)>,
+    all_of: bool,
+    apply_to_principal_collection_set: bool,
+}
+
+impl PrincipalSearch {
+    /// A search for principals matching any of the properties.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Matches principals whose property `name` in `namespace` contains
+    /// `value`, case-insensitively.
+    pub fn property(mut self, namespace: &str, name: &str, value: &str) -> Self {
+        self.properties
+            .push((namespace.to_owned(), name.to_owned(), value.to_owned()));
+        self
+    }
+    /// Matches the `DAV:displayname`.
+    pub fn display_name(self, value: &str) -> Self {
+        self.property("DAV:", "displayname", value)
+    }
+    /// Matches the `email-address` of SabreDAV based servers.
+    pub fn email(self, value: &str) -> Self {
+        self.property(SABRE_NS, "email-address", value)
+    }
+    /// Only matches principals matching all the properties.
+    pub fn all_of(self) -> Self {
+        Self {
+            all_of: true,
+            ..self
+        }
+    }
+    /// Searches the principal collections of the request URL rather than
+    /// the collection itself.
+    pub fn apply_to_principal_collection_set(self) -> Self {
+        Self {
+            apply_to_principal_collection_set: true,
+            ..self
+        }
+    }
+
+    pub(crate) fn report(&self) -> Report<PrincipalPropertySearch> {
+        let mut report = Report::new(PrincipalPropertySearch {
+            test: if self.all_of { "allof" } else { "anyof" },
+            property_search: Vec::new(),
+            prop: PrincipalPropNames::default(),
+            apply_to_principal_collection_set: self.apply_to_principal_collection_set.then_some(()),
+        })
+        .namespace("s", SABRE_NS)
+        .namespace("c", CALDAV_NS);
+        for (i, (namespace, name, value)) in self.properties.iter().enumerate() {
+            let element = match namespace.as_str() {
+                "DAV:" => format!("d:{name}"),
+                SABRE_NS => format!("s:{name}"),
+                CALDAV_NS => format!("c:{name}"),
+                _ => {
+                    report = report.namespace(&format!("p{i}"), namespace);
+                    format!("p{i}:{name}")
+                }
+            };
+            report.body.property_search.push(PropertySearch {
+                prop: BTreeMap::from([(element, ())]),
+                matches: value.clone(),
+            });
+        }
+        report
+    }
+}
+
+/// The body of the `principal-property-search` REPORT.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename = "d:principal-property-search")]
+pub(crate) struct PrincipalPropertySearch {
+    #[serde(rename = "@test")]
+    test: &'static str,
+    #[serde(rename = "d:property-search")]
+    property_search: Vec<PropertySearch>,
+    #[serde(rename = "d:prop")]
+    prop: PrincipalPropNames,
+    #[serde(
+        rename = "d:apply-to-principal-collection-set",
+        skip_serializing_if = "Option::is_none"
+    )]
+    apply_to_principal_collection_set: Option<()>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PropertySearch {
+    #[serde(rename = "d:prop")]
+    prop: BTreeMap<String, ()>,
+    #[serde(rename = "d:match")]
+    matches: String,
+}
+
+/// The properties [`Principal`] is built from.
+#[derive(Debug, Clone, Default, Serialize)]
+struct PrincipalPropNames {
+    #[serde(rename = "d:displayname")]
+    display_name: (),
+    #[serde(rename = "s:email-address")]
+    email_address: (),
+    #[serde(rename = "c:calendar-user-address-set")]
+    calendar_user_address_set: (),
+    #[serde(rename = "d:group-membership")]
+    group_membership: (),
+}
@@ -1,105 +1,332 @@
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 use serde_json::Value;
 
-#[derive(Clone, Default, Debug, Deserialize)]
+use super::acl::SupportedPrivilege;
+use super::element::DAV;
+
+/// A privilege element, identified by its namespace and local name, e.g.
+/// `read` in `DAV:`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PrivilegeName {
+    pub namespace: String,
+    pub name: String,
+}
+
+impl PrivilegeName {
+    pub fn new(namespace: &str, name: &str) -> Self {
+        Self {
+            namespace: namespace.to_owned(),
+            name: name.to_owned(),
+        }
+    }
+    /// The privilege `name` in the `DAV:` namespace, e.g. `read-acl`.
+    pub fn dav(name: &str) -> Self {
+        Self::new(DAV, name)
+    }
+}
+
+impl From<PrivilegeType> for PrivilegeName {
+    fn from(value: PrivilegeType) -> Self {
+        value.name()
+    }
+}
+
+#[derive(Clone, Default, Debug)]
 pub struct CurrentUserPrivilegeSet {
-    #[serde(rename = "privilege", default)]
     privileges: Vec<PrivilegeType>,
 }
 
+/// Reads each privilege in the namespace declared on its element, or else
+/// in `DAV:`: serde sees local names only, so the namespaces bound on
+/// ancestors are unknown. `DAV:privilege` elements without a privilege are
+/// skipped.
+impl<'de> Deserialize<'de> for CurrentUserPrivilegeSet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Set {
+            #[serde(rename = "privilege", default)]
+            privileges: Vec<Value>,
+        }
+        let set = Set::deserialize(deserializer)?;
+        let privileges = set.privileges.iter().filter_map(|privilege| {
+            let Value::Object(privilege) = privilege else {
+                return None;
+            };
+            let (name, element) = privilege
+                .iter()
+                .find(|(key, _)| !key.starts_with(['@', '$']))?;
+            let namespace = declared_namespace(element).unwrap_or(DAV);
+            Some(PrivilegeType::from(PrivilegeName::new(namespace, name)))
+        });
+        Ok(Self {
+            privileges: privileges.collect(),
+        })
+    }
+}
+
+/// The namespace `element` declares for itself: its default namespace, or
+/// its only prefixed one.
+fn declared_namespace(element: &Value) -> Option<&str> {
+    let Value::Object(attributes) = element else {
+        return None;
+    };
+    if let Some(Value::String(namespace)) = attributes.get("@xmlns") {
+        return Some(namespace);
+    }
+    let mut bindings = attributes
+        .iter()
+        .filter(|(key, _)| key.starts_with("@xmlns:"));
+    match (bindings.next(), bindings.next()) {
+        (Some((_, Value::String(namespace))), None) => Some(namespace),
+        _ => None,
+    }
+}
+
 impl CurrentUserPrivilegeSet {
+    /// The privileges as reported, without the ones they aggregate.
+    pub fn privileges(&self) -> &[PrivilegeType] {
+        &self.privileges
+    }
     pub fn privilege(self) -> Privilege {
         Privilege::from(self.privileges)
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// A privilege of RFC 3744, or another one by namespace and name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum PrivilegeType {
     Read,
     Write,
+    WriteProperties,
+    WriteContent,
+    Unlock,
     ReadAcl,
+    ReadCurrentUserPrivilegeSet,
     WriteAcl,
+    Bind,
+    Unbind,
     All,
-    None,
+    Other(PrivilegeName),
 }
-impl<'de> Deserialize<'de> for PrivilegeType {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let value = Value::deserialize(deserializer)?;
-        Ok(match value {
-            Value::Object(map) => {
-                if map.contains_key("read") {
-                    PrivilegeType::Read
-                } else if map.contains_key("write") {
-                    PrivilegeType::Write
-                } else if map.contains_key("read_acl") {
-                    PrivilegeType::ReadAcl
-                } else if map.contains_key("write_acl") {
-                    PrivilegeType::WriteAcl
-                } else if map.contains_key("all") {
-                    PrivilegeType::All
-                } else {
-                    PrivilegeType::None
-                }
+
+impl PrivilegeType {
+    /// The privileges of the `DAV:` namespace.
+    const DAV: [PrivilegeType; 11] = [
+        Self::Read,
+        Self::Write,
+        Self::WriteProperties,
+        Self::WriteContent,
+        Self::Unlock,
+        Self::ReadAcl,
+        Self::ReadCurrentUserPrivilegeSet,
+        Self::WriteAcl,
+        Self::Bind,
+        Self::Unbind,
+        Self::All,
+    ];
+
+    pub fn name(&self) -> PrivilegeName {
+        let name = match self {
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::WriteProperties => "write-properties",
+            Self::WriteContent => "write-content",
+            Self::Unlock => "unlock",
+            Self::ReadAcl => "read-acl",
+            Self::ReadCurrentUserPrivilegeSet => "read-current-user-privilege-set",
+            Self::WriteAcl => "write-acl",
+            Self::Bind => "bind",
+            Self::Unbind => "unbind",
+            Self::All => "all",
+            Self::Other(name) => return name.clone(),
+        };
+        PrivilegeName::dav(name)
+    }
+
+    /// The privileges RFC 3744 requires this one to contain: `all` contains
+    /// every privilege, `write` contains `write-properties`,
+    /// `write-content`, `bind` and `unbind`.
+    pub fn aggregates(&self) -> &'static [PrivilegeType] {
+        match self {
+            Self::All => &Self::DAV,
+            Self::Write => &[
+                Self::WriteProperties,
+                Self::WriteContent,
+                Self::Bind,
+                Self::Unbind,
+            ],
+            _ => &[],
+        }
+    }
+}
+
+impl From<PrivilegeName> for PrivilegeType {
+    fn from(value: PrivilegeName) -> Self {
+        if value.namespace == DAV {
+            if let Some(privilege) = Self::DAV
+                .into_iter()
+                .find(|privilege| privilege.name().name == value.name)
+            {
+                return privilege;
             }
-            _ => PrivilegeType::None,
-        })
+        }
+        Self::Other(value)
     }
 }
 
-#[derive(Debug, Default, Deserialize, Clone)]
-#[serde(default)]
+/// The privileges held on a resource, including the ones they aggregate.
+///
+/// Deserializes from the flags of its accessors, e.g.
+/// `{"read": true, "write": true}`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(from = "PrivilegeFlags")]
 pub struct Privilege {
+    granted: Vec<PrivilegeType>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct PrivilegeFlags {
     read: bool,
     write: bool,
+    write_properties: bool,
+    write_content: bool,
+    unlock: bool,
     read_acl: bool,
+    read_current_user_privilege_set: bool,
     write_acl: bool,
+    bind: bool,
+    unbind: bool,
     all: bool,
 }
 
+impl From<PrivilegeFlags> for Privilege {
+    fn from(value: PrivilegeFlags) -> Self {
+        let flags = [
+            value.read,
+            value.write,
+            value.write_properties,
+            value.write_content,
+            value.unlock,
+            value.read_acl,
+            value.read_current_user_privilege_set,
+            value.write_acl,
+            value.bind,
+            value.unbind,
+            value.all,
+        ];
+        let held = PrivilegeType::DAV.into_iter().zip(flags);
+        Self::from(
+            held.filter_map(|(ty, held)| held.then_some(ty))
+                .collect::<Vec<_>>(),
+        )
+    }
+}
+
 impl Privilege {
     pub fn read(&self) -> bool {
-        self.read
+        self.has(&PrivilegeType::Read)
     }
     pub fn write(&self) -> bool {
-        self.write
+        self.has(&PrivilegeType::Write)
+    }
+    pub fn write_properties(&self) -> bool {
+        self.has(&PrivilegeType::WriteProperties)
+    }
+    pub fn write_content(&self) -> bool {
+        self.has(&PrivilegeType::WriteContent)
+    }
+    pub fn unlock(&self) -> bool {
+        self.has(&PrivilegeType::Unlock)
     }
     pub fn read_acl(&self) -> bool {
-        self.read_acl
+        self.has(&PrivilegeType::ReadAcl)
+    }
+    pub fn read_current_user_privilege_set(&self) -> bool {
+        self.has(&PrivilegeType::ReadCurrentUserPrivilegeSet)
     }
     pub fn write_acl(&self) -> bool {
-        self.write_acl
+        self.has(&PrivilegeType::WriteAcl)
+    }
+    pub fn bind(&self) -> bool {
+        self.has(&PrivilegeType::Bind)
+    }
+    pub fn unbind(&self) -> bool {
+        self.has(&PrivilegeType::Unbind)
     }
     pub fn all(&self) -> bool {
-        self.all
+        self.has(&PrivilegeType::All)
+    }
+    pub fn has(&self, privilege: &PrivilegeType) -> bool {
+        self.granted.contains(privilege)
+    }
+    /// Every privilege held, reported or aggregated.
+    pub fn granted(&self) -> &[PrivilegeType] {
+        &self.granted
+    }
+
+    /// Adds the privileges aggregated by the held ones according to the
+    /// server's `supported-privilege-set`, e.g. from
+    /// [`super::acl::AccessControl::supported_privileges`].
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// use webdav_request::res::acl::SupportedPrivilege;
+    /// use webdav_request::res::privilege::{Privilege, PrivilegeName, PrivilegeType};
+    ///
+    /// let free_busy = PrivilegeName::new("urn:ietf:params:xml:ns:caldav", "read-free-busy");
+    /// let supported = [SupportedPrivilege {
+    ///     privilege: PrivilegeName::dav("read"),
+    ///     is_abstract: false,
+    ///     description: None,
+    ///     aggregates: vec![SupportedPrivilege {
+    ///         privilege: free_busy.clone(),
+    ///         is_abstract: true,
+    ///         description: None,
+    ///         aggregates: Vec::new(),
+    ///     }],
+    /// }];
+    ///
+    /// let privilege = Privilege::from(vec![PrivilegeType::All]);
+    /// assert!(privilege.write() && privilege.bind() && privilege.read_acl());
+    /// let privilege = privilege.aggregate(&supported);
+    /// assert!(privilege.has(&PrivilegeType::Other(free_busy)));
+    /// ```
+    pub fn aggregate(mut self, supported: &[SupportedPrivilege]) -> Self {
+        let mut pending: Vec<&SupportedPrivilege> = supported.iter().collect();
+        while let Some(node) = pending.pop() {
+            if self.has(&PrivilegeType::from(node.privilege.clone())) {
+                let mut contained: Vec<&SupportedPrivilege> = node.aggregates.iter().collect();
+                while let Some(child) = contained.pop() {
+                    self.insert(PrivilegeType::from(child.privilege.clone()));
+                    contained.extend(&child.aggregates);
+                }
+            } else {
+                pending.extend(&node.aggregates);
+            }
+        }
+        self
+    }
+
+    fn insert(&mut self, privilege: PrivilegeType) {
+        if self.has(&privilege) {
+            return;
+        }
+        let aggregates = privilege.aggregates();
+        self.granted.push(privilege);
+        for aggregated in aggregates {
+            self.insert(aggregated.clone());
+        }
     }
 }
 
 impl From<Vec<PrivilegeType>> for Privilege {
     fn from(value: Vec<PrivilegeType>) -> Self {
-        let mut read = false;
-        let mut write = false;
-        let mut read_acl = false;
-        let mut write_acl = false;
-        let mut all = false;
+        let mut privilege = Self::default();
         for ty in value {
-            match ty {
-                PrivilegeType::Read => read = true,
-                PrivilegeType::Write => write = true,
-                PrivilegeType::ReadAcl => read_acl = true,
-                PrivilegeType::WriteAcl => write_acl = true,
-                PrivilegeType::All => all = true,
-                PrivilegeType::None => (),
-            }
-        }
-        Self {
-            read,
-            write,
-            read_acl,
-            write_acl,
-            all,
+            privilege.insert(ty);
         }
+        privilege
     }
 }
@@ -0,0 +1,101 @@
+//! Quotas (RFC 4331).
+
+/// Asks for the quota properties of a collection.
+pub(crate) const QUOTA_PROPFIND: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+    <D:propfind xmlns:D="DAV:">
+        <D:prop>
+            <D:quota-available-bytes/>
+            <D:quota-used-bytes/>
+        </D:prop>
+    </D:propfind>
+"#;
+
+/// The value Nextcloud and ownCloud report for an unlimited quota. Other
+/// negative values mean it is not known.
+const UNLIMITED: i64 = -3;
+
+/// A number of bytes of a quota.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QuotaBytes {
+    Bytes(u64),
+    Unlimited,
+    /// Not reported, or not computed by the server.
+    #[default]
+    Unknown,
+}
+
+impl QuotaBytes {
+    /// Reads a reported value, mapping the negative values of Nextcloud.
+    pub fn from_reported(value: i64) -> Self {
+        match value {
+            0.. => Self::Bytes(value as u64),
+            UNLIMITED => Self::Unlimited,
+            _ => Self::Unknown,
+        }
+    }
+    /// The number of bytes, if limited and known.
+    pub fn bytes(&self) -> Option<u64> {
+        match self {
+            Self::Bytes(bytes) => Some(*bytes),
+            _ => None,
+        }
+    }
+}
+
+/// The storage used and available in a collection, returned by
+/// [`crate::WebDAVClient::quota`].
+///
+/// # Usage
+///
+/// ```
+/// use webdav_request::res::quota::{QuotaBytes, QuotaInfo};
+///
+/// let quota = QuotaInfo::from_reported(Some(1024), Some(3072));
+/// assert_eq!(quota.total(), QuotaBytes::Bytes(4096));
+/// assert!(quota.fits(1000) && !quota.fits(2000));
+///
+/// let quota = QuotaInfo::from_reported(Some(-3), Some(3072));
+/// assert_eq!(quota.available, QuotaBytes::Unlimited);
+/// assert!(quota.fits(u64::MAX));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QuotaInfo {
+    /// The bytes used, if known.
+    pub used: Option<u64>,
+    /// The bytes that can still be stored, which may be unlimited.
+    pub available: QuotaBytes,
+}
+
+impl QuotaInfo {
+    /// Reads the values of `DAV:quota-available-bytes` and
+    /// `DAV:quota-used-bytes`.
+    pub fn from_reported(available: Option<i64>, used: Option<i64>) -> Self {
+        Self {
+            used: used.and_then(|used| u64::try_from(used).ok()),
+            available: available.map_or(QuotaBytes::Unknown, QuotaBytes::from_reported),
+        }
+    }
+    /// The size of the quota: the bytes used and available.
+    pub fn total(&self) -> QuotaBytes {
+        match (self.available, self.used) {
+            (QuotaBytes::Bytes(available), Some(used)) => {
+                QuotaBytes::Bytes(available.saturating_add(used))
+            }
+            (QuotaBytes::Bytes(_), None) => QuotaBytes::Unknown,
+            (available, _) => available,
+        }
+    }
+    /// Whether `len` bytes fit into the available space. An unknown quota
+    /// is assumed to fit.
+    pub fn fits(&self, len: u64) -> bool {
+        self.available
+            .bytes()
+            .is_none_or(|available| len <= available)
+    }
+}
+
+/// The collection a resource at `url` is created in, whose quota applies.
+pub(crate) fn parent_collection(url: &reqwest::Url) -> reqwest::Url {
+    let parent = if url.path().ends_with('/') { ".." } else { "." };
+    url.join(parent).unwrap_or_else(|_| url.clone())
+}
@@ -0,0 +1,304 @@
+//! Queries for the DASL `SEARCH` method (RFC 5323).
+//!
+//! # Usage
+//!
+//! ```
+//! use webdav_request::depth::Depth;
+//! use webdav_request::search::{Order, Property, SearchQuery};
+//!
+//! let query = SearchQuery::new("/files/alice/docs")
+//!     .depth(Depth::Infinity)
+//!     .condition(
+//!         Property::display_name()
+//!             .like("%.pdf")
+//!             .and(Property::content_length().gt(1 << 20)),
+//!     )
+//!     .order_by(Property::last_modified(), Order::Descending)
+//!     .limit(20);
+//! let xml = query.to_xml()?;
+//! assert!(xml.contains("<d:like><d:prop><d:displayname/></d:prop><d:literal>%.pdf</d:literal></d:like>"));
+//! assert!(xml.contains("<d:nresults>20</d:nresults>"));
+//! # Ok::<(), webdav_request::error::Error>(())
+//! ```
+use std::fmt::Write;
+
+use quick_xml::escape::escape;
+
+use crate::depth::Depth;
+use crate::error::Error;
+use crate::res::element::write_empty;
+
+/// A property a query compares or sorts by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Property {
+    namespace: String,
+    name: String,
+}
+
+impl Property {
+    /// The property `name` in `namespace`, e.g. `fileid` in
+    /// `http://owncloud.org/ns`.
+    pub fn new(namespace: &str, name: &str) -> Self {
+        Self {
+            namespace: namespace.to_owned(),
+            name: name.to_owned(),
+        }
+    }
+    /// The property `name` in the `DAV:` namespace.
+    pub fn dav(name: &str) -> Self {
+        Self::new("DAV:", name)
+    }
+    pub fn display_name() -> Self {
+        Self::dav("displayname")
+    }
+    pub fn content_type() -> Self {
+        Self::dav("getcontenttype")
+    }
+    pub fn content_length() -> Self {
+        Self::dav("getcontentlength")
+    }
+    pub fn last_modified() -> Self {
+        Self::dav("getlastmodified")
+    }
+    pub fn etag() -> Self {
+        Self::dav("getetag")
+    }
+
+    /// Matches values equal to `value`.
+    pub fn eq(self, value: impl ToString) -> Condition {
+        Condition::Eq(self, value.to_string())
+    }
+    /// Matches values less than `value`.
+    pub fn lt(self, value: impl ToString) -> Condition {
+        Condition::Lt(self, value.to_string())
+    }
+    /// Matches values less than or equal to `value`.
+    pub fn lte(self, value: impl ToString) -> Condition {
+        Condition::Lte(self, value.to_string())
+    }
+    /// Matches values greater than `value`.
+    pub fn gt(self, value: impl ToString) -> Condition {
+        Condition::Gt(self, value.to_string())
+    }
+    /// Matches values greater than or equal to `value`.
+    pub fn gte(self, value: impl ToString) -> Condition {
+        Condition::Gte(self, value.to_string())
+    }
+    /// Matches values against `pattern`, where `%` matches any characters
+    /// and `_` a single one, e.g. `%.pdf`.
+    pub fn like(self, pattern: &str) -> Condition {
+        Condition::Like(self, pattern.to_owned())
+    }
+    /// Matches resources that have the property.
+    pub fn is_defined(self) -> Condition {
+        Condition::IsDefined(self)
+    }
+
+    fn write(&self, xml: &mut String) -> Result<(), Error> {
+        write_empty(xml, "d", &self.namespace, &self.name)
+    }
+}
+
+/// The `DAV:where` clause of a query.
+///
+/// Conditions are combined with [`Condition::and`], [`Condition::or`] and
+/// `!`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    Eq(Property, String),
+    Lt(Property, String),
+    Lte(Property, String),
+    Gt(Property, String),
+    Gte(Property, String),
+    Like(Property, String),
+    IsDefined(Property),
+    /// Matches collections.
+    IsCollection,
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    /// Matches if both conditions do.
+    pub fn and(self, other: Condition) -> Self {
+        match self {
+            Self::And(mut conditions) => {
+                conditions.push(other);
+                Self::And(conditions)
+            }
+            condition => Self::And(vec![condition, other]),
+        }
+    }
+    /// Matches if either condition does.
+    pub fn or(self, other: Condition) -> Self {
+        match self {
+            Self::Or(mut conditions) => {
+                conditions.push(other);
+                Self::Or(conditions)
+            }
+            condition => Self::Or(vec![condition, other]),
+        }
+    }
+
+    fn write(&self, xml: &mut String) -> Result<(), Error> {
+        let (operator, property, literal) = match self {
+            Self::Eq(property, literal) => ("eq", property, literal),
+            Self::Lt(property, literal) => ("lt", property, literal),
+            Self::Lte(property, literal) => ("lte", property, literal),
+            Self::Gt(property, literal) => ("gt", property, literal),
+            Self::Gte(property, literal) => ("gte", property, literal),
+            Self::Like(property, literal) => ("like", property, literal),
+            Self::IsDefined(property) => {
+                xml.push_str("<d:is-defined><d:prop>");
+                property.write(xml)?;
+                xml.push_str("</d:prop></d:is-defined>");
+                return Ok(());
+            }
+            Self::IsCollection => {
+                xml.push_str("<d:is-collection/>");
+                return Ok(());
+            }
+            Self::And(conditions) | Self::Or(conditions) => {
+                let operator = if matches!(self, Self::And(_)) {
+                    "and"
+                } else {
+                    "or"
+                };
+                let _ = write!(xml, "<d:{operator}>");
+                for condition in conditions {
+                    condition.write(xml)?;
+                }
+                let _ = write!(xml, "</d:{operator}>");
+                return Ok(());
+            }
+            Self::Not(condition) => {
+                xml.push_str("<d:not>");
+                condition.write(xml)?;
+                xml.push_str("</d:not>");
+                return Ok(());
+            }
+        };
+        let _ = write!(xml, "<d:{operator}><d:prop>");
+        property.write(xml)?;
+        let literal = escape(literal.as_str());
+        let _ = write!(
+            xml,
+            "</d:prop><d:literal>{literal}</d:literal></d:{operator}>"
+        );
+        Ok(())
+    }
+}
+
+impl std::ops::Not for Condition {
+    type Output = Self;
+    fn not(self) -> Self {
+        match self {
+            Self::Not(condition) => *condition,
+            condition => Self::Not(Box::new(condition)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Order {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+/// A `DAV:basicsearch` query, sent by [`crate::WebDAVClient::search`].
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    scope: String,
+    depth: Depth,
+    condition: Option<Condition>,
+    order: Vec<(Property, Order)>,
+    limit: Option<u32>,
+}
+
+impl SearchQuery {
+    /// Searches below the collection at `scope`, an absolute path or URL,
+    /// e.g. `/files/alice` on Nextcloud.
+    pub fn new(scope: &str) -> Self {
+        Self {
+            scope: scope.to_owned(),
+            depth: Depth::default(),
+            condition: None,
+            order: Vec::new(),
+            limit: None,
+        }
+    }
+    /// Searches `depth` levels below the scope, all of them by default.
+    pub fn depth(self, depth: Depth) -> Self {
+        Self { depth, ..self }
+    }
+    /// Only returns the resources matching `condition`.
+    pub fn condition(self, condition: Condition) -> Self {
+        Self {
+            condition: Some(condition),
+            ..self
+        }
+    }
+    /// Sorts by `property`, after the properties added before.
+    pub fn order_by(mut self, property: Property, order: Order) -> Self {
+        self.order.push((property, order));
+        self
+    }
+    /// Returns at most `limit` resources.
+    pub fn limit(self, limit: u32) -> Self {
+        Self {
+            limit: Some(limit),
+            ..self
+        }
+    }
+
+    /// The `DAV:searchrequest` body. Fails with
+    /// [`Error::InvalidXmlName`] for a property name that is not a valid
+    /// XML name.
+    pub fn to_xml(&self) -> Result<String, Error> {
+        let mut xml = String::from(
+            r#"<?xml version="1.0" encoding="utf-8"?><d:searchrequest xmlns:d="DAV:"><d:basicsearch>"#,
+        );
+        xml.push_str("<d:select><d:prop>");
+        for property in [
+            Property::display_name(),
+            Property::dav("resourcetype"),
+            Property::content_length(),
+            Property::content_type(),
+            Property::last_modified(),
+            Property::etag(),
+        ] {
+            property.write(&mut xml)?;
+        }
+        xml.push_str("</d:prop></d:select>");
+        let depth = self.depth.as_str();
+        let scope = escape(self.scope.as_str());
+        let _ = write!(
+            xml,
+            "<d:from><d:scope><d:href>{scope}</d:href><d:depth>{depth}</d:depth></d:scope></d:from>"
+        );
+        if let Some(condition) = &self.condition {
+            xml.push_str("<d:where>");
+            condition.write(&mut xml)?;
+            xml.push_str("</d:where>");
+        }
+        if !self.order.is_empty() {
+            xml.push_str("<d:orderby>");
+            for (property, order) in &self.order {
+                xml.push_str("<d:order><d:prop>");
+                property.write(&mut xml)?;
+                xml.push_str(match order {
+                    Order::Ascending => "</d:prop><d:ascending/></d:order>",
+                    Order::Descending => "</d:prop><d:descending/></d:order>",
+                });
+            }
+            xml.push_str("</d:orderby>");
+        }
+        if let Some(limit) = self.limit {
+            let _ = write!(xml, "<d:limit><d:nresults>{limit}</d:nresults></d:limit>");
+        }
+        xml.push_str("</d:basicsearch></d:searchrequest>");
+        Ok(xml)
+    }
+}
@@ -0,0 +1,100 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use bytes::{Buf, Bytes, BytesMut};
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Body, Frame};
+use tokio::io::{AsyncRead, ReadBuf};
+
+use super::fs::DavReader;
+use super::DavBody;
+
+/// The size of the chunks a file is read in.
+const CHUNK_SIZE: usize = 64 << 10;
+
+/// A body of `content`.
+pub(crate) fn full(content: impl Into<Bytes>) -> DavBody {
+    Full::new(content.into())
+        .map_err(|never| match never {})
+        .boxed_unsync()
+}
+
+/// A body read from `reader` in chunks.
+pub(crate) fn stream(reader: DavReader) -> DavBody {
+    ReaderBody {
+        reader,
+        buf: BytesMut::new(),
+    }
+    .boxed_unsync()
+}
+
+struct ReaderBody {
+    reader: DavReader,
+    buf: BytesMut,
+}
+
+impl Body for ReaderBody {
+    type Data = Bytes;
+    type Error = io::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, io::Error>>> {
+        let this = &mut *self;
+        this.buf.reserve(CHUNK_SIZE);
+        let read = tokio_util::io::poll_read_buf(this.reader.as_mut(), cx, &mut this.buf);
+        Poll::Ready(match ready!(read) {
+            Ok(0) => None,
+            Ok(_) => Some(Ok(Frame::data(this.buf.split().freeze()))),
+            Err(err) => Some(Err(err)),
+        })
+    }
+}
+
+/// A request body read as a [`DavReader`]. A body that cannot be read
+/// fails with `InvalidData`.
+pub(crate) struct BodyReader<B> {
+    body: Pin<Box<B>>,
+    chunk: Bytes,
+}
+
+impl<B> BodyReader<B> {
+    pub(crate) fn new(body: B) -> Self {
+        Self {
+            body: Box::pin(body),
+            chunk: Bytes::new(),
+        }
+    }
+}
+
+impl<B> AsyncRead for BodyReader<B>
+where
+    B: Body,
+    B::Error: std::fmt::Display,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        while this.chunk.is_empty() {
+            let frame = match ready!(this.body.as_mut().poll_frame(cx)) {
+                Some(Ok(frame)) => frame,
+                Some(Err(err)) => {
+                    let err = io::Error::new(io::ErrorKind::InvalidData, err.to_string());
+                    return Poll::Ready(Err(err));
+                }
+                None => return Poll::Ready(Ok(())),
+            };
+            if let Ok(mut data) = frame.into_data() {
+                this.chunk = data.copy_to_bytes(data.remaining());
+            }
+        }
+        let len = buf.remaining().min(this.chunk.len());
+        buf.put_slice(&this.chunk.split_to(len));
+        Poll::Ready(Ok(()))
+    }
+}
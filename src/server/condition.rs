@@ -0,0 +1,60 @@
+/// A condition of an `If` header list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Condition {
+    StateToken(String),
+    /// An entity tag, with its quotes.
+    ETag(String),
+}
+
+/// A list of an `If` header: all of its conditions must hold for the
+/// resource it is tagged with, or the request URL if untagged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct IfList {
+    pub(crate) resource: Option<String>,
+    /// The conditions, each with whether it is negated with `Not`.
+    pub(crate) conditions: Vec<(bool, Condition)>,
+}
+
+/// Parses an `If` header into its lists; `None` if it is malformed.
+pub(crate) fn parse(value: &str) -> Option<Vec<IfList>> {
+    let mut lists = Vec::new();
+    let mut resource = None;
+    let mut rest = value.trim_start();
+    while !rest.is_empty() {
+        if let Some(tagged) = rest.strip_prefix('<') {
+            let (tag, after) = tagged.split_once('>')?;
+            resource = Some(tag.to_owned());
+            rest = after.trim_start();
+            continue;
+        }
+        rest = rest.strip_prefix('(')?.trim_start();
+        let mut conditions = Vec::new();
+        loop {
+            if let Some(after) = rest.strip_prefix(')') {
+                rest = after.trim_start();
+                break;
+            }
+            let (not, after) = match rest.strip_prefix("Not") {
+                Some(after) => (true, after.trim_start()),
+                None => (false, rest),
+            };
+            let (condition, after) = if let Some(token) = after.strip_prefix('<') {
+                let (token, after) = token.split_once('>')?;
+                (Condition::StateToken(token.to_owned()), after)
+            } else {
+                let (etag, after) = after.strip_prefix('[')?.split_once(']')?;
+                (Condition::ETag(etag.to_owned()), after)
+            };
+            conditions.push((not, condition));
+            rest = after.trim_start();
+        }
+        if conditions.is_empty() {
+            return None;
+        }
+        lists.push(IfList {
+            resource: resource.clone(),
+            conditions,
+        });
+    }
+    (!lists.is_empty()).then_some(lists)
+}
@@ -0,0 +1,146 @@
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::time::SystemTime;
+
+use tokio::io::AsyncRead;
+
+/// The content of a file, read or written in chunks.
+pub type DavReader = Pin<Box<dyn AsyncRead + Send>>;
+
+/// The properties of a file or collection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DavMetadata {
+    pub is_collection: bool,
+    /// The length of a file; `0` for a collection.
+    pub len: u64,
+    pub modified: SystemTime,
+    /// A quoted entity tag that changes with the content, e.g. `"5-1a2b"`.
+    pub etag: String,
+}
+
+/// A property set by `PROPPATCH` and stored by the file system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadProperty {
+    pub namespace: String,
+    pub name: String,
+    /// The XML content of the property element, with its text trimmed and
+    /// written before its child elements.
+    pub value: String,
+}
+
+/// A change of a `PROPPATCH`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PropertyUpdate {
+    Set(DeadProperty),
+    Remove { namespace: String, name: String },
+}
+
+/// The storage behind a [`super::DavService`].
+///
+/// Paths are absolute, decoded and normalized, e.g. `/dir/file.txt`, with
+/// `/` for the root collection. Their segments are never `..` and contain
+/// neither `\` nor `:`. The service checks that parents exist and targets
+/// are free before it calls [`DavFileSystem::write`],
+/// [`DavFileSystem::create_dir`], [`DavFileSystem::copy_file`] and
+/// [`DavFileSystem::rename`].
+///
+/// Errors are mapped to statuses by kind: `NotFound` to `404`,
+/// `AlreadyExists` to `405`, `PermissionDenied` to `403`, and anything else
+/// to `500`.
+///
+/// Dead properties are optional: by default a file system has none and
+/// refuses `PROPPATCH` with `403`. One that stores them keeps them with
+/// the file or collection on [`DavFileSystem::write`] and
+/// [`DavFileSystem::rename`], and drops them on [`DavFileSystem::remove`];
+/// the service copies them on `COPY`.
+pub trait DavFileSystem: Send + Sync + 'static {
+    fn metadata(&self, path: &str) -> impl Future<Output = io::Result<DavMetadata>> + Send;
+    /// The names of the members of the collection at `path`.
+    fn read_dir(&self, path: &str) -> impl Future<Output = io::Result<Vec<String>>> + Send;
+    /// The content of the file at `path`, from byte `offset` to the end.
+    fn read(&self, path: &str, offset: u64) -> impl Future<Output = io::Result<DavReader>> + Send;
+    /// Creates or replaces the file at `path` with `content`, read to its
+    /// end. A failed write should leave an existing file unchanged.
+    fn write(&self, path: &str, content: DavReader) -> impl Future<Output = io::Result<()>> + Send;
+    fn create_dir(&self, path: &str) -> impl Future<Output = io::Result<()>> + Send;
+    /// Removes the file or collection at `path` and everything below it.
+    fn remove(&self, path: &str) -> impl Future<Output = io::Result<()>> + Send;
+    /// Copies the file at `from` to `to`. Collections are copied by the
+    /// service, member by member.
+    fn copy_file(&self, from: &str, to: &str) -> impl Future<Output = io::Result<()>> + Send;
+    /// Moves the file or collection at `from` to `to`, which does not exist.
+    fn rename(&self, from: &str, to: &str) -> impl Future<Output = io::Result<()>> + Send;
+    /// The dead properties of the file or collection at `path`.
+    fn properties(&self, path: &str) -> impl Future<Output = io::Result<Vec<DeadProperty>>> + Send {
+        let _ = path;
+        async { Ok(Vec::new()) }
+    }
+    /// Applies `updates` in order to the dead properties of `path`, all or
+    /// none of them. Fails with `Unsupported` by default.
+    fn patch_properties(
+        &self,
+        path: &str,
+        updates: Vec<PropertyUpdate>,
+    ) -> impl Future<Output = io::Result<()>> + Send {
+        let _ = (path, updates);
+        async { Err(io::ErrorKind::Unsupported.into()) }
+    }
+}
+
+/// Applies `updates` to `properties`, replacing a property of the same name.
+pub(crate) fn apply_updates(properties: &mut Vec<DeadProperty>, updates: Vec<PropertyUpdate>) {
+    for update in updates {
+        let (namespace, name) = match &update {
+            PropertyUpdate::Set(property) => (&property.namespace, &property.name),
+            PropertyUpdate::Remove { namespace, name } => (namespace, name),
+        };
+        let position = properties
+            .iter()
+            .position(|property| &property.namespace == namespace && &property.name == name);
+        match (update, position) {
+            (PropertyUpdate::Set(property), Some(position)) => properties[position] = property,
+            (PropertyUpdate::Set(property), None) => properties.push(property),
+            (PropertyUpdate::Remove { .. }, Some(position)) => drop(properties.remove(position)),
+            (PropertyUpdate::Remove { .. }, None) => (),
+        }
+    }
+}
+
+/// The parent of a normalized path; `None` for the root.
+pub(crate) fn parent(path: &str) -> Option<&str> {
+    if path == "/" {
+        return None;
+    }
+    match path.rfind('/') {
+        Some(0) => Some("/"),
+        Some(index) => Some(&path[..index]),
+        None => None,
+    }
+}
+
+/// `name` inside the collection at `path`.
+pub(crate) fn join(path: &str, name: &str) -> String {
+    if path == "/" {
+        format!("/{name}")
+    } else {
+        format!("{path}/{name}")
+    }
+}
+
+/// Decodes a request path and removes empty segments, `.` and trailing
+/// slashes. `None` for a `..` segment, or one containing `\` or `:`,
+/// which a local file system could read as a separator or a drive.
+pub(crate) fn normalize(path: &str) -> Option<String> {
+    let path = percent_encoding::percent_decode_str(path).decode_utf8_lossy();
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => (),
+            ".." => return None,
+            segment if segment.contains(['\\', ':']) => return None,
+            segment => segments.push(segment),
+        }
+    }
+    Some(format!("/{}", segments.join("/")))
+}
@@ -0,0 +1,876 @@
+use std::fmt::Write;
+use std::io;
+use std::time::Duration;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use http_body_util::BodyExt;
+use hyper::body::Body;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use quick_xml::escape::escape;
+use reqwest::header::{
+    HeaderMap, HeaderValue, ACCEPT_RANGES, ALLOW, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE,
+    ETAG, IF_MATCH, IF_NONE_MATCH, LAST_MODIFIED, RANGE,
+};
+use reqwest::{Method, StatusCode};
+
+use tokio::io::AsyncReadExt;
+
+use super::body::{self, BodyReader};
+use super::condition::{self, Condition, IfList};
+use super::fs::{
+    join, parent, DavFileSystem, DavMetadata, DavReader, DeadProperty, PropertyUpdate,
+};
+use super::lock::ActiveLock;
+use super::{DavResponse, DavService};
+use crate::if_header::IF;
+use crate::res::element::{Element, DAV};
+
+const ALLOWED: &str =
+    "OPTIONS, GET, HEAD, PUT, DELETE, MKCOL, COPY, MOVE, PROPFIND, PROPPATCH, LOCK, UNLOCK";
+
+/// The `DAV:` properties computed by the service, which `PROPPATCH` cannot
+/// change.
+const LIVE_PROPERTIES: [&str; 7] = [
+    "getcontentlength",
+    "getcontenttype",
+    "getetag",
+    "getlastmodified",
+    "lockdiscovery",
+    "resourcetype",
+    "supportedlock",
+];
+
+const SUPPORTED_LOCK: &str = "<d:lockentry><d:lockscope><d:exclusive/></d:lockscope><d:locktype><d:write/></d:locktype></d:lockentry>";
+
+/// The characters escaped in the segments of an `href`.
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// A request with its file system path, the lists of its `If` header and
+/// its collected XML body.
+struct Request {
+    method: Method,
+    path: String,
+    headers: HeaderMap,
+    if_lists: Vec<IfList>,
+    body: Bytes,
+}
+
+impl Request {
+    /// Whether the request submits the lock `token` in its `If` header.
+    fn submits(&self, token: &str) -> bool {
+        let mut conditions = self.if_lists.iter().flat_map(|list| &list.conditions);
+        conditions.any(|(not, condition)| {
+            !not && matches!(condition, Condition::StateToken(submitted) if submitted == token)
+        })
+    }
+}
+
+pub(crate) async fn handle<F, B>(service: &DavService<F>, request: hyper::Request<B>) -> DavResponse
+where
+    F: DavFileSystem,
+    B: Body + Send + 'static,
+    B::Data: Send,
+    B::Error: std::fmt::Display,
+{
+    let Some(path) = service.fs_path(request.uri().path()) else {
+        return status(StatusCode::NOT_FOUND);
+    };
+    let (parts, body) = request.into_parts();
+    let if_lists = match parts.headers.get(IF) {
+        Some(value) => match value.to_str().ok().and_then(condition::parse) {
+            Some(lists) => lists,
+            None => return status(StatusCode::BAD_REQUEST),
+        },
+        None => Vec::new(),
+    };
+    // Only the content of a `PUT` is streamed; other bodies are XML.
+    let (body, content): (Bytes, DavReader) = if parts.method == Method::PUT {
+        (Bytes::new(), Box::pin(BodyReader::new(body)))
+    } else {
+        match collect(body, service.max_body_size).await {
+            Ok(body) => (body, Box::pin(tokio::io::empty())),
+            Err(code) => return status(code),
+        }
+    };
+    let request = Request {
+        method: parts.method,
+        path,
+        headers: parts.headers,
+        if_lists,
+        body,
+    };
+    match service.if_holds(&request).await {
+        Ok(true) => (),
+        Ok(false) => return status(StatusCode::PRECONDITION_FAILED),
+        Err(err) => return status(io_status(&err)),
+    }
+    let result = match request.method.as_str() {
+        "OPTIONS" => {
+            let mut response = status(StatusCode::OK);
+            let headers = response.headers_mut();
+            headers.insert("dav", HeaderValue::from_static("1, 2"));
+            headers.insert(ALLOW, HeaderValue::from_static(ALLOWED));
+            Ok(response)
+        }
+        "GET" | "HEAD" => service.get(&request).await,
+        "PUT" => service.put(&request, content).await,
+        "DELETE" => service.delete(&request).await,
+        "MKCOL" => service.mkcol(&request).await,
+        "COPY" | "MOVE" => service.copy(&request).await,
+        "PROPFIND" => service.propfind(&request).await,
+        "PROPPATCH" => service.proppatch(&request).await,
+        "LOCK" => service.lock(&request).await,
+        "UNLOCK" => Ok(service.unlock(&request)),
+        _ => {
+            let mut response = status(StatusCode::METHOD_NOT_ALLOWED);
+            let allow = HeaderValue::from_static(ALLOWED);
+            response.headers_mut().insert(ALLOW, allow);
+            Ok(response)
+        }
+    };
+    result.unwrap_or_else(|err| status(io_status(&err)))
+}
+
+/// Reads a body of at most `limit` bytes, or the status to answer instead.
+async fn collect<B>(body: B, limit: usize) -> Result<Bytes, StatusCode>
+where
+    B: Body,
+    B::Error: std::fmt::Display,
+{
+    if body.size_hint().lower() > limit as u64 {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+    let mut body = std::pin::pin!(body);
+    let mut collected = BytesMut::new();
+    while let Some(frame) = body.frame().await {
+        let frame = frame.map_err(|_| StatusCode::BAD_REQUEST)?;
+        if let Ok(data) = frame.into_data() {
+            if data.remaining() > limit - collected.len() {
+                return Err(StatusCode::PAYLOAD_TOO_LARGE);
+            }
+            collected.put(data);
+        }
+    }
+    Ok(collected.freeze())
+}
+
+fn status(status: StatusCode) -> DavResponse {
+    let mut response = DavResponse::new(body::full(Bytes::new()));
+    *response.status_mut() = status;
+    response
+}
+
+fn io_status(err: &io::Error) -> StatusCode {
+    match err.kind() {
+        io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+        io::ErrorKind::AlreadyExists => StatusCode::METHOD_NOT_ALLOWED,
+        io::ErrorKind::PermissionDenied => StatusCode::FORBIDDEN,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn xml_response(status: StatusCode, xml: String) -> DavResponse {
+    let mut response = DavResponse::new(body::full(xml));
+    *response.status_mut() = status;
+    let content_type = HeaderValue::from_static("application/xml; charset=utf-8");
+    response.headers_mut().insert(CONTENT_TYPE, content_type);
+    response
+}
+
+fn multistatus(responses: &str) -> DavResponse {
+    let xml = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?><d:multistatus xmlns:d="DAV:">{responses}</d:multistatus>"#
+    );
+    xml_response(StatusCode::MULTI_STATUS, xml)
+}
+
+/// Writes a `propstat` of the properties `props` with `status`.
+fn write_propstat(xml: &mut String, props: &str, status: StatusCode) {
+    let _ = write!(
+        xml,
+        "<d:propstat><d:prop>{props}</d:prop><d:status>HTTP/1.1 {status}</d:status></d:propstat>"
+    );
+}
+
+/// Writes the property `name` in `namespace` with the XML `value`.
+fn write_prop(xml: &mut String, namespace: &str, name: &str, value: &str) {
+    let _ = if namespace == DAV {
+        write!(xml, "<d:{name}>{value}</d:{name}>")
+    } else {
+        write!(
+            xml,
+            r#"<{name} xmlns="{}">{value}</{name}>"#,
+            escape(namespace)
+        )
+    };
+}
+
+fn metadata_headers(headers: &mut HeaderMap, metadata: &DavMetadata) {
+    if let Ok(etag) = HeaderValue::from_str(&metadata.etag) {
+        headers.insert(ETAG, etag);
+    }
+    let modified = httpdate::fmt_http_date(metadata.modified);
+    headers.insert(LAST_MODIFIED, HeaderValue::from_str(&modified).unwrap());
+}
+
+/// Evaluates `If-Match` and `If-None-Match`. A failed `If-None-Match` is
+/// `304 Not Modified` for `GET` and `HEAD`.
+fn precondition(request: &Request, metadata: Option<&DavMetadata>) -> Option<StatusCode> {
+    let matches = |name| {
+        let value = request.headers.get(name)?.to_str().ok()?;
+        Some(value.split(',').any(|etag| match etag.trim() {
+            "*" => metadata.is_some(),
+            etag => metadata.is_some_and(|m| m.etag == etag.trim_start_matches("W/")),
+        }))
+    };
+    if matches(IF_MATCH) == Some(false) {
+        return Some(StatusCode::PRECONDITION_FAILED);
+    }
+    if matches(IF_NONE_MATCH) == Some(true) {
+        return Some(match request.method {
+            Method::GET | Method::HEAD => StatusCode::NOT_MODIFIED,
+            _ => StatusCode::PRECONDITION_FAILED,
+        });
+    }
+    None
+}
+
+/// Parses a single `bytes=` range: `None` if it is not one, and `Some(None)`
+/// if it cannot be satisfied.
+fn parse_range(range: &str, len: u64) -> Option<Option<(u64, u64)>> {
+    let (start, end) = range.trim().strip_prefix("bytes=")?.split_once('-')?;
+    let last = len.saturating_sub(1);
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => (len.saturating_sub(suffix.parse().ok()?), last),
+        (start, "") => (start.parse().ok()?, last),
+        (start, end) => (start.parse().ok()?, end.parse::<u64>().ok()?.min(last)),
+    };
+    Some((start < len && start <= end).then_some((start, end)))
+}
+
+/// Reads the `Timeout` header: `Second-n` or `Infinite`.
+fn lock_timeout(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get("timeout")?.to_str().ok()?;
+    let seconds = value.split(',').next()?.trim().strip_prefix("Second-")?;
+    seconds.parse().ok().map(Duration::from_secs)
+}
+
+impl<F: DavFileSystem> DavService<F> {
+    async fn metadata(&self, path: &str) -> io::Result<Option<DavMetadata>> {
+        match self.fs.metadata(path).await {
+            Ok(metadata) => Ok(Some(metadata)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn parent_is_collection(&self, path: &str) -> io::Result<bool> {
+        let Some(parent) = parent(path) else {
+            return Ok(false);
+        };
+        Ok(self
+            .metadata(parent)
+            .await?
+            .is_some_and(|metadata| metadata.is_collection))
+    }
+
+    /// Whether `path` (and, if `deep`, its members) is locked with a token
+    /// the request did not submit.
+    fn is_locked_for(&self, request: &Request, path: &str, deep: bool) -> bool {
+        let tokens = self.locks.lock().unwrap().tokens(path, deep);
+        tokens.iter().any(|token| !request.submits(token))
+    }
+
+    /// The file system path of a URL or an absolute path, or `None` outside
+    /// the prefix.
+    fn resource_path(&self, reference: &str) -> Option<String> {
+        match url::Url::parse(reference) {
+            Ok(url) => self.fs_path(url.path()),
+            Err(_) => self.fs_path(reference),
+        }
+    }
+
+    /// Evaluates the `If` header: whether one of its lists holds, or `true`
+    /// without one. A list tagged with a resource outside the prefix holds
+    /// only through negated conditions.
+    async fn if_holds(&self, request: &Request) -> io::Result<bool> {
+        if request.if_lists.is_empty() {
+            return Ok(true);
+        }
+        for list in &request.if_lists {
+            let path = match &list.resource {
+                Some(resource) => self.resource_path(resource),
+                None => Some(request.path.clone()),
+            };
+            let (etag, token) = match &path {
+                Some(path) => {
+                    let etag = self.metadata(path).await?.map(|metadata| metadata.etag);
+                    let mut locks = self.locks.lock().unwrap();
+                    let token = locks.covering(path).map(|(_, lock)| lock.token.clone());
+                    (etag, token)
+                }
+                None => (None, None),
+            };
+            let holds = list.conditions.iter().all(|(not, condition)| {
+                let matches = match condition {
+                    Condition::StateToken(submitted) => token.as_ref() == Some(submitted),
+                    Condition::ETag(submitted) => etag.as_deref().is_some_and(|etag| {
+                        etag.trim_start_matches("W/") == submitted.trim_start_matches("W/")
+                    }),
+                };
+                matches != *not
+            });
+            if holds {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn href(&self, path: &str, collection: bool) -> String {
+        let path = match (self.prefix.as_str(), path) {
+            ("/", path) => path.to_owned(),
+            (prefix, "/") => prefix.to_owned(),
+            (prefix, path) => format!("{prefix}{path}"),
+        };
+        let mut href = path
+            .split('/')
+            .map(|segment| utf8_percent_encode(segment, SEGMENT).to_string())
+            .collect::<Vec<_>>()
+            .join("/");
+        if collection && !href.ends_with('/') {
+            href.push('/');
+        }
+        href
+    }
+
+    async fn get(&self, request: &Request) -> io::Result<DavResponse> {
+        let Some(metadata) = self.metadata(&request.path).await? else {
+            return Ok(status(StatusCode::NOT_FOUND));
+        };
+        if let Some(status_code) = precondition(request, Some(&metadata)) {
+            return Ok(status(status_code));
+        }
+        let len = metadata.len;
+        let range = request
+            .headers
+            .get(RANGE)
+            .and_then(|range| range.to_str().ok())
+            .and_then(|range| parse_range(range, len));
+        let (status_code, start, end) = match range {
+            None => (StatusCode::OK, 0, len),
+            Some(None) => {
+                let mut response = status(StatusCode::RANGE_NOT_SATISFIABLE);
+                let content_range = HeaderValue::from_str(&format!("bytes */{len}")).unwrap();
+                response.headers_mut().insert(CONTENT_RANGE, content_range);
+                return Ok(response);
+            }
+            Some(Some((start, end))) => (StatusCode::PARTIAL_CONTENT, start, end + 1),
+        };
+        let mut response = if metadata.is_collection || request.method == Method::HEAD {
+            status(status_code)
+        } else {
+            let content = self.fs.read(&request.path, start).await?;
+            let mut response = DavResponse::new(body::stream(Box::pin(content.take(end - start))));
+            *response.status_mut() = status_code;
+            response
+        };
+        let headers = response.headers_mut();
+        headers.insert(CONTENT_LENGTH, HeaderValue::from(end - start));
+        if status_code == StatusCode::PARTIAL_CONTENT {
+            let content_range = format!("bytes {start}-{}/{len}", end - 1);
+            let content_range = HeaderValue::from_str(&content_range).unwrap();
+            headers.insert(CONTENT_RANGE, content_range);
+        }
+        metadata_headers(headers, &metadata);
+        headers.insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+        Ok(response)
+    }
+
+    async fn put(&self, request: &Request, content: DavReader) -> io::Result<DavResponse> {
+        let path = &request.path;
+        let metadata = self.metadata(path).await?;
+        if metadata.as_ref().is_some_and(|m| m.is_collection) {
+            return Ok(status(StatusCode::METHOD_NOT_ALLOWED));
+        }
+        if !self.parent_is_collection(path).await? {
+            return Ok(status(StatusCode::CONFLICT));
+        }
+        if self.is_locked_for(request, path, false) {
+            return Ok(status(StatusCode::LOCKED));
+        }
+        if let Some(status_code) = precondition(request, metadata.as_ref()) {
+            return Ok(status(status_code));
+        }
+        if let Err(err) = self.fs.write(path, content).await {
+            // A body that could not be received.
+            if err.kind() == io::ErrorKind::InvalidData {
+                return Ok(status(StatusCode::BAD_REQUEST));
+            }
+            return Err(err);
+        }
+        let mut response = status(match metadata {
+            Some(_) => StatusCode::NO_CONTENT,
+            None => StatusCode::CREATED,
+        });
+        if let Some(metadata) = self.metadata(path).await? {
+            metadata_headers(response.headers_mut(), &metadata);
+        }
+        Ok(response)
+    }
+
+    async fn delete(&self, request: &Request) -> io::Result<DavResponse> {
+        let path = &request.path;
+        if path == "/" {
+            return Ok(status(StatusCode::FORBIDDEN));
+        }
+        let Some(metadata) = self.metadata(path).await? else {
+            return Ok(status(StatusCode::NOT_FOUND));
+        };
+        if let Some(status_code) = precondition(request, Some(&metadata)) {
+            return Ok(status(status_code));
+        }
+        if self.is_locked_for(request, path, true) {
+            return Ok(status(StatusCode::LOCKED));
+        }
+        self.fs.remove(path).await?;
+        self.locks.lock().unwrap().remove_tree(path);
+        Ok(status(StatusCode::NO_CONTENT))
+    }
+
+    async fn mkcol(&self, request: &Request) -> io::Result<DavResponse> {
+        let path = &request.path;
+        if !request.body.is_empty() {
+            return Ok(status(StatusCode::UNSUPPORTED_MEDIA_TYPE));
+        }
+        if self.metadata(path).await?.is_some() {
+            return Ok(status(StatusCode::METHOD_NOT_ALLOWED));
+        }
+        if !self.parent_is_collection(path).await? {
+            return Ok(status(StatusCode::CONFLICT));
+        }
+        if self.is_locked_for(request, path, false) {
+            return Ok(status(StatusCode::LOCKED));
+        }
+        self.fs.create_dir(path).await?;
+        Ok(status(StatusCode::CREATED))
+    }
+
+    async fn copy(&self, request: &Request) -> io::Result<DavResponse> {
+        let from = &request.path;
+        let is_move = request.method.as_str() == "MOVE";
+        let Some(destination) = request
+            .headers
+            .get("destination")
+            .and_then(|value| value.to_str().ok())
+        else {
+            return Ok(status(StatusCode::BAD_REQUEST));
+        };
+        let Some(to) = self.resource_path(destination) else {
+            return Ok(status(StatusCode::BAD_GATEWAY));
+        };
+        if self.metadata(from).await?.is_none() {
+            return Ok(status(StatusCode::NOT_FOUND));
+        }
+        // Overlapping paths, since replacing an ancestor would remove the
+        // source and copying into a member would never end.
+        if from == "/"
+            || &to == from
+            || to.starts_with(&join(from, ""))
+            || from.starts_with(&join(&to, ""))
+        {
+            return Ok(status(StatusCode::FORBIDDEN));
+        }
+        if !self.parent_is_collection(&to).await? {
+            return Ok(status(StatusCode::CONFLICT));
+        }
+        let overwrite = request
+            .headers
+            .get("overwrite")
+            .is_none_or(|value| !value.as_bytes().eq_ignore_ascii_case(b"F"));
+        let existed = self.metadata(&to).await?.is_some();
+        if existed && !overwrite {
+            return Ok(status(StatusCode::PRECONDITION_FAILED));
+        }
+        if (is_move && self.is_locked_for(request, from, true))
+            || self.is_locked_for(request, &to, true)
+        {
+            return Ok(status(StatusCode::LOCKED));
+        }
+        if existed {
+            self.fs.remove(&to).await?;
+            self.locks.lock().unwrap().remove_tree(&to);
+        }
+        if is_move {
+            self.fs.rename(from, &to).await?;
+            self.locks.lock().unwrap().remove_tree(from);
+        } else {
+            let deep = request
+                .headers
+                .get("depth")
+                .is_none_or(|depth| depth.as_bytes() != b"0");
+            self.copy_tree(from, &to, deep).await?;
+        }
+        Ok(status(if existed {
+            StatusCode::NO_CONTENT
+        } else {
+            StatusCode::CREATED
+        }))
+    }
+
+    async fn copy_tree(&self, from: &str, to: &str, deep: bool) -> io::Result<()> {
+        let mut pending = vec![(from.to_owned(), to.to_owned())];
+        while let Some((from, to)) = pending.pop() {
+            let is_collection = self.fs.metadata(&from).await?.is_collection;
+            if is_collection {
+                self.fs.create_dir(&to).await?;
+            } else {
+                self.fs.copy_file(&from, &to).await?;
+            }
+            let properties = self.fs.properties(&from).await?;
+            if !properties.is_empty() {
+                let updates = properties.into_iter().map(PropertyUpdate::Set).collect();
+                self.fs.patch_properties(&to, updates).await?;
+            }
+            if is_collection && deep {
+                for name in self.fs.read_dir(&from).await? {
+                    pending.push((join(&from, &name), join(&to, &name)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn propfind(&self, request: &Request) -> io::Result<DavResponse> {
+        let depth = match request.headers.get("depth").map(HeaderValue::as_bytes) {
+            Some(b"0") => Some(0),
+            Some(b"1") => Some(1),
+            _ => None,
+        };
+        let Some(propfind) = PropFind::parse(&request.body) else {
+            return Ok(status(StatusCode::BAD_REQUEST));
+        };
+        let Some(metadata) = self.metadata(&request.path).await? else {
+            return Ok(status(StatusCode::NOT_FOUND));
+        };
+        let mut responses = String::new();
+        let mut pending = vec![(request.path.clone(), metadata, 0)];
+        while let Some((path, metadata, level)) = pending.pop() {
+            if metadata.is_collection && depth.is_none_or(|depth| level < depth) {
+                let mut names = self.fs.read_dir(&path).await?;
+                names.sort_unstable_by(|a, b| b.cmp(a));
+                for name in names {
+                    let member = join(&path, &name);
+                    if let Some(metadata) = self.metadata(&member).await? {
+                        pending.push((member, metadata, level + 1));
+                    }
+                }
+            }
+            self.write_prop_response(&mut responses, &path, &metadata, &propfind)
+                .await?;
+        }
+        Ok(multistatus(&responses))
+    }
+
+    /// The live properties of the resource at `path`, as `DAV:` names and
+    /// XML values.
+    fn live_properties(&self, path: &str, metadata: &DavMetadata) -> Vec<(&'static str, String)> {
+        let mut props = Vec::new();
+        if !metadata.is_collection {
+            props.push(("getcontentlength", metadata.len.to_string()));
+            props.push(("getcontenttype", String::from("application/octet-stream")));
+        }
+        props.push(("getetag", escape(metadata.etag.as_str()).into_owned()));
+        let modified = httpdate::fmt_http_date(metadata.modified);
+        props.push(("getlastmodified", modified));
+        let lock = self
+            .locks
+            .lock()
+            .unwrap()
+            .covering(path)
+            .map(|(root, lock)| self.active_lock(&root, lock));
+        props.push(("lockdiscovery", lock.unwrap_or_default()));
+        let resource_type = if metadata.is_collection {
+            "<d:collection/>"
+        } else {
+            ""
+        };
+        props.push(("resourcetype", resource_type.to_owned()));
+        props.push(("supportedlock", SUPPORTED_LOCK.to_owned()));
+        props
+    }
+
+    /// Writes the `response` of a `PROPFIND` for the resource at `path`,
+    /// with a `404` `propstat` for the requested properties it lacks.
+    async fn write_prop_response(
+        &self,
+        xml: &mut String,
+        path: &str,
+        metadata: &DavMetadata,
+        propfind: &PropFind,
+    ) -> io::Result<()> {
+        let live = self.live_properties(path, metadata);
+        let dead = self.fs.properties(path).await?;
+        let mut found = String::new();
+        let mut missing = String::new();
+        match propfind {
+            PropFind::All | PropFind::Names => {
+                let names_only = matches!(propfind, PropFind::Names);
+                for (name, value) in &live {
+                    let value = if names_only { "" } else { value };
+                    write_prop(&mut found, DAV, name, value);
+                }
+                for property in &dead {
+                    let value = if names_only { "" } else { &property.value };
+                    write_prop(&mut found, &property.namespace, &property.name, value);
+                }
+            }
+            PropFind::Props(names) => {
+                for (namespace, name) in names {
+                    let live = live
+                        .iter()
+                        .find(|(live, _)| namespace == DAV && live == name)
+                        .map(|(_, value)| value);
+                    let dead = dead
+                        .iter()
+                        .find(|property| &property.namespace == namespace && &property.name == name)
+                        .map(|property| &property.value);
+                    match live.or(dead) {
+                        Some(value) => write_prop(&mut found, namespace, name, value),
+                        None => write_prop(&mut missing, namespace, name, ""),
+                    }
+                }
+            }
+        }
+        let href = self.href(path, metadata.is_collection);
+        let _ = write!(
+            xml,
+            "<d:response><d:href>{}</d:href>",
+            escape(href.as_str())
+        );
+        if !found.is_empty() || missing.is_empty() {
+            write_propstat(xml, &found, StatusCode::OK);
+        }
+        if !missing.is_empty() {
+            write_propstat(xml, &missing, StatusCode::NOT_FOUND);
+        }
+        xml.push_str("</d:response>");
+        Ok(())
+    }
+
+    /// Sets and removes dead properties, all or none of them. Live
+    /// properties are refused with `403`, and so is every property if the
+    /// file system does not store them.
+    async fn proppatch(&self, request: &Request) -> io::Result<DavResponse> {
+        let Some(metadata) = self.metadata(&request.path).await? else {
+            return Ok(status(StatusCode::NOT_FOUND));
+        };
+        if self.is_locked_for(request, &request.path, false) {
+            return Ok(status(StatusCode::LOCKED));
+        }
+        let Some(updates) = parse_property_update(&request.body) else {
+            return Ok(status(StatusCode::BAD_REQUEST));
+        };
+        let names: Vec<(String, String)> = updates
+            .iter()
+            .map(|update| match update {
+                PropertyUpdate::Set(property) => {
+                    (property.namespace.clone(), property.name.clone())
+                }
+                PropertyUpdate::Remove { namespace, name } => (namespace.clone(), name.clone()),
+            })
+            .collect();
+        let protected = |(namespace, name): &(String, String)| {
+            namespace == DAV && LIVE_PROPERTIES.contains(&name.as_str())
+        };
+        let statuses: Vec<StatusCode> = if names.iter().any(protected) {
+            names
+                .iter()
+                .map(|name| match protected(name) {
+                    true => StatusCode::FORBIDDEN,
+                    false => StatusCode::FAILED_DEPENDENCY,
+                })
+                .collect()
+        } else {
+            let code = match self.fs.patch_properties(&request.path, updates).await {
+                Ok(()) => StatusCode::OK,
+                Err(err) if err.kind() == io::ErrorKind::Unsupported => StatusCode::FORBIDDEN,
+                Err(err) => return Err(err),
+            };
+            vec![code; names.len()]
+        };
+        let href = self.href(&request.path, metadata.is_collection);
+        let mut xml = format!("<d:response><d:href>{}</d:href>", escape(href.as_str()));
+        for code in [
+            StatusCode::OK,
+            StatusCode::FORBIDDEN,
+            StatusCode::FAILED_DEPENDENCY,
+        ] {
+            let mut props = String::new();
+            for (name, _) in names
+                .iter()
+                .zip(&statuses)
+                .filter(|(_, status)| **status == code)
+            {
+                write_prop(&mut props, &name.0, &name.1, "");
+            }
+            if !props.is_empty() {
+                write_propstat(&mut xml, &props, code);
+            }
+        }
+        xml.push_str("</d:response>");
+        Ok(multistatus(&xml))
+    }
+
+    async fn lock(&self, request: &Request) -> io::Result<DavResponse> {
+        let path = &request.path;
+        let timeout = lock_timeout(&request.headers);
+        if request.body.is_empty() {
+            // A refresh, which must submit the token of the existing lock.
+            let mut locks = self.locks.lock().unwrap();
+            return Ok(match locks.covering(path) {
+                Some((root, lock)) if request.submits(&lock.token) => {
+                    lock.refresh(timeout);
+                    self.lock_response(StatusCode::OK, &root, lock)
+                }
+                _ => status(StatusCode::PRECONDITION_FAILED),
+            });
+        }
+        let deep = request
+            .headers
+            .get("depth")
+            .is_none_or(|depth| depth.as_bytes() != b"0");
+        if !self.locks.lock().unwrap().tokens(path, deep).is_empty() {
+            return Ok(status(StatusCode::LOCKED));
+        }
+        let metadata = self.metadata(path).await?;
+        let collection = metadata.as_ref().is_some_and(|m| m.is_collection);
+        let status_code = if metadata.is_some() {
+            StatusCode::OK
+        } else if self.parent_is_collection(path).await? {
+            self.fs.write(path, Box::pin(tokio::io::empty())).await?;
+            StatusCode::CREATED
+        } else {
+            return Ok(status(StatusCode::CONFLICT));
+        };
+        let lock = ActiveLock::new(collection, deep, timeout);
+        let response = self.lock_response(status_code, path, &lock);
+        self.locks.lock().unwrap().insert(path, lock);
+        Ok(response)
+    }
+
+    /// The `DAV:activelock` element of `lock`, taken on `root`.
+    fn active_lock(&self, root: &str, lock: &ActiveLock) -> String {
+        let timeout = match lock.timeout {
+            Some(timeout) => format!("Second-{}", timeout.as_secs()),
+            None => String::from("Infinite"),
+        };
+        let depth = if lock.deep { "infinity" } else { "0" };
+        format!(
+            "<d:activelock><d:locktype><d:write/></d:locktype><d:lockscope><d:exclusive/></d:lockscope><d:depth>{depth}</d:depth><d:timeout>{timeout}</d:timeout><d:locktoken><d:href>{}</d:href></d:locktoken><d:lockroot><d:href>{}</d:href></d:lockroot></d:activelock>",
+            escape(lock.token.as_str()),
+            escape(self.href(root, lock.collection)),
+        )
+    }
+
+    fn lock_response(&self, status: StatusCode, root: &str, lock: &ActiveLock) -> DavResponse {
+        let xml = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?><d:prop xmlns:d="DAV:"><d:lockdiscovery>{}</d:lockdiscovery></d:prop>"#,
+            self.active_lock(root, lock),
+        );
+        let mut response = xml_response(status, xml);
+        let token = HeaderValue::from_str(&format!("<{}>", lock.token)).unwrap();
+        response.headers_mut().insert("lock-token", token);
+        response
+    }
+
+    fn unlock(&self, request: &Request) -> DavResponse {
+        let token = request
+            .headers
+            .get("lock-token")
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().trim_start_matches('<').trim_end_matches('>'));
+        let mut locks = self.locks.lock().unwrap();
+        let root = locks
+            .covering(&request.path)
+            .filter(|(_, lock)| Some(lock.token.as_str()) == token)
+            .map(|(root, _)| root);
+        match root {
+            Some(root) => {
+                locks.remove(&root);
+                status(StatusCode::NO_CONTENT)
+            }
+            None => status(StatusCode::CONFLICT),
+        }
+    }
+}
+
+/// What a `PROPFIND` asks for.
+enum PropFind {
+    /// All properties, for `allprop` or an empty body.
+    All,
+    /// The names of all properties.
+    Names,
+    /// The properties with these namespaces and names.
+    Props(Vec<(String, String)>),
+}
+
+impl PropFind {
+    /// Parses a `propfind` body; `None` if it is not one.
+    fn parse(body: &[u8]) -> Option<Self> {
+        if body.trim_ascii().is_empty() {
+            return Some(Self::All);
+        }
+        let root = Element::parse(std::str::from_utf8(body).ok()?).ok()?;
+        if !root.is("propfind") {
+            return None;
+        }
+        if let Some(prop) = root.child("prop") {
+            let names = prop.children.iter();
+            let names = names.map(|prop| (prop.namespace.clone(), prop.name.clone()));
+            return Some(Self::Props(names.collect()));
+        }
+        if root.child("propname").is_some() {
+            return Some(Self::Names);
+        }
+        root.child("allprop").map(|_| Self::All)
+    }
+}
+
+/// The changes of a `propertyupdate` body, in order; `None` if it is not
+/// one or has none.
+fn parse_property_update(body: &[u8]) -> Option<Vec<PropertyUpdate>> {
+    let root = Element::parse(std::str::from_utf8(body).ok()?).ok()?;
+    if !root.is("propertyupdate") {
+        return None;
+    }
+    let mut updates = Vec::new();
+    for instruction in &root.children {
+        let set = instruction.is("set");
+        if !set && !instruction.is("remove") {
+            continue;
+        }
+        for prop in instruction.children("prop").flat_map(|prop| &prop.children) {
+            let (namespace, name) = (prop.namespace.clone(), prop.name.clone());
+            updates.push(match set {
+                true => PropertyUpdate::Set(DeadProperty {
+                    namespace,
+                    name,
+                    value: prop.content(),
+                }),
+                false => PropertyUpdate::Remove { namespace, name },
+            });
+        }
+    }
+    (!updates.is_empty()).then_some(updates)
+}
@@ -0,0 +1,160 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+
+use tokio::io::AsyncSeekExt;
+
+use super::fs::{
+    apply_updates, join, DavFileSystem, DavMetadata, DavReader, DeadProperty, PropertyUpdate,
+};
+
+/// The suffix of files being written, which listings skip.
+const PARTIAL: &str = ".webdav-part";
+
+/// A [`DavFileSystem`] serving a directory of the local disk.
+///
+/// Request paths cannot climb above `root`, but symbolic links inside it
+/// are followed. Dead properties are kept in memory, shared by clones, and
+/// lost when the last clone is dropped.
+#[derive(Debug, Clone)]
+pub struct LocalFs {
+    root: PathBuf,
+    properties: Arc<Mutex<BTreeMap<String, Vec<DeadProperty>>>>,
+}
+
+impl LocalFs {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            properties: Default::default(),
+        }
+    }
+
+    /// The local path of a normalized path. Anything but plain names, e.g.
+    /// a Windows drive or `..`, is refused with `PermissionDenied`.
+    fn path(&self, path: &str) -> io::Result<PathBuf> {
+        let relative = Path::new(path.trim_start_matches('/'));
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("`{path}` is outside of the root"),
+            ));
+        }
+        Ok(self.root.join(relative))
+    }
+}
+
+impl DavFileSystem for LocalFs {
+    async fn metadata(&self, path: &str) -> io::Result<DavMetadata> {
+        let metadata = tokio::fs::metadata(self.path(path)?).await?;
+        let modified = metadata.modified()?;
+        let len = if metadata.is_dir() { 0 } else { metadata.len() };
+        let nanos = modified
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        Ok(DavMetadata {
+            is_collection: metadata.is_dir(),
+            len,
+            modified,
+            etag: format!("\"{len:x}-{nanos:x}\""),
+        })
+    }
+
+    async fn read_dir(&self, path: &str) -> io::Result<Vec<String>> {
+        let mut entries = tokio::fs::read_dir(self.path(path)?).await?;
+        let mut names = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            // Names that are not valid UTF-8 cannot be addressed by a URL.
+            if let Ok(name) = entry.file_name().into_string() {
+                if !name.ends_with(PARTIAL) {
+                    names.push(name);
+                }
+            }
+        }
+        Ok(names)
+    }
+
+    async fn read(&self, path: &str, offset: u64) -> io::Result<DavReader> {
+        let mut file = tokio::fs::File::open(self.path(path)?).await?;
+        file.seek(io::SeekFrom::Start(offset)).await?;
+        Ok(Box::pin(file))
+    }
+
+    /// Writes to a partial file next to `path`, which replaces it once
+    /// `content` is read to its end.
+    async fn write(&self, path: &str, mut content: DavReader) -> io::Result<()> {
+        let local = self.path(path)?;
+        let mut partial = local.as_os_str().to_owned();
+        partial.push(format!(".{:x}{PARTIAL}", crate::client::random()));
+        let partial = PathBuf::from(partial);
+        let written = async {
+            let mut file = tokio::fs::File::create(&partial).await?;
+            tokio::io::copy(&mut content, &mut file).await?;
+            file.sync_all().await
+        };
+        if let Err(err) = written.await {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(err);
+        }
+        tokio::fs::rename(&partial, local).await
+    }
+
+    async fn create_dir(&self, path: &str) -> io::Result<()> {
+        tokio::fs::create_dir(self.path(path)?).await
+    }
+
+    async fn remove(&self, path: &str) -> io::Result<()> {
+        let local = self.path(path)?;
+        if tokio::fs::metadata(&local).await?.is_dir() {
+            tokio::fs::remove_dir_all(local).await?;
+        } else {
+            tokio::fs::remove_file(local).await?;
+        }
+        let prefix = join(path, "");
+        self.properties
+            .lock()
+            .unwrap()
+            .retain(|key, _| key != path && !key.starts_with(&prefix));
+        Ok(())
+    }
+
+    async fn copy_file(&self, from: &str, to: &str) -> io::Result<()> {
+        tokio::fs::copy(self.path(from)?, self.path(to)?)
+            .await
+            .map(drop)
+    }
+
+    async fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        tokio::fs::rename(self.path(from)?, self.path(to)?).await?;
+        let mut properties = self.properties.lock().unwrap();
+        let prefix = join(from, "");
+        let moved: Vec<String> = properties
+            .keys()
+            .filter(|key| *key == from || key.starts_with(&prefix))
+            .cloned()
+            .collect();
+        for key in moved {
+            let value = properties.remove(&key).unwrap_or_default();
+            properties.insert(format!("{to}{}", &key[from.len()..]), value);
+        }
+        Ok(())
+    }
+
+    async fn properties(&self, path: &str) -> io::Result<Vec<DeadProperty>> {
+        let properties = self.properties.lock().unwrap();
+        Ok(properties.get(path).cloned().unwrap_or_default())
+    }
+
+    async fn patch_properties(&self, path: &str, updates: Vec<PropertyUpdate>) -> io::Result<()> {
+        tokio::fs::metadata(self.path(path)?).await?;
+        let mut properties = self.properties.lock().unwrap();
+        apply_updates(properties.entry(path.to_owned()).or_default(), updates);
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use super::fs::{join, parent};
+
+/// An exclusive write lock.
+#[derive(Debug, Clone)]
+pub(crate) struct ActiveLock {
+    pub(crate) token: String,
+    /// Whether the lock was taken on a collection.
+    pub(crate) collection: bool,
+    /// Whether the lock covers the members of a collection.
+    pub(crate) deep: bool,
+    /// `None` if the lock does not expire.
+    pub(crate) timeout: Option<Duration>,
+    expires: Option<Instant>,
+}
+
+impl ActiveLock {
+    pub(crate) fn new(collection: bool, deep: bool, timeout: Option<Duration>) -> Self {
+        let (high, low) = (crate::client::random(), crate::client::random());
+        let token = format!(
+            "urn:uuid:{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            high >> 32,
+            (high >> 16) & 0xffff,
+            high & 0xffff,
+            low >> 48,
+            low & 0xffff_ffff_ffff
+        );
+        Self {
+            token,
+            collection,
+            deep,
+            timeout,
+            expires: timeout.map(|timeout| Instant::now() + timeout),
+        }
+    }
+
+    pub(crate) fn refresh(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+        self.expires = timeout.map(|timeout| Instant::now() + timeout);
+    }
+}
+
+/// The active locks, keyed by the path they were taken on.
+#[derive(Debug, Default)]
+pub(crate) struct Locks(BTreeMap<String, ActiveLock>);
+
+impl Locks {
+    fn purge(&mut self) {
+        let now = Instant::now();
+        self.0
+            .retain(|_, lock| lock.expires.is_none_or(|expires| expires > now));
+    }
+
+    /// The lock covering `path`, and the path it was taken on.
+    pub(crate) fn covering(&mut self, path: &str) -> Option<(String, &mut ActiveLock)> {
+        self.purge();
+        let mut current = Some(path);
+        let mut root = None;
+        while let Some(candidate) = current {
+            let covers = |lock: &ActiveLock| lock.deep || candidate == path;
+            if self.0.get(candidate).is_some_and(covers) {
+                root = Some(candidate.to_owned());
+                break;
+            }
+            current = parent(candidate);
+        }
+        let root = root?;
+        let lock = self.0.get_mut(&root)?;
+        Some((root, lock))
+    }
+
+    /// The tokens of the locks on `path`, its parents, and, if `deep`, its
+    /// members.
+    pub(crate) fn tokens(&mut self, path: &str, deep: bool) -> Vec<String> {
+        let mut tokens: Vec<String> = self
+            .covering(path)
+            .map(|(_, lock)| lock.token.clone())
+            .into_iter()
+            .collect();
+        if deep {
+            let prefix = join(path, "");
+            tokens.extend(
+                self.0
+                    .range(prefix.clone()..)
+                    .take_while(|(key, _)| key.starts_with(&prefix))
+                    .map(|(_, lock)| lock.token.clone()),
+            );
+        }
+        tokens
+    }
+
+    pub(crate) fn insert(&mut self, path: &str, lock: ActiveLock) {
+        self.0.insert(path.to_owned(), lock);
+    }
+
+    pub(crate) fn remove(&mut self, path: &str) {
+        self.0.remove(path);
+    }
+
+    /// Removes the locks on `path` and its members.
+    pub(crate) fn remove_tree(&mut self, path: &str) {
+        let prefix = join(path, "");
+        self.0
+            .retain(|key, _| key != path && !key.starts_with(&prefix));
+    }
+}
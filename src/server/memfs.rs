@@ -0,0 +1,245 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use bytes::Bytes;
+use tokio::io::AsyncReadExt;
+
+use super::fs::{
+    apply_updates, join, normalize, parent, DavFileSystem, DavMetadata, DavReader, DeadProperty,
+    PropertyUpdate,
+};
+
+#[derive(Debug, Clone)]
+struct Entry {
+    /// The content of a file; `None` for a collection.
+    content: Option<Bytes>,
+    modified: SystemTime,
+    etag: String,
+    properties: Vec<DeadProperty>,
+}
+
+#[derive(Debug)]
+struct Tree {
+    entries: BTreeMap<String, Entry>,
+    version: u64,
+}
+
+impl Tree {
+    /// Creates or replaces the entry at `path`, keeping its properties.
+    fn insert(&mut self, path: &str, content: Option<Bytes>) {
+        self.version += 1;
+        let properties = self
+            .entries
+            .remove(path)
+            .map(|entry| entry.properties)
+            .unwrap_or_default();
+        let entry = Entry {
+            content,
+            modified: SystemTime::now(),
+            etag: format!("\"{:x}\"", self.version),
+            properties,
+        };
+        self.entries.insert(path.to_owned(), entry);
+    }
+
+    fn create_parents(&mut self, path: &str) {
+        let mut missing = Vec::new();
+        let mut current = parent(path);
+        while let Some(path) = current.filter(|path| !self.entries.contains_key(*path)) {
+            missing.push(path.to_owned());
+            current = parent(path);
+        }
+        for path in missing.iter().rev() {
+            self.insert(path, None);
+        }
+    }
+
+    /// The paths below the collection at `path`.
+    fn descendants(&self, path: &str) -> Vec<String> {
+        let prefix = join(path, "");
+        self.entries
+            .range(prefix.clone()..)
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .filter(|(key, _)| key.len() > prefix.len())
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+}
+
+/// A [`DavFileSystem`] kept in memory, e.g. for tests.
+///
+/// # Usage
+///
+/// ```
+/// use webdav_request::server::MemFs;
+///
+/// let fs = MemFs::new();
+/// fs.insert_file("/docs/a.txt", "hello");
+/// assert!(fs.exists("/docs"));
+/// assert_eq!(fs.file("/docs/a.txt").unwrap(), "hello");
+/// ```
+#[derive(Debug)]
+pub struct MemFs {
+    tree: Mutex<Tree>,
+}
+
+impl Default for MemFs {
+    fn default() -> Self {
+        let mut tree = Tree {
+            entries: BTreeMap::new(),
+            version: 0,
+        };
+        tree.insert("/", None);
+        Self {
+            tree: Mutex::new(tree),
+        }
+    }
+}
+
+impl MemFs {
+    /// An empty root collection.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates or replaces the file at `path`, creating missing parent
+    /// collections.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` climbs above the root.
+    pub fn insert_file(&self, path: &str, content: impl Into<Bytes>) {
+        let path = normalize(path).expect("path above the root");
+        let mut tree = self.tree.lock().unwrap();
+        tree.create_parents(&path);
+        tree.insert(&path, Some(content.into()));
+    }
+    /// Creates the collection at `path` and its missing parents.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` climbs above the root.
+    pub fn create_dir_all(&self, path: &str) {
+        let path = normalize(path).expect("path above the root");
+        let mut tree = self.tree.lock().unwrap();
+        tree.create_parents(&path);
+        if !tree.entries.contains_key(&path) {
+            tree.insert(&path, None);
+        }
+    }
+    /// The content of the file at `path`.
+    pub fn file(&self, path: &str) -> Option<Bytes> {
+        let path = normalize(path)?;
+        let tree = self.tree.lock().unwrap();
+        tree.entries.get(&path)?.content.clone()
+    }
+    /// Whether a file or collection exists at `path`.
+    pub fn exists(&self, path: &str) -> bool {
+        let Some(path) = normalize(path) else {
+            return false;
+        };
+        self.tree.lock().unwrap().entries.contains_key(&path)
+    }
+
+    fn entry(&self, path: &str) -> io::Result<Entry> {
+        let tree = self.tree.lock().unwrap();
+        tree.entries.get(path).cloned().ok_or_else(not_found)
+    }
+}
+
+fn not_found() -> io::Error {
+    io::ErrorKind::NotFound.into()
+}
+
+impl DavFileSystem for MemFs {
+    async fn metadata(&self, path: &str) -> io::Result<DavMetadata> {
+        let entry = self.entry(path)?;
+        Ok(DavMetadata {
+            is_collection: entry.content.is_none(),
+            len: entry
+                .content
+                .as_ref()
+                .map_or(0, |content| content.len() as u64),
+            modified: entry.modified,
+            etag: entry.etag,
+        })
+    }
+
+    async fn read_dir(&self, path: &str) -> io::Result<Vec<String>> {
+        let tree = self.tree.lock().unwrap();
+        let prefix = join(path, "");
+        Ok(tree
+            .descendants(path)
+            .into_iter()
+            .map(|member| member[prefix.len()..].to_owned())
+            .filter(|name| !name.contains('/'))
+            .collect())
+    }
+
+    async fn read(&self, path: &str, offset: u64) -> io::Result<DavReader> {
+        let content = self
+            .entry(path)?
+            .content
+            .ok_or_else(|| io::Error::other("a collection has no content"))?;
+        let offset = content.len().min(offset.try_into().unwrap_or(usize::MAX));
+        Ok(Box::pin(io::Cursor::new(content.slice(offset..))))
+    }
+
+    async fn write(&self, path: &str, mut content: DavReader) -> io::Result<()> {
+        let mut buf = Vec::new();
+        content.read_to_end(&mut buf).await?;
+        self.tree.lock().unwrap().insert(path, Some(buf.into()));
+        Ok(())
+    }
+
+    async fn create_dir(&self, path: &str) -> io::Result<()> {
+        let mut tree = self.tree.lock().unwrap();
+        if tree.entries.contains_key(path) {
+            return Err(io::ErrorKind::AlreadyExists.into());
+        }
+        tree.insert(path, None);
+        Ok(())
+    }
+
+    async fn remove(&self, path: &str) -> io::Result<()> {
+        let mut tree = self.tree.lock().unwrap();
+        for member in tree.descendants(path) {
+            tree.entries.remove(&member);
+        }
+        tree.entries.remove(path).map(drop).ok_or_else(not_found)
+    }
+
+    async fn copy_file(&self, from: &str, to: &str) -> io::Result<()> {
+        let content = self
+            .entry(from)?
+            .content
+            .ok_or_else(|| io::Error::other("a collection has no content"))?;
+        self.tree.lock().unwrap().insert(to, Some(content));
+        Ok(())
+    }
+
+    async fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        let mut tree = self.tree.lock().unwrap();
+        let mut paths = tree.descendants(from);
+        paths.insert(0, from.to_owned());
+        for path in paths {
+            let entry = tree.entries.remove(&path).ok_or_else(not_found)?;
+            tree.entries
+                .insert(format!("{to}{}", &path[from.len()..]), entry);
+        }
+        Ok(())
+    }
+
+    async fn properties(&self, path: &str) -> io::Result<Vec<DeadProperty>> {
+        Ok(self.entry(path)?.properties)
+    }
+
+    async fn patch_properties(&self, path: &str, updates: Vec<PropertyUpdate>) -> io::Result<()> {
+        let mut tree = self.tree.lock().unwrap();
+        let entry = tree.entries.get_mut(path).ok_or_else(not_found)?;
+        apply_updates(&mut entry.properties, updates);
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+//! An embeddable WebDAV server (RFC 4918, classes 1 and 2).
+//!
+//! [`DavService`] answers WebDAV requests over a [`DavFileSystem`], either
+//! [`LocalFs`], [`MemFs`] or a custom one. It is a hyper and a tower
+//! `Service`, so it can be served by hyper directly or mounted in a
+//! tower-based framework.
+//!
+//! Locks are exclusive write locks kept in memory by the service. `If`
+//! headers are evaluated against entity tags and lock tokens, and a request
+//! whose header holds for none of its lists is answered with `412`. Dead
+//! properties are stored by the file system: [`MemFs`] keeps them with its
+//! entries and [`LocalFs`] in memory, so they are lost on restart.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use hyper::server::conn::http1;
+//! use hyper_util::rt::TokioIo;
+//! use webdav_request::server::{DavService, LocalFs};
+//!
+//! # async fn serve() -> std::io::Result<()> {
+//! let service = DavService::new(LocalFs::new("/srv/files")).prefix("/dav");
+//! let listener = tokio::net::TcpListener::bind("127.0.0.1:8080").await?;
+//! loop {
+//!     let (stream, _) = listener.accept().await?;
+//!     let service = service.clone();
+//!     tokio::spawn(async move {
+//!         let _ = http1::Builder::new()
+//!             .serve_connection(TokioIo::new(stream), service)
+//!             .await;
+//!     });
+//! }
+//! # }
+//! ```
+mod body;
+mod condition;
+mod fs;
+mod handler;
+mod localfs;
+mod lock;
+mod memfs;
+
+use std::convert::Infallible;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use http_body_util::combinators::UnsyncBoxBody;
+use hyper::body::{Body, Incoming};
+
+#[cfg(feature = "testing")]
+pub(crate) use body::full;
+#[cfg(feature = "testing")]
+pub(crate) use fs::normalize;
+pub use fs::{DavFileSystem, DavMetadata, DavReader, DeadProperty, PropertyUpdate};
+pub use localfs::LocalFs;
+pub use memfs::MemFs;
+
+/// The body of a [`DavResponse`], buffered for XML and streamed for files.
+pub type DavBody = UnsyncBoxBody<Bytes, std::io::Error>;
+
+/// The response type of a [`DavService`].
+pub type DavResponse = hyper::Response<DavBody>;
+
+/// The default of [`DavService::max_body_size`], 64 MiB.
+const MAX_BODY_SIZE: usize = 64 << 20;
+
+/// A WebDAV request handler over the file system `F`.
+///
+/// Clones share the file system and the locks.
+pub struct DavService<F> {
+    fs: Arc<F>,
+    prefix: String,
+    max_body_size: usize,
+    locks: Arc<Mutex<lock::Locks>>,
+}
+
+impl<F> Clone for DavService<F> {
+    fn clone(&self) -> Self {
+        Self {
+            fs: self.fs.clone(),
+            prefix: self.prefix.clone(),
+            max_body_size: self.max_body_size,
+            locks: self.locks.clone(),
+        }
+    }
+}
+
+impl<F: DavFileSystem> DavService<F> {
+    pub fn new(fs: F) -> Self {
+        Self {
+            fs: Arc::new(fs),
+            prefix: String::from("/"),
+            max_body_size: MAX_BODY_SIZE,
+            locks: Default::default(),
+        }
+    }
+    /// Serves the file system under `prefix`, e.g. `/dav`. Requests for
+    /// other paths are answered with `404`.
+    pub fn prefix(self, prefix: &str) -> Self {
+        Self {
+            prefix: fs::normalize(prefix).unwrap_or_else(|| String::from("/")),
+            ..self
+        }
+    }
+    /// The largest XML request body read into memory, 64 MiB by default.
+    /// Larger bodies are answered with `413`. `PUT` bodies are streamed to
+    /// the file system and not limited.
+    pub fn max_body_size(self, max_body_size: usize) -> Self {
+        Self {
+            max_body_size,
+            ..self
+        }
+    }
+    pub fn fs(&self) -> &F {
+        &self.fs
+    }
+    /// Whether `path`, a path of the file system, is covered by a lock.
+    pub fn is_locked(&self, path: &str) -> bool {
+        let Some(path) = fs::normalize(path) else {
+            return false;
+        };
+        self.locks.lock().unwrap().covering(&path).is_some()
+    }
+
+    /// Answers `request`. A body that cannot be read is answered with
+    /// `400`, and one larger than [`Self::max_body_size`] with `413`.
+    pub async fn handle<B>(&self, request: hyper::Request<B>) -> DavResponse
+    where
+        B: Body + Send + 'static,
+        B::Data: Send,
+        B::Error: std::fmt::Display,
+    {
+        handler::handle(self, request).await
+    }
+
+    /// The file system path of a request path, or `None` outside the
+    /// prefix.
+    fn fs_path(&self, path: &str) -> Option<String> {
+        let path = fs::normalize(path)?;
+        if self.prefix == "/" {
+            return Some(path);
+        }
+        match path.strip_prefix(&self.prefix) {
+            Some("") => Some(String::from("/")),
+            Some(rest) if rest.starts_with('/') => Some(rest.to_owned()),
+            _ => None,
+        }
+    }
+}
+
+type ResponseFuture = Pin<Box<dyn Future<Output = Result<DavResponse, Infallible>> + Send>>;
+
+impl<F: DavFileSystem> hyper::service::Service<hyper::Request<Incoming>> for DavService<F> {
+    type Response = DavResponse;
+    type Error = Infallible;
+    type Future = ResponseFuture;
+
+    fn call(&self, request: hyper::Request<Incoming>) -> Self::Future {
+        let service = self.clone();
+        Box::pin(async move { Ok(service.handle(request).await) })
+    }
+}
+
+impl<F, B> tower_service::Service<hyper::Request<B>> for DavService<F>
+where
+    F: DavFileSystem,
+    B: Body + Send + 'static,
+    B::Data: Send,
+    B::Error: std::fmt::Display,
+{
+    type Response = DavResponse;
+    type Error = Infallible;
+    type Future = ResponseFuture;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: hyper::Request<B>) -> Self::Future {
+        let service = self.clone();
+        Box::pin(async move { Ok(service.handle(request).await) })
+    }
+}
@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use super::Conflict;
+
+/// How a file changed on both sides since the last sync is resolved.
+#[derive(Clone, Default)]
+pub enum ConflictPolicy {
+    /// Leaves the conflict to be reported.
+    #[default]
+    Skip,
+    /// Keeps both versions: the local file is renamed by inserting the
+    /// suffix before its extension, e.g. `a.conflict.txt` for `.conflict`,
+    /// and uploaded, and the remote file is downloaded.
+    KeepBoth(String),
+    /// Keeps the version modified last; the conflict is left if the times
+    /// are equal or unknown.
+    PreferNewer,
+    PreferLocal,
+    PreferRemote,
+    /// Asks the callback for each conflict.
+    Ask(Arc<dyn Fn(&Conflict) -> Resolution + Send + Sync>),
+}
+
+impl ConflictPolicy {
+    /// [`ConflictPolicy::Ask`] with `callback`.
+    pub fn ask(callback: impl Fn(&Conflict) -> Resolution + Send + Sync + 'static) -> Self {
+        Self::Ask(Arc::new(callback))
+    }
+
+    /// The resolution of `conflict` under this policy.
+    pub fn resolve(&self, conflict: &Conflict) -> Resolution {
+        match self {
+            Self::Skip => Resolution::Skip,
+            Self::KeepBoth(suffix) => Resolution::KeepBoth(suffix.clone()),
+            Self::PreferNewer => match conflict.remote_modified {
+                Some(remote) if remote > conflict.local_modified => Resolution::KeepRemote,
+                Some(remote) if remote < conflict.local_modified => Resolution::KeepLocal,
+                _ => Resolution::Skip,
+            },
+            Self::PreferLocal => Resolution::KeepLocal,
+            Self::PreferRemote => Resolution::KeepRemote,
+            Self::Ask(callback) => callback(conflict),
+        }
+    }
+}
+
+impl std::fmt::Debug for ConflictPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Skip => f.write_str("Skip"),
+            Self::KeepBoth(suffix) => f.debug_tuple("KeepBoth").field(suffix).finish(),
+            Self::PreferNewer => f.write_str("PreferNewer"),
+            Self::PreferLocal => f.write_str("PreferLocal"),
+            Self::PreferRemote => f.write_str("PreferRemote"),
+            Self::Ask(_) => f.write_str("Ask"),
+        }
+    }
+}
+
+/// The decision for one conflict.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolution {
+    Skip,
+    /// Keeps both versions, see [`ConflictPolicy::KeepBoth`].
+    KeepBoth(String),
+    /// Uploads the local file.
+    KeepLocal,
+    /// Downloads the remote file.
+    KeepRemote,
+}
@@ -0,0 +1,455 @@
+//! Synchronization of a local directory with a remote collection.
+//!
+//! A [`Synchronizer`] compares both trees with the state they had after the
+//! last sync, kept in a JSON file, and uploads, downloads, deletes and
+//! renames files accordingly. Files are compared by entity tag, size and
+//! modification time. A file changed on both sides since the last sync is a
+//! conflict, resolved according to the [`ConflictPolicy`]. A [`Filter`]
+//! limits the synced paths.
+//!
+//! An unchanged file moved on one side is moved on the other instead of
+//! being transferred again, and so is a directory whose synced files all
+//! moved with it and that gained no new entries.
+//!
+//! [`Synchronizer::plan`] computes a [`SyncPlan`] without changing anything,
+//! which [`Synchronizer::apply`] applies, e.g. after a review.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use webdav_request::sync::{ConflictPolicy, SyncMode, Synchronizer};
+//! use webdav_request::WebDAVClient;
+//!
+//! # async fn run() -> Result<(), webdav_request::error::Error> {
+//! let client = WebDAVClient::new("user", "password")?;
+//! let sync = Synchronizer::new(client, "/home/user/notes", "https://dav.example.com/notes/")?
+//!     .mode(SyncMode::TwoWay)
+//!     .conflict_policy(ConflictPolicy::KeepBoth(".conflict".to_owned()))
+//!     .concurrency(8);
+//! let plan = sync.plan().await?;
+//! for planned in &plan.actions {
+//!     println!("{:?} because {:?}", planned.action, planned.reason);
+//! }
+//! let report = sync.apply(plan).await?;
+//! for conflict in &report.conflicts {
+//!     println!("conflict: {}", conflict.path);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+mod conflict;
+mod plan;
+mod state;
+mod tree;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use reqwest::{IntoUrl, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinSet;
+
+use crate::client::check;
+use crate::client::walk::collection_url;
+use crate::error::Error;
+use crate::filter::Filter;
+use crate::transfer;
+use crate::{Method, WebDAVClient};
+pub use conflict::{ConflictPolicy, Resolution};
+pub use plan::{Conflict, ConflictKind, PlannedAction, Reason, Snapshot, SyncPlan};
+use state::{SyncState, SyncedEntry};
+use tree::{local_path, plain_path, remote_url};
+
+/// The name of the state file kept in the local directory by default.
+pub const STATE_FILE: &str = ".webdav-sync.json";
+
+/// The direction of a sync.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SyncMode {
+    /// Propagates changes in both directions.
+    #[default]
+    TwoWay,
+    /// Makes the remote collection a mirror of the local directory.
+    Upload,
+    /// Makes the local directory a mirror of the remote collection.
+    Download,
+}
+
+/// A change applied to one side. Paths are relative to the synced roots and
+/// separated by `/`, e.g. `docs/a.txt`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Upload(String),
+    Download(String),
+    /// Deletes a local file or directory.
+    DeleteLocal(String),
+    /// Deletes a remote file or collection.
+    DeleteRemote(String),
+    CreateLocalDir(String),
+    CreateRemoteDir(String),
+    RenameLocal {
+        from: String,
+        to: String,
+    },
+    RenameRemote {
+        from: String,
+        to: String,
+    },
+}
+
+impl Action {
+    /// The path the action creates, changes or deletes.
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Upload(path)
+            | Self::Download(path)
+            | Self::DeleteLocal(path)
+            | Self::DeleteRemote(path)
+            | Self::CreateLocalDir(path)
+            | Self::CreateRemoteDir(path) => path,
+            Self::RenameLocal { to, .. } | Self::RenameRemote { to, .. } => to,
+        }
+    }
+}
+
+/// The outcome of [`Synchronizer::apply`].
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub applied: Vec<Action>,
+    /// The conflicts left by the plan.
+    pub conflicts: Vec<Conflict>,
+    pub failed: Vec<(Action, Error)>,
+}
+
+impl SyncReport {
+    /// Whether both sides are in sync.
+    pub fn is_complete(&self) -> bool {
+        self.conflicts.is_empty() && self.failed.is_empty()
+    }
+}
+
+/// Syncs a local directory with a remote collection.
+pub struct Synchronizer {
+    client: WebDAVClient,
+    local: PathBuf,
+    remote: Url,
+    mode: SyncMode,
+    policy: ConflictPolicy,
+    filter: Filter,
+    state_file: Option<PathBuf>,
+    concurrency: usize,
+}
+
+impl Synchronizer {
+    /// Syncs the directory `local` with the collection at `remote`, in
+    /// [`SyncMode::TwoWay`].
+    pub fn new(
+        client: WebDAVClient,
+        local: impl Into<PathBuf>,
+        remote: impl IntoUrl,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            client,
+            local: local.into(),
+            remote: collection_url(remote.into_url()?),
+            mode: SyncMode::default(),
+            policy: ConflictPolicy::default(),
+            filter: Filter::default(),
+            state_file: None,
+            concurrency: 4,
+        })
+    }
+    pub fn mode(self, mode: SyncMode) -> Self {
+        Self { mode, ..self }
+    }
+    /// [`ConflictPolicy::Skip`] by default.
+    pub fn conflict_policy(self, policy: ConflictPolicy) -> Self {
+        Self { policy, ..self }
+    }
+    /// Only syncs the paths `filter` accepts. Paths it rejects are left
+    /// alone on both sides.
+    pub fn filter(self, filter: Filter) -> Self {
+        Self { filter, ..self }
+    }
+    /// Keeps the state in `path` instead of [`STATE_FILE`] in the local
+    /// directory.
+    pub fn state_file(self, path: impl Into<PathBuf>) -> Self {
+        Self {
+            state_file: Some(path.into()),
+            ..self
+        }
+    }
+    /// The number of files transferred or deleted at once, 4 by default.
+    pub fn concurrency(self, concurrency: usize) -> Self {
+        Self {
+            concurrency: concurrency.max(1),
+            ..self
+        }
+    }
+
+    fn state_path(&self) -> PathBuf {
+        self.state_file
+            .clone()
+            .unwrap_or_else(|| self.local.join(STATE_FILE))
+    }
+
+    async fn scan(
+        &self,
+        state_path: &Path,
+    ) -> Result<(tree::Tree<tree::LocalEntry>, tree::Tree<tree::RemoteEntry>), Error> {
+        let mut local = tree::scan_local(&self.local, state_path, &self.filter).await?;
+        let mut remote = tree::scan_remote(&self.client, &self.remote, &self.filter).await?;
+        tree::retain_accepted(&self.filter, &mut local, &mut remote);
+        Ok((local, remote))
+    }
+
+    /// Compares both trees with the last synced state, without changing
+    /// either. [`ConflictPolicy::Ask`] is called here.
+    pub async fn plan(&self) -> Result<SyncPlan, Error> {
+        let state_path = self.state_path();
+        let state = SyncState::load(&state_path).await?;
+        tokio::fs::create_dir_all(&self.local).await?;
+        let (local, remote) = self.scan(&state_path).await?;
+        Ok(plan::plan(self.mode, &self.policy, &local, &remote, &state))
+    }
+
+    /// Plans and applies a sync.
+    pub async fn run(&self) -> Result<SyncReport, Error> {
+        self.apply(self.plan().await?).await
+    }
+
+    /// Applies the actions of `plan` and saves the new state. Actions that
+    /// no longer apply, e.g. an upload of a file deleted since, fail, and
+    /// so do actions on paths that would leave either tree, e.g.
+    /// `../a.txt` in a plan edited after [`Synchronizer::plan`].
+    ///
+    /// A path that changed since the plan was made, or whose action was
+    /// removed from it, keeps its previous state, so that the next plan
+    /// picks it up again.
+    ///
+    /// Fails only if a tree cannot be listed or the state cannot be read or
+    /// saved; failed actions are reported in [`SyncReport::failed`].
+    pub async fn apply(&self, plan: SyncPlan) -> Result<SyncReport, Error> {
+        let state_path = self.state_path();
+        let state = SyncState::load(&state_path).await?;
+        let context = Arc::new(Context {
+            client: self.client.clone(),
+            local: self.local.clone(),
+            remote: self.remote.clone(),
+        });
+        let snapshot = plan.snapshot;
+        let mut report = SyncReport {
+            conflicts: plan.conflicts,
+            ..Default::default()
+        };
+        let mut tasks = JoinSet::new();
+        for PlannedAction { action, .. } in plan.actions {
+            if is_concurrent(&action) {
+                if tasks.len() >= self.concurrency {
+                    collect(&mut report, tasks.join_next().await);
+                }
+                let context = context.clone();
+                tasks.spawn(async move {
+                    let result = context.apply(&action).await;
+                    (action, result)
+                });
+                continue;
+            }
+            // Directories and renames are applied in order, once the
+            // transfers before them are done.
+            while let Some(joined) = tasks.join_next().await {
+                collect(&mut report, Some(joined));
+            }
+            let result = context.apply(&action).await;
+            collect(&mut report, Some(Ok((action, result))));
+        }
+        while let Some(joined) = tasks.join_next().await {
+            collect(&mut report, Some(joined));
+        }
+
+        let (local, remote) = self.scan(&state_path).await?;
+        next_state(&state, &snapshot, &report, &local, &remote)
+            .save(&state_path)
+            .await?;
+        Ok(report)
+    }
+}
+
+fn is_concurrent(action: &Action) -> bool {
+    matches!(
+        action,
+        Action::Upload(_) | Action::Download(_) | Action::DeleteLocal(_) | Action::DeleteRemote(_)
+    )
+}
+
+type Applied = Result<(Action, Result<(), Error>), tokio::task::JoinError>;
+
+fn collect(report: &mut SyncReport, joined: Option<Applied>) {
+    match joined {
+        Some(Ok((action, Ok(())))) => report.applied.push(action),
+        Some(Ok((action, Err(err)))) => report.failed.push((action, err)),
+        Some(Err(err)) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+        _ => (),
+    }
+}
+
+/// The state after a sync. A path present on both sides is synced if the
+/// plan found it in sync or an applied action wrote it, and each side no
+/// action wrote is as the plan saw it. Other paths, including conflicting
+/// and failed ones, keep their previous state while they exist.
+fn next_state(
+    previous: &SyncState,
+    snapshot: &Snapshot,
+    report: &SyncReport,
+    local: &tree::Tree<tree::LocalEntry>,
+    remote: &tree::Tree<tree::RemoteEntry>,
+) -> SyncState {
+    let mut unsettled: Vec<&str> = report
+        .conflicts
+        .iter()
+        .map(|conflict| conflict.path.as_str())
+        .collect();
+    for (action, _) in &report.failed {
+        unsettled.push(action.path());
+        if let Action::RenameLocal { from, .. } | Action::RenameRemote { from, .. } = action {
+            unsettled.push(from);
+        }
+    }
+    let is_unsettled = |path: &str| {
+        unsettled.iter().any(|unsettled| {
+            path == *unsettled
+                || path
+                    .strip_prefix(*unsettled)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    };
+    // The sides each applied action wrote, and the applied renames, which
+    // move everything below a renamed directory too.
+    let mut written: BTreeMap<&str, (bool, bool)> = BTreeMap::new();
+    let mut renames: Vec<(&str, &str, bool)> = Vec::new();
+    for action in &report.applied {
+        let (path, local, remote) = match action {
+            Action::Upload(path) | Action::CreateRemoteDir(path) => (path, false, true),
+            Action::Download(path) | Action::CreateLocalDir(path) => (path, true, false),
+            // A local rename keeps the size and modification time, while a
+            // remote one may change the entity tag.
+            Action::RenameLocal { from, to } => {
+                renames.push((from, to, false));
+                continue;
+            }
+            Action::RenameRemote { from, to } => {
+                renames.push((from, to, true));
+                continue;
+            }
+            Action::DeleteLocal(_) | Action::DeleteRemote(_) => continue,
+        };
+        let sides = written.entry(path).or_default();
+        *sides = (sides.0 || local, sides.1 || remote);
+    }
+
+    let mut state = SyncState::default();
+    for (path, l) in local {
+        let Some(r) = remote.get(path).filter(|r| r.dir == l.dir) else {
+            continue;
+        };
+        if is_unsettled(path) {
+            continue;
+        }
+        let rename = renames.iter().find(|(_, to, _)| {
+            path == to
+                || path
+                    .strip_prefix(to)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        });
+        let (local_written, remote_written) = match (written.get(path.as_str()), rename) {
+            (Some(sides), _) => *sides,
+            (None, Some((_, _, remote))) => (false, *remote),
+            (None, None) if snapshot.is_in_sync(path) => (false, false),
+            (None, None) => continue,
+        };
+        let seen = match rename {
+            Some((from, to, false)) => format!("{from}{}", &path[to.len()..]),
+            _ => path.clone(),
+        };
+        if (local_written || snapshot.local_unchanged(&seen, l))
+            && (remote_written || snapshot.remote_unchanged(path, r))
+        {
+            state.entries.insert(path.clone(), SyncedEntry::new(l, r));
+        }
+    }
+    for (path, synced) in &previous.entries {
+        let exists = local.contains_key(path) || remote.contains_key(path);
+        if (exists || is_unsettled(path)) && !state.entries.contains_key(path) {
+            state.entries.insert(path.clone(), synced.clone());
+        }
+    }
+    state
+}
+
+/// What the actions of a sync run against.
+struct Context {
+    client: WebDAVClient,
+    local: PathBuf,
+    remote: Url,
+}
+
+impl Context {
+    fn local(&self, path: &str) -> Result<PathBuf, Error> {
+        local_path(&self.local, path)
+    }
+    fn remote(&self, path: &str) -> Result<Url, Error> {
+        Ok(remote_url(&self.remote, plain_path(path)?, false))
+    }
+
+    async fn apply(&self, action: &Action) -> Result<(), Error> {
+        match action {
+            Action::Upload(path) => {
+                transfer::upload_file(&self.client, &self.local(path)?, self.remote(path)?)
+                    .await
+                    .map(drop)
+            }
+            Action::Download(path) => {
+                transfer::download_file(&self.client, self.remote(path)?, &self.local(path)?)
+                    .await
+                    .map(drop)
+            }
+            Action::DeleteLocal(path) => {
+                let local = self.local(path)?;
+                if tokio::fs::metadata(&local).await?.is_dir() {
+                    Ok(tokio::fs::remove_dir_all(local).await?)
+                } else {
+                    Ok(tokio::fs::remove_file(local).await?)
+                }
+            }
+            Action::DeleteRemote(path) => {
+                let response = self.client.delete(self.remote(path)?).send().await?;
+                check(Method::DELETE, response).await.map(drop)
+            }
+            Action::CreateLocalDir(path) => Ok(tokio::fs::create_dir_all(self.local(path)?).await?),
+            Action::CreateRemoteDir(path) => {
+                match self
+                    .client
+                    .mkcol(remote_url(&self.remote, plain_path(path)?, true))
+                    .await
+                {
+                    Err(err) if err.status() == Some(StatusCode::METHOD_NOT_ALLOWED) => Ok(()),
+                    result => result,
+                }
+            }
+            Action::RenameLocal { from, to } => {
+                Ok(tokio::fs::rename(self.local(from)?, self.local(to)?).await?)
+            }
+            Action::RenameRemote { from, to } => {
+                // A directory renamed locally is moved as a collection.
+                let dir = tokio::fs::metadata(self.local(to)?)
+                    .await
+                    .is_ok_and(|metadata| metadata.is_dir());
+                let from = remote_url(&self.remote, plain_path(from)?, dir);
+                let to = remote_url(&self.remote, plain_path(to)?, dir);
+                self.client.move_to(from, to, false).await
+            }
+        }
+    }
+}
@@ -0,0 +1,586 @@
+use std::collections::BTreeSet;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use super::conflict::{ConflictPolicy, Resolution};
+use super::state::SyncState;
+use super::tree::{LocalEntry, RemoteEntry, Tree};
+use super::{Action, SyncMode};
+
+/// The actions a sync would apply, in order, and the conflicts it leaves.
+///
+/// A plan can be inspected, stored as JSON with serde, and applied later
+/// with [`super::Synchronizer::apply`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncPlan {
+    pub actions: Vec<PlannedAction>,
+    pub conflicts: Vec<Conflict>,
+    /// The trees the plan was made from.
+    #[serde(default)]
+    pub snapshot: Snapshot,
+}
+
+/// Both trees as [`super::Synchronizer::plan`] scanned them. Applying a
+/// plan records a path as synced only if it did not change since.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    local: Tree<LocalEntry>,
+    remote: Tree<RemoteEntry>,
+    /// The paths on both sides that needed no action.
+    in_sync: BTreeSet<String>,
+}
+
+impl Snapshot {
+    /// Whether the plan found `path` in sync on both sides.
+    pub(crate) fn is_in_sync(&self, path: &str) -> bool {
+        self.in_sync.contains(path)
+    }
+
+    /// Whether the local `path` of the snapshot is still `local`.
+    pub(crate) fn local_unchanged(&self, path: &str, local: &LocalEntry) -> bool {
+        self.local.get(path).is_some_and(|seen| {
+            seen.dir == local.dir
+                && (seen.dir || (seen.len == local.len && seen.modified == local.modified))
+        })
+    }
+
+    /// Whether the remote `path` of the snapshot is still `remote`, by its
+    /// entity tag or else by its size and modification date.
+    pub(crate) fn remote_unchanged(&self, path: &str, remote: &RemoteEntry) -> bool {
+        self.remote.get(path).is_some_and(|seen| {
+            seen.dir == remote.dir
+                && (seen.dir
+                    || match (&seen.etag, &remote.etag) {
+                        (Some(seen), Some(etag)) => seen == etag,
+                        _ => seen.len == remote.len && seen.last_modified == remote.last_modified,
+                    })
+        })
+    }
+}
+
+impl SyncPlan {
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty() && self.conflicts.is_empty()
+    }
+}
+
+/// An [`Action`] and why it is planned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlannedAction {
+    pub action: Action,
+    pub reason: Reason,
+}
+
+/// Why an [`Action`] is planned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    /// Created on the side it is copied from since the last sync.
+    Created,
+    /// Changed on the side it is copied from since the last sync.
+    Changed,
+    /// Deleted on the other side since the last sync.
+    Deleted,
+    /// Moved on the other side since the last sync.
+    Moved,
+    /// Differs from the mirrored side.
+    Mirrored,
+    /// A directory deleted on the other side that still receives changes.
+    Parent,
+    /// Resolves a conflict according to the [`ConflictPolicy`].
+    Conflict,
+}
+
+/// A path that cannot be synced without a decision.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Conflict {
+    pub path: String,
+    pub kind: ConflictKind,
+    pub local_len: u64,
+    pub local_modified: SystemTime,
+    pub remote_len: u64,
+    pub remote_modified: Option<SystemTime>,
+    pub etag: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    /// The file was created or changed on both sides since the last sync.
+    Modified,
+    /// A file on one side and a directory on the other. No policy resolves
+    /// it.
+    Type,
+}
+
+impl Conflict {
+    fn new(path: &str, kind: ConflictKind, local: &LocalEntry, remote: &RemoteEntry) -> Self {
+        Self {
+            path: path.to_owned(),
+            kind,
+            local_len: local.len,
+            local_modified: local.modified,
+            remote_len: remote.len,
+            remote_modified: remote.modified(),
+            etag: remote.etag.clone(),
+        }
+    }
+}
+
+fn planned(action: Action, reason: Reason) -> PlannedAction {
+    PlannedAction { action, reason }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+fn inside(path: &str, dir: &str) -> bool {
+    path.strip_prefix(dir)
+        .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Whether the files look identical without a synced state: same size and
+/// the same modification time to the second.
+fn same_file(local: &LocalEntry, remote: &RemoteEntry) -> bool {
+    let seconds = |time: SystemTime| {
+        time.duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs())
+    };
+    local.len == remote.len
+        && remote
+            .modified()
+            .is_some_and(|modified| seconds(modified) == seconds(local.modified))
+}
+
+pub(crate) fn plan(
+    mode: SyncMode,
+    policy: &ConflictPolicy,
+    local: &Tree<LocalEntry>,
+    remote: &Tree<RemoteEntry>,
+    state: &SyncState,
+) -> SyncPlan {
+    let paths: BTreeSet<&String> = local
+        .keys()
+        .chain(remote.keys())
+        .chain(state.entries.keys())
+        .collect();
+    let mut plan = SyncPlan::default();
+    // A file on one side and a directory on the other, including everything
+    // below.
+    let mut clashes: Vec<&str> = Vec::new();
+    let mut actions = Vec::new();
+    for path in &paths {
+        if clashes.iter().any(|clash| inside(path, clash)) {
+            continue;
+        }
+        if let (Some(l), Some(r)) = (local.get(*path), remote.get(*path)) {
+            if l.dir != r.dir {
+                clashes.push(path);
+                plan.conflicts
+                    .push(Conflict::new(path, ConflictKind::Type, l, r));
+                continue;
+            }
+        }
+        let l = local.get(*path).filter(|entry| !entry.dir);
+        let r = remote.get(*path).filter(|entry| !entry.dir);
+        let synced = state.entries.get(*path).filter(|entry| !entry.dir);
+        let local_changed = match (l, synced) {
+            (Some(l), Some(synced)) => synced.local_changed(l),
+            (l, synced) => l.is_some() != synced.is_some(),
+        };
+        let remote_changed = match (r, synced) {
+            (Some(r), Some(synced)) => synced.remote_changed(r),
+            (r, synced) => r.is_some() != synced.is_some(),
+        };
+        let change = match synced {
+            Some(_) => Reason::Changed,
+            None => Reason::Created,
+        };
+        let path = path.to_string();
+        let action = match (mode, l, r) {
+            (_, None, None) => None,
+            (SyncMode::TwoWay, Some(l), Some(r)) => match (local_changed, remote_changed) {
+                (false, false) => None,
+                (true, false) => Some(planned(Action::Upload(path), Reason::Changed)),
+                (false, true) => Some(planned(Action::Download(path), Reason::Changed)),
+                _ if synced.is_none() && same_file(l, r) => None,
+                _ => {
+                    let conflict = Conflict::new(&path, ConflictKind::Modified, l, r);
+                    actions.extend(resolve(policy, &conflict, local, remote));
+                    if !actions.iter().any(|action| action.action.path() == path) {
+                        plan.conflicts.push(conflict);
+                    }
+                    None
+                }
+            },
+            // A change wins over a deletion on the other side.
+            (SyncMode::TwoWay, Some(_), None) if synced.is_some() && !local_changed => {
+                Some(planned(Action::DeleteLocal(path), Reason::Deleted))
+            }
+            (SyncMode::TwoWay, None, Some(_)) if synced.is_some() && !remote_changed => {
+                Some(planned(Action::DeleteRemote(path), Reason::Deleted))
+            }
+            (SyncMode::TwoWay, Some(_), None) => Some(planned(Action::Upload(path), change)),
+            (SyncMode::TwoWay, None, Some(_)) => Some(planned(Action::Download(path), change)),
+            (SyncMode::Upload, Some(_), None) => {
+                Some(planned(Action::Upload(path), Reason::Mirrored))
+            }
+            (SyncMode::Download, None, Some(_)) => {
+                Some(planned(Action::Download(path), Reason::Mirrored))
+            }
+            (SyncMode::Upload, None, Some(_)) => {
+                Some(planned(Action::DeleteRemote(path), Reason::Mirrored))
+            }
+            (SyncMode::Download, Some(_), None) => {
+                Some(planned(Action::DeleteLocal(path), Reason::Mirrored))
+            }
+            (_, Some(l), Some(r)) => {
+                let unchanged = match synced {
+                    Some(_) => !local_changed && !remote_changed,
+                    None => same_file(l, r),
+                };
+                let action = match (unchanged, mode) {
+                    (true, _) => None,
+                    (false, SyncMode::Download) => Some(Action::Download(path)),
+                    (false, _) => Some(Action::Upload(path)),
+                };
+                action.map(|action| planned(action, Reason::Mirrored))
+            }
+        };
+        actions.extend(action);
+    }
+    detect_renames(&mut actions, local, remote, state);
+
+    let mut created = Vec::new();
+    let mut deleted = Vec::new();
+    for path in paths {
+        if clashes
+            .iter()
+            .any(|clash| path == clash || inside(path, clash))
+        {
+            continue;
+        }
+        let is_dir = |entry: Option<bool>| entry.unwrap_or(false);
+        let l = is_dir(local.get(path).map(|entry| entry.dir));
+        let r = is_dir(remote.get(path).map(|entry| entry.dir));
+        let synced = is_dir(state.entries.get(path).map(|entry| entry.dir));
+        // A directory deleted on one side is kept if something is still
+        // synced into it from the other.
+        let kept = plan
+            .conflicts
+            .iter()
+            .any(|conflict| inside(&conflict.path, path))
+            || actions.iter().any(|planned| match &planned.action {
+                Action::DeleteLocal(_) | Action::DeleteRemote(_) => false,
+                action => inside(action.path(), path),
+            });
+        let reason = match (mode, synced) {
+            (SyncMode::TwoWay, true) => Reason::Parent,
+            (SyncMode::TwoWay, false) => Reason::Created,
+            _ => Reason::Mirrored,
+        };
+        let path = path.to_string();
+        match (mode, l, r) {
+            (SyncMode::TwoWay, true, false) if synced && !kept => {
+                deleted.push(planned(Action::DeleteLocal(path), Reason::Deleted))
+            }
+            (SyncMode::TwoWay, false, true) if synced && !kept => {
+                deleted.push(planned(Action::DeleteRemote(path), Reason::Deleted))
+            }
+            (SyncMode::TwoWay | SyncMode::Upload, true, false) => {
+                created.push(planned(Action::CreateRemoteDir(path), reason))
+            }
+            (SyncMode::TwoWay | SyncMode::Download, false, true) => {
+                created.push(planned(Action::CreateLocalDir(path), reason))
+            }
+            (SyncMode::Upload, false, true) => {
+                deleted.push(planned(Action::DeleteRemote(path), reason))
+            }
+            (SyncMode::Download, true, false) => {
+                deleted.push(planned(Action::DeleteLocal(path), reason))
+            }
+            _ => (),
+        }
+    }
+
+    // Deleting a directory deletes everything below it.
+    let subsumed = |planned: &PlannedAction| {
+        deleted
+            .iter()
+            .any(|dir| match (&dir.action, &planned.action) {
+                (Action::DeleteLocal(dir), Action::DeleteLocal(path))
+                | (Action::DeleteRemote(dir), Action::DeleteRemote(path)) => inside(path, dir),
+                _ => false,
+            })
+    };
+    actions.retain(|planned| !subsumed(planned));
+    let mut deleted_dirs: Vec<PlannedAction> = deleted
+        .iter()
+        .filter(|planned| !subsumed(planned))
+        .cloned()
+        .collect();
+    detect_dir_renames(
+        &mut created,
+        &mut actions,
+        &mut deleted_dirs,
+        local,
+        remote,
+        state,
+    );
+
+    let rank = |planned: &PlannedAction| match planned.action {
+        Action::RenameLocal { .. } | Action::RenameRemote { .. } => 0,
+        Action::Upload(_) | Action::Download(_) => 1,
+        _ => 2,
+    };
+    actions.sort_by_key(rank);
+    plan.actions = created;
+    plan.actions.extend(actions);
+    plan.actions.extend(deleted_dirs.into_iter().rev());
+
+    let planned: BTreeSet<&str> = plan
+        .actions
+        .iter()
+        .flat_map(|planned| match &planned.action {
+            Action::RenameLocal { from, to } | Action::RenameRemote { from, to } => {
+                vec![from.as_str(), to.as_str()]
+            }
+            action => vec![action.path()],
+        })
+        .chain(plan.conflicts.iter().map(|conflict| conflict.path.as_str()))
+        .collect();
+    let in_sync = local
+        .iter()
+        .filter(|(path, l)| remote.get(*path).is_some_and(|r| r.dir == l.dir))
+        .map(|(path, _)| path)
+        .filter(|path| !planned.contains(path.as_str()))
+        .cloned()
+        .collect();
+    plan.snapshot = Snapshot {
+        local: local.clone(),
+        remote: remote.clone(),
+        in_sync,
+    };
+    plan
+}
+
+/// The actions resolving a conflict, or none to leave it.
+fn resolve(
+    policy: &ConflictPolicy,
+    conflict: &Conflict,
+    local: &Tree<LocalEntry>,
+    remote: &Tree<RemoteEntry>,
+) -> Vec<PlannedAction> {
+    let path = conflict.path.clone();
+    let actions = match policy.resolve(conflict) {
+        Resolution::Skip => vec![],
+        Resolution::KeepLocal => vec![Action::Upload(path)],
+        Resolution::KeepRemote => vec![Action::Download(path)],
+        Resolution::KeepBoth(suffix) => {
+            let free = |candidate: &String| {
+                !local.contains_key(candidate) && !remote.contains_key(candidate)
+            };
+            let copy = (0..)
+                .map(|i| match i {
+                    0 => with_suffix(&path, &suffix),
+                    i => with_suffix(&path, &format!("{suffix}-{i}")),
+                })
+                .find(free)
+                .unwrap();
+            vec![
+                Action::RenameLocal {
+                    from: path.clone(),
+                    to: copy.clone(),
+                },
+                Action::Upload(copy),
+                Action::Download(path),
+            ]
+        }
+    };
+    actions
+        .into_iter()
+        .map(|action| planned(action, Reason::Conflict))
+        .collect()
+}
+
+/// Inserts `suffix` before the extension of the file name, e.g.
+/// `docs/a.conflict.txt`.
+fn with_suffix(path: &str, suffix: &str) -> String {
+    let (dir, name) = match path.rsplit_once('/') {
+        Some((dir, name)) => (format!("{dir}/"), name),
+        None => (String::new(), path),
+    };
+    match name.rsplit_once('.').filter(|(stem, _)| !stem.is_empty()) {
+        Some((stem, extension)) => format!("{dir}{stem}{suffix}.{extension}"),
+        None => format!("{dir}{name}{suffix}"),
+    }
+}
+
+/// Turns a deletion and a transfer of the same unchanged file into a rename.
+fn detect_renames(
+    actions: &mut Vec<PlannedAction>,
+    local: &Tree<LocalEntry>,
+    remote: &Tree<RemoteEntry>,
+    state: &SyncState,
+) {
+    let mut renames = Vec::new();
+    for (i, action) in actions.iter().map(|planned| &planned.action).enumerate() {
+        let (Action::DeleteRemote(from) | Action::DeleteLocal(from)) = action else {
+            continue;
+        };
+        let Some(synced) = state.entries.get(from).filter(|entry| !entry.dir) else {
+            continue;
+        };
+        let unchanged = match action {
+            Action::DeleteRemote(_) => remote.get(from).is_some_and(|r| !synced.remote_changed(r)),
+            _ => local.get(from).is_some_and(|l| !synced.local_changed(l)),
+        };
+        if !unchanged {
+            continue;
+        }
+        let targets = actions.iter().enumerate().filter(|(j, candidate)| {
+            !renames.iter().any(|(_, used)| used == j)
+                && match (action, &candidate.action) {
+                    (Action::DeleteRemote(_), Action::Upload(to)) => {
+                        !state.entries.contains_key(to)
+                            && !remote.contains_key(to)
+                            && local.get(to).is_some_and(|l| {
+                                l.len == synced.local_len && l.modified == synced.local_modified()
+                            })
+                    }
+                    (Action::DeleteLocal(_), Action::Download(to)) => {
+                        !state.entries.contains_key(to)
+                            && !local.contains_key(to)
+                            && synced.etag.is_some()
+                            && remote.get(to).is_some_and(|r| r.etag == synced.etag)
+                    }
+                    _ => false,
+                }
+        });
+        // Files of the same size are told apart by their names, as when a
+        // directory is renamed.
+        let targets: Vec<usize> = targets.map(|(j, _)| j).collect();
+        let same_name = |j: &&usize| file_name(actions[**j].action.path()) == file_name(from);
+        let target = targets.iter().find(same_name).or(targets.first());
+        if let Some(&j) = target {
+            renames.push((i, j));
+        }
+    }
+    let mut replaced = Vec::new();
+    for &(i, j) in &renames {
+        let from = actions[i].action.path().to_owned();
+        let to = actions[j].action.path().to_owned();
+        let action = match actions[i].action {
+            Action::DeleteRemote(_) => Action::RenameRemote { from, to },
+            _ => Action::RenameLocal { from, to },
+        };
+        replaced.push(planned(action, Reason::Moved));
+    }
+    let used: Vec<usize> = renames.iter().flat_map(|&(i, j)| [i, j]).collect();
+    let mut index = 0;
+    actions.retain(|_| {
+        index += 1;
+        !used.contains(&(index - 1))
+    });
+    actions.extend(replaced);
+}
+
+/// Turns a deleted directory whose synced content all reappears under a
+/// created directory, file by file renamed, into a rename of the directory.
+fn detect_dir_renames(
+    created: &mut Vec<PlannedAction>,
+    actions: &mut Vec<PlannedAction>,
+    deleted: &mut Vec<PlannedAction>,
+    local: &Tree<LocalEntry>,
+    remote: &Tree<RemoteEntry>,
+    state: &SyncState,
+) {
+    let mut i = 0;
+    while i < deleted.len() {
+        // The directory was renamed on the side it still exists on.
+        let (from, renamed_locally) = match &deleted[i] {
+            PlannedAction {
+                action: Action::DeleteRemote(from),
+                reason: Reason::Deleted,
+            } => (from.clone(), true),
+            PlannedAction {
+                action: Action::DeleteLocal(from),
+                reason: Reason::Deleted,
+            } => (from.clone(), false),
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        let is_dir = |path: &str| match renamed_locally {
+            true => local.get(path).is_some_and(|entry| entry.dir),
+            false => remote.get(path).is_some_and(|entry| entry.dir),
+        };
+        let is_file_rename = |old: &str, new: &str| {
+            actions.iter().any(|planned| match &planned.action {
+                Action::RenameRemote { from, to } if renamed_locally => from == old && to == new,
+                Action::RenameLocal { from, to } if !renamed_locally => from == old && to == new,
+                _ => false,
+            })
+        };
+        let renamed_to = |to: &str| {
+            let synced: Vec<_> = state
+                .entries
+                .iter()
+                .filter(|(path, _)| inside(path, &from))
+                .collect();
+            let mut entries: Vec<(&String, bool)> = match renamed_locally {
+                true => local
+                    .iter()
+                    .map(|(path, entry)| (path, entry.dir))
+                    .collect(),
+                false => remote
+                    .iter()
+                    .map(|(path, entry)| (path, entry.dir))
+                    .collect(),
+            };
+            entries.retain(|(path, _)| inside(path, to));
+            synced.iter().any(|(_, entry)| !entry.dir)
+                && synced.iter().all(|(path, entry)| {
+                    let new = format!("{to}{}", &path[from.len()..]);
+                    match entry.dir {
+                        true => is_dir(&new),
+                        false => is_file_rename(path, &new),
+                    }
+                })
+                && entries.iter().all(|(path, dir)| {
+                    let old = format!("{from}{}", &path[to.len()..]);
+                    state
+                        .entries
+                        .get(&old)
+                        .is_some_and(|entry| entry.dir == *dir)
+                })
+        };
+        let to = created.iter().find_map(|planned| match &planned.action {
+            Action::CreateRemoteDir(to) if renamed_locally && renamed_to(to) => Some(to.clone()),
+            Action::CreateLocalDir(to) if !renamed_locally && renamed_to(to) => Some(to.clone()),
+            _ => None,
+        });
+        let Some(to) = to else {
+            i += 1;
+            continue;
+        };
+        deleted.remove(i);
+        created.retain(|planned| {
+            let path = planned.action.path();
+            path != to && !inside(path, &to)
+        });
+        actions.retain(|planned| match &planned.action {
+            Action::RenameRemote { from: old, .. } if renamed_locally => !inside(old, &from),
+            Action::RenameLocal { from: old, .. } if !renamed_locally => !inside(old, &from),
+            _ => true,
+        });
+        let action = match renamed_locally {
+            true => Action::RenameRemote { from, to },
+            false => Action::RenameLocal { from, to },
+        };
+        actions.push(planned(action, Reason::Moved));
+    }
+}
@@ -0,0 +1,83 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::tree::{LocalEntry, RemoteEntry, PARTIAL};
+
+/// A path as both sides had it after the last sync.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct SyncedEntry {
+    pub(crate) dir: bool,
+    pub(crate) local_len: u64,
+    /// Nanoseconds since the Unix epoch.
+    pub(crate) local_modified: u64,
+    pub(crate) etag: Option<String>,
+    pub(crate) remote_len: u64,
+    pub(crate) last_modified: String,
+}
+
+impl SyncedEntry {
+    pub(crate) fn new(local: &LocalEntry, remote: &RemoteEntry) -> Self {
+        Self {
+            dir: local.dir,
+            local_len: local.len,
+            local_modified: nanos(local.modified),
+            etag: remote.etag.clone(),
+            remote_len: remote.len,
+            last_modified: remote.last_modified.clone(),
+        }
+    }
+
+    /// Whether the local file changed since the last sync.
+    pub(crate) fn local_changed(&self, local: &LocalEntry) -> bool {
+        self.local_len != local.len || self.local_modified != nanos(local.modified)
+    }
+
+    /// Whether the remote file changed since the last sync, by its entity
+    /// tag or else by its size and modification date.
+    pub(crate) fn remote_changed(&self, remote: &RemoteEntry) -> bool {
+        match (&self.etag, &remote.etag) {
+            (Some(synced), Some(etag)) => synced != etag,
+            _ => self.remote_len != remote.len || self.last_modified != remote.last_modified,
+        }
+    }
+
+    pub(crate) fn local_modified(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_nanos(self.local_modified)
+    }
+}
+
+fn nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_nanos() as u64)
+}
+
+/// The state database, stored as JSON.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct SyncState {
+    pub(crate) entries: BTreeMap<String, SyncedEntry>,
+}
+
+impl SyncState {
+    /// Loads the state at `path`; empty if the file does not exist.
+    pub(crate) async fn load(path: &Path) -> io::Result<Self> {
+        match tokio::fs::read(path).await {
+            Ok(json) => serde_json::from_slice(&json)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Replaces the file at `path` atomically.
+    pub(crate) async fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_vec_pretty(self)?;
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(PARTIAL);
+        tokio::fs::write(&partial, json).await?;
+        tokio::fs::rename(&partial, path).await
+    }
+}
@@ -0,0 +1,92 @@
+use std::path::Path;
+use std::time::SystemTime;
+
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+use super::STATE_FILE;
+use crate::client::walk::walk;
+use crate::error::Error;
+use crate::filter::Filter;
+use crate::transfer;
+pub(crate) use crate::transfer::{local_path, plain_path, remote_url, LocalEntry, Tree, PARTIAL};
+use crate::WebDAVClient;
+
+/// A resource of the remote tree.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct RemoteEntry {
+    pub(crate) dir: bool,
+    pub(crate) len: u64,
+    pub(crate) etag: Option<String>,
+    pub(crate) last_modified: String,
+}
+
+impl RemoteEntry {
+    pub(crate) fn modified(&self) -> Option<SystemTime> {
+        httpdate::parse_http_date(&self.last_modified).ok()
+    }
+}
+
+/// Lists the local tree below `root`, skipping state files, `skip` and the
+/// paths `filter` excludes.
+pub(crate) async fn scan_local(
+    root: &Path,
+    skip: &Path,
+    filter: &Filter,
+) -> Result<Tree<LocalEntry>, Error> {
+    transfer::scan_local(root, filter, |path, name| {
+        path == skip || name == STATE_FILE
+    })
+    .await
+}
+
+/// Lists the remote tree below `root`, skipping state files and the paths
+/// `filter` excludes.
+pub(crate) async fn scan_remote(
+    client: &WebDAVClient,
+    root: &Url,
+    filter: &Filter,
+) -> Result<Tree<RemoteEntry>, Error> {
+    let mut tree = Tree::new();
+    walk(client, root, |path, child| {
+        let skipped = path == STATE_FILE
+            || path.ends_with(&format!("/{STATE_FILE}"))
+            || filter.excludes(&path, child.is_collection);
+        if !skipped {
+            let remote = RemoteEntry {
+                dir: child.is_collection,
+                len: child.len,
+                etag: child.etag,
+                last_modified: child.last_modified,
+            };
+            tree.insert(path, remote);
+        }
+        !skipped
+    })
+    .await?;
+    Ok(tree)
+}
+
+/// Removes the files `filter` rejects on either side, so that a file
+/// rejected on one side only is not taken for deleted.
+pub(crate) fn retain_accepted(
+    filter: &Filter,
+    local: &mut Tree<LocalEntry>,
+    remote: &mut Tree<RemoteEntry>,
+) {
+    let rejected: Vec<String> = local
+        .iter()
+        .filter(|(path, l)| !filter.accepts(path, l.dir, l.len, Some(l.modified)))
+        .map(|(path, _)| path.clone())
+        .chain(
+            remote
+                .iter()
+                .filter(|(path, r)| !filter.accepts(path, r.dir, r.len, r.modified()))
+                .map(|(path, _)| path.clone()),
+        )
+        .collect();
+    for path in rejected {
+        local.remove(&path);
+        remote.remove(&path);
+    }
+}
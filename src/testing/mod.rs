@@ -0,0 +1,297 @@
+//! An in-process WebDAV server for testing code built on this crate.
+//!
+//! [`MockServer`] serves an in-memory tree on `127.0.0.1` and answers
+//! `OPTIONS`, `GET`, `HEAD`, `PUT`, `DELETE`, `MKCOL`, `COPY`, `MOVE`,
+//! `PROPFIND`, `PROPPATCH`, `LOCK` and `UNLOCK`. Credentials are accepted but not
+//! checked. [`Fault`]s inject latency, error statuses and dropped
+//! connections, and [`MockServer::requests`] lists what was received.
+//!
+//! # Usage
+//!
+//! ```
+//! use webdav_request::testing::{Fault, MockServer};
+//! use webdav_request::{StatusCode, WebDAVClient};
+//!
+//! # tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap().block_on(async {
+//! let server = MockServer::start().await.unwrap();
+//! server.put_file("/docs/a.txt", "hello");
+//!
+//! let client = WebDAVClient::anonymous().unwrap();
+//! let collection = client.list(server.url_for("/docs/")).await.unwrap();
+//! assert_eq!(collection.children[0].name(), "a.txt");
+//!
+//! server.inject(
+//!     Fault::new()
+//!         .path("/docs/a.txt")
+//!         .status(StatusCode::SERVICE_UNAVAILABLE)
+//!         .times(1),
+//! );
+//! let response = client.get(server.url_for("/docs/a.txt")).send().await.unwrap();
+//! assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+//! let response = client.get(server.url_for("/docs/a.txt")).send().await.unwrap();
+//! assert_eq!(response.text().await.unwrap(), "hello");
+//! # });
+//! ```
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::BodyExt;
+use hyper::{body::Incoming, server::conn::http1, service::service_fn};
+use hyper_util::rt::TokioIo;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{StatusCode, Url};
+use tokio::{net::TcpListener, task::JoinHandle};
+
+use crate::server::{full, normalize, DavResponse, DavService, MemFs};
+
+/// A failure injected into the responses of a [`MockServer`].
+///
+/// A fault applies to every request unless restricted with
+/// [`Fault::method`], [`Fault::path`] or [`Fault::times`]. Without a status
+/// or disconnect it only delays the request.
+#[derive(Debug, Clone, Default)]
+pub struct Fault {
+    method: Option<reqwest::Method>,
+    path: Option<String>,
+    times: Option<usize>,
+    delay: Option<Duration>,
+    status: Option<StatusCode>,
+    headers: HeaderMap,
+    body: Bytes,
+    disconnect: bool,
+}
+
+impl Fault {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn method(self, method: crate::Method) -> Self {
+        Self {
+            method: Some(method.convert()),
+            ..self
+        }
+    }
+    /// Restricts the fault to requests for `path`, e.g. `/dir/file.txt`.
+    pub fn path(self, path: &str) -> Self {
+        Self {
+            path: normalize(path),
+            ..self
+        }
+    }
+    /// Applies the fault to the next `times` matching requests only.
+    pub fn times(self, times: usize) -> Self {
+        Self {
+            times: Some(times),
+            ..self
+        }
+    }
+    /// Delays the response by `delay`.
+    pub fn delay(self, delay: Duration) -> Self {
+        Self {
+            delay: Some(delay),
+            ..self
+        }
+    }
+    /// Answers with `status` instead of handling the request. The body is
+    /// empty unless set with [`Fault::body`].
+    pub fn status(self, status: StatusCode) -> Self {
+        Self {
+            status: Some(status),
+            ..self
+        }
+    }
+    /// Adds a header to the response of [`Fault::status`], e.g.
+    /// `Retry-After` or `Location`.
+    pub fn header(mut self, key: HeaderName, val: HeaderValue) -> Self {
+        self.headers.insert(key, val);
+        self
+    }
+    /// The body of the response of [`Fault::status`], e.g. a
+    /// `DAV:multistatus` for a method the server does not implement.
+    pub fn body(self, body: impl Into<Bytes>) -> Self {
+        Self {
+            body: body.into(),
+            ..self
+        }
+    }
+    /// Closes the connection without a response.
+    pub fn disconnect(self) -> Self {
+        Self {
+            disconnect: true,
+            ..self
+        }
+    }
+
+    fn matches(&self, method: &reqwest::Method, path: &str) -> bool {
+        self.method.as_ref().is_none_or(|m| m == method)
+            && self.path.as_deref().is_none_or(|p| p == path)
+    }
+}
+
+/// A request received by a [`MockServer`].
+#[derive(Debug, Clone)]
+pub struct ReceivedRequest {
+    pub method: reqwest::Method,
+    /// The decoded and normalized path, e.g. `/dir/file.txt`.
+    pub path: String,
+    pub headers: HeaderMap,
+    /// The body of a request answered by a [`Fault::status`]; empty
+    /// otherwise, since the file system reads the others.
+    pub body: Bytes,
+}
+
+struct State {
+    service: DavService<MemFs>,
+    faults: Mutex<Vec<Fault>>,
+    requests: Mutex<Vec<ReceivedRequest>>,
+}
+
+impl State {
+    /// The first fault matching a request, counting it as used.
+    fn take_fault(&self, method: &reqwest::Method, path: &str) -> Option<Fault> {
+        let mut faults = self.faults.lock().unwrap();
+        let index = faults
+            .iter()
+            .position(|fault| fault.matches(method, path))?;
+        let fault = &mut faults[index];
+        match &mut fault.times {
+            Some(0 | 1) => Some(faults.remove(index)),
+            Some(times) => {
+                *times -= 1;
+                Some(fault.clone())
+            }
+            None => Some(fault.clone()),
+        }
+    }
+}
+
+/// A WebDAV server on a random port of `127.0.0.1`, stopped on drop.
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<State>,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Starts a server with an empty root collection. Must be called from
+    /// within a tokio runtime, which runs the server.
+    pub async fn start() -> std::io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(State {
+            service: DavService::new(MemFs::new()),
+            faults: Default::default(),
+            requests: Default::default(),
+        });
+        let task = tokio::spawn(serve(listener, state.clone()));
+        Ok(Self { addr, state, task })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+    /// The URL of the root collection, e.g. `http://127.0.0.1:41234/`.
+    pub fn url(&self) -> Url {
+        Url::parse(&format!("http://{}/", self.addr)).unwrap()
+    }
+    /// The URL of `path` on the server.
+    pub fn url_for(&self, path: &str) -> Url {
+        self.url().join(path.trim_start_matches('/')).unwrap()
+    }
+
+    /// Creates or replaces the file at `path`, creating missing parent
+    /// collections.
+    pub fn put_file(&self, path: &str, content: impl Into<Bytes>) {
+        self.state.service.fs().insert_file(path, content);
+    }
+    /// Creates the collection at `path` and its missing parents.
+    pub fn create_dir(&self, path: &str) {
+        self.state.service.fs().create_dir_all(path);
+    }
+    /// The content of the file at `path`.
+    pub fn file(&self, path: &str) -> Option<Bytes> {
+        self.state.service.fs().file(path)
+    }
+    /// Whether a file or collection exists at `path`.
+    pub fn exists(&self, path: &str) -> bool {
+        self.state.service.fs().exists(path)
+    }
+    /// Whether `path` or one of its parents is locked.
+    pub fn is_locked(&self, path: &str) -> bool {
+        self.state.service.is_locked(path)
+    }
+
+    /// Adds a fault. Faults are matched in the order they were added.
+    pub fn inject(&self, fault: Fault) {
+        self.state.faults.lock().unwrap().push(fault);
+    }
+    pub fn clear_faults(&self) {
+        self.state.faults.lock().unwrap().clear();
+    }
+    /// The requests received so far, in order, including those answered by
+    /// a fault.
+    pub fn requests(&self) -> Vec<ReceivedRequest> {
+        self.state.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn serve(listener: TcpListener, state: Arc<State>) {
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let state = state.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |request| handle(state.clone(), request));
+            let _ = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await;
+        });
+    }
+}
+
+/// Answers `request`, applying the first matching fault. An injected
+/// disconnect is returned as an error, which makes hyper drop the
+/// connection.
+async fn handle(
+    state: Arc<State>,
+    request: hyper::Request<Incoming>,
+) -> Result<DavResponse, std::io::Error> {
+    let path = normalize(request.uri().path()).unwrap_or_default();
+    let recorded = {
+        let mut requests = state.requests.lock().unwrap();
+        requests.push(ReceivedRequest {
+            method: request.method().clone(),
+            path: path.clone(),
+            headers: request.headers().clone(),
+            body: Bytes::new(),
+        });
+        requests.len() - 1
+    };
+    if let Some(fault) = state.take_fault(request.method(), &path) {
+        if let Some(delay) = fault.delay {
+            tokio::time::sleep(delay).await;
+        }
+        if fault.disconnect {
+            return Err(std::io::Error::other("injected disconnect"));
+        }
+        if let Some(status) = fault.status {
+            let body = request.into_body().collect().await;
+            let body = body.map_err(std::io::Error::other)?.to_bytes();
+            state.requests.lock().unwrap()[recorded].body = body;
+            let mut response = DavResponse::new(full(fault.body));
+            *response.status_mut() = status;
+            response.headers_mut().extend(fault.headers);
+            return Ok(response);
+        }
+    }
+    Ok(state.service.handle(request).await)
+}
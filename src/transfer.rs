@@ -0,0 +1,453 @@
+//! Recursive upload, download and copy of directory trees.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use webdav_request::filter::Filter;
+//! use webdav_request::WebDAVClient;
+//!
+//! # async fn run() -> Result<(), webdav_request::error::Error> {
+//! let client = WebDAVClient::new("user", "password")?;
+//! let report = client
+//!     .upload_dir("/home/user/photos", "https://dav.example.com/photos/")
+//!     .filter(Filter::new().exclude(".DS_Store")?)
+//!     .concurrency(8)
+//!     .send()
+//!     .await?;
+//! for (path, err) in &report.failed {
+//!     println!("{path}: {err}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use reqwest::header::{HeaderName, HeaderValue, CONTENT_LENGTH, LAST_MODIFIED};
+use reqwest::{IntoUrl, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::task::JoinSet;
+use tokio_util::io::ReaderStream;
+
+use crate::client::check;
+use crate::client::walk::{collection_url, is_plain_path, walk};
+use crate::error::Error;
+use crate::filter::Filter;
+use crate::{Method, WebDAVClient};
+
+/// The suffix of files being written, which scans skip.
+pub(crate) const PARTIAL: &str = ".webdav-part";
+
+/// The header ownCloud and Nextcloud set the modification time from, in
+/// seconds since the Unix epoch.
+const OC_MTIME: &str = "x-oc-mtime";
+
+/// Trees are keyed by paths relative to the root, separated by `/`, e.g.
+/// `docs/a.txt`.
+pub(crate) type Tree<T> = BTreeMap<String, T>;
+
+/// A file or directory of the local tree.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct LocalEntry {
+    pub(crate) dir: bool,
+    pub(crate) len: u64,
+    pub(crate) modified: SystemTime,
+}
+
+/// `path` if it is a plain path, which stays below the root it is joined
+/// to.
+pub(crate) fn plain_path(path: &str) -> Result<&str, Error> {
+    if !is_plain_path(path) {
+        let message = format!("`{path}` is not a plain relative path");
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, message).into());
+    }
+    Ok(path)
+}
+
+/// The local path of the relative `path`, which must be a plain path so
+/// that it stays below `root`.
+pub(crate) fn local_path(root: &Path, path: &str) -> Result<PathBuf, Error> {
+    Ok(plain_path(path)?
+        .split('/')
+        .fold(root.to_path_buf(), |local, segment| local.join(segment)))
+}
+
+/// The URL of the relative `path`; `root` must end with `/`.
+pub(crate) fn remote_url(root: &Url, path: &str, dir: bool) -> Url {
+    let mut url = root.clone();
+    if let Ok(mut segments) = url.path_segments_mut() {
+        segments.pop_if_empty().extend(path.split('/'));
+        if dir {
+            segments.push("");
+        }
+    }
+    url
+}
+
+/// Lists the local tree below `root`, skipping partial files, the paths
+/// `filter` excludes and those `skip` returns `true` for.
+pub(crate) async fn scan_local(
+    root: &Path,
+    filter: &Filter,
+    skip: impl Fn(&Path, &str) -> bool,
+) -> Result<Tree<LocalEntry>, Error> {
+    let mut tree = Tree::new();
+    let mut pending = vec![(root.to_path_buf(), String::new())];
+    while let Some((dir, prefix)) = pending.pop() {
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            // Names that are not valid UTF-8 cannot be addressed by a URL.
+            let Ok(name) = entry.file_name().into_string() else {
+                continue;
+            };
+            if name.ends_with(PARTIAL) || skip(&entry.path(), &name) {
+                continue;
+            }
+            let metadata = tokio::fs::metadata(entry.path()).await?;
+            let path = format!("{prefix}{name}");
+            if filter.excludes(&path, metadata.is_dir()) {
+                continue;
+            }
+            if metadata.is_dir() {
+                pending.push((entry.path(), format!("{path}/")));
+            }
+            let local = LocalEntry {
+                dir: metadata.is_dir(),
+                len: if metadata.is_dir() { 0 } else { metadata.len() },
+                modified: metadata.modified()?,
+            };
+            tree.insert(path, local);
+        }
+    }
+    Ok(tree)
+}
+
+/// Uploads the file at `local` to `url`, asking the server to keep its
+/// modification time. Returns whether the server confirmed it.
+///
+/// The file is streamed. If the server challenges the credentials, the
+/// consumed stream cannot be replayed, so the file is sent once more with
+/// the credentials the challenge left.
+pub(crate) async fn upload_file(
+    client: &WebDAVClient,
+    local: &Path,
+    url: Url,
+) -> Result<bool, Error> {
+    let response = match put_file(client, local, url.clone(), false).await {
+        Err(Error::NotReplayable(_)) => put_file(client, local, url, true).await,
+        result => result,
+    };
+    let response = match response {
+        Ok(response) => check(Method::PUT, response).await,
+        Err(err) => Err(err),
+    };
+    let response = match response {
+        Ok(response) => response,
+        Err(err) => return Err(client.with_quota(err).await),
+    };
+    Ok(response
+        .headers()
+        .get(OC_MTIME)
+        .is_some_and(|value| value.as_bytes().eq_ignore_ascii_case(b"accepted")))
+}
+
+/// Streams the file at `local` to `url` in one `PUT`.
+async fn put_file(
+    client: &WebDAVClient,
+    local: &Path,
+    url: Url,
+    preemptive: bool,
+) -> Result<reqwest::Response, Error> {
+    let file = tokio::fs::File::open(local).await?;
+    let metadata = file.metadata().await?;
+    let body = reqwest::Body::wrap_stream(ReaderStream::new(file));
+    let mut request = client
+        .put(url)
+        .header(CONTENT_LENGTH, HeaderValue::from(metadata.len()))
+        .body(body);
+    if let Ok(seconds) = metadata.modified()?.duration_since(UNIX_EPOCH) {
+        let mtime = HeaderValue::from(seconds.as_secs());
+        request = request.header(HeaderName::from_static(OC_MTIME), mtime);
+    }
+    if preemptive {
+        request = request.preemptive();
+    }
+    request.send().await
+}
+
+/// Downloads `url` to `local`, writing next to it first, and keeps the
+/// remote modification time. Returns whether it was known.
+pub(crate) async fn download_file(
+    client: &WebDAVClient,
+    url: Url,
+    local: &Path,
+) -> Result<bool, Error> {
+    let response = check(Method::GET, client.get(url).send().await?).await?;
+    let modified = response
+        .headers()
+        .get(LAST_MODIFIED)
+        .and_then(|value| httpdate::parse_http_date(value.to_str().ok()?).ok());
+    let mut partial = local.as_os_str().to_owned();
+    partial.push(PARTIAL);
+    let partial = Path::new(&partial);
+    if let Err(err) = write_partial(response, partial, modified).await {
+        let _ = tokio::fs::remove_file(partial).await;
+        return Err(err);
+    }
+    tokio::fs::rename(partial, local).await?;
+    Ok(modified.is_some())
+}
+
+/// Streams the body of `response` into the file at `partial`.
+async fn write_partial(
+    mut response: reqwest::Response,
+    partial: &Path,
+    modified: Option<SystemTime>,
+) -> Result<(), Error> {
+    let mut file = tokio::fs::File::create(partial).await?;
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+    let file = file.into_std().await;
+    if let Some(modified) = modified {
+        file.set_modified(modified)?;
+    }
+    Ok(())
+}
+
+/// A file transferred by [`DirTransfer::send`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferredFile {
+    /// The path relative to the transferred directory, e.g. `docs/a.txt`.
+    pub path: String,
+    pub len: u64,
+    /// Whether the modification time was kept.
+    pub mtime_preserved: bool,
+}
+
+/// The outcome of [`DirTransfer::send`], per file.
+#[derive(Debug, Default)]
+pub struct TransferReport {
+    pub transferred: Vec<TransferredFile>,
+    /// Files and directories that failed, by relative path.
+    pub failed: Vec<(String, Error)>,
+}
+
+impl TransferReport {
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Upload,
+    Download,
+    /// A copy on the server, from the collection at `source`.
+    Copy,
+}
+
+/// A recursive upload, download or copy, created by
+/// [`WebDAVClient::upload_dir`], [`WebDAVClient::download_dir`] or
+/// [`WebDAVClient::copy_dir`].
+pub struct DirTransfer {
+    client: WebDAVClient,
+    direction: Direction,
+    local: PathBuf,
+    source: Option<Url>,
+    remote: Result<Url, Error>,
+    filter: Filter,
+    concurrency: usize,
+}
+
+impl DirTransfer {
+    /// Only transfers the paths `filter` accepts.
+    pub fn filter(self, filter: Filter) -> Self {
+        Self { filter, ..self }
+    }
+    /// The number of files transferred at once, 4 by default.
+    pub fn concurrency(self, concurrency: usize) -> Self {
+        Self {
+            concurrency: concurrency.max(1),
+            ..self
+        }
+    }
+
+    /// Creates the missing directories, then transfers the files,
+    /// replacing existing ones.
+    ///
+    /// Fails only if a tree cannot be listed; the files and directories
+    /// that cannot be transferred are reported in
+    /// [`TransferReport::failed`].
+    pub async fn send(self) -> Result<TransferReport, Error> {
+        let remote = collection_url(self.remote?);
+        let mut report = TransferReport::default();
+        let mut dirs = Vec::new();
+        let mut files = Vec::new();
+        if matches!(self.direction, Direction::Upload | Direction::Copy) {
+            match self.client.mkcol(remote.clone()).await {
+                Err(err) if err.status() != Some(StatusCode::METHOD_NOT_ALLOWED) => {
+                    return Err(err)
+                }
+                _ => (),
+            }
+        }
+        match self.direction {
+            Direction::Upload => {
+                let tree = scan_local(&self.local, &self.filter, |_, _| false).await?;
+                for (path, entry) in tree {
+                    if entry.dir {
+                        dirs.push(path);
+                    } else if self
+                        .filter
+                        .accepts(&path, false, entry.len, Some(entry.modified))
+                    {
+                        files.push((path, entry.len));
+                    }
+                }
+            }
+            Direction::Download | Direction::Copy => {
+                if let Direction::Download = self.direction {
+                    tokio::fs::create_dir_all(&self.local).await?;
+                }
+                // Copies list their source, downloads the remote tree.
+                let root = self.source.as_ref().unwrap_or(&remote);
+                walk(&self.client, root, |path, resource| {
+                    let accepted = self.filter.accepts_resource(&path, &resource);
+                    match (accepted, resource.is_collection) {
+                        (true, true) => dirs.push(path),
+                        (true, false) => files.push((path, resource.len)),
+                        _ => (),
+                    }
+                    accepted
+                })
+                .await?;
+            }
+        }
+
+        // Parents sort before their members.
+        dirs.sort();
+        let mut failed_dirs: Vec<String> = Vec::new();
+        for dir in dirs {
+            let result = match self.direction {
+                Direction::Upload | Direction::Copy => {
+                    match self.client.mkcol(remote_url(&remote, &dir, true)).await {
+                        Err(err) if err.status() == Some(StatusCode::METHOD_NOT_ALLOWED) => Ok(()),
+                        result => result,
+                    }
+                }
+                Direction::Download => match local_path(&self.local, &dir) {
+                    Ok(local) => tokio::fs::create_dir_all(local).await.map_err(Error::from),
+                    Err(err) => Err(err),
+                },
+            };
+            if let Err(err) = result {
+                report.failed.push((dir.clone(), err));
+                failed_dirs.push(dir);
+            }
+        }
+
+        let context = Arc::new((self.client, self.local, self.source, remote));
+        let mut tasks = JoinSet::new();
+        for (path, len) in files {
+            let below_failed = failed_dirs.iter().any(|dir| {
+                path.strip_prefix(dir.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+            });
+            if below_failed {
+                continue;
+            }
+            if tasks.len() >= self.concurrency {
+                collect(&mut report, tasks.join_next().await);
+            }
+            let context = context.clone();
+            let direction = self.direction;
+            tasks.spawn(async move {
+                let (client, local, source, remote) = &*context;
+                let result = async {
+                    let url = remote_url(remote, plain_path(&path)?, false);
+                    match (direction, source) {
+                        (Direction::Upload, _) => {
+                            upload_file(client, &local_path(local, &path)?, url).await
+                        }
+                        (Direction::Download, _) => {
+                            download_file(client, url, &local_path(local, &path)?).await
+                        }
+                        // The server decides the modification time of a copy.
+                        (Direction::Copy, source) => {
+                            let source = source.as_ref().unwrap_or(remote);
+                            let from = remote_url(source, &path, false);
+                            client.copy_to(from, url, true).await.map(|()| false)
+                        }
+                    }
+                }
+                .await;
+                (path, len, result)
+            });
+        }
+        while let Some(joined) = tasks.join_next().await {
+            collect(&mut report, Some(joined));
+        }
+        Ok(report)
+    }
+}
+
+type Transferred = Result<(String, u64, Result<bool, Error>), tokio::task::JoinError>;
+
+fn collect(report: &mut TransferReport, joined: Option<Transferred>) {
+    match joined {
+        Some(Ok((path, len, Ok(mtime_preserved)))) => report.transferred.push(TransferredFile {
+            path,
+            len,
+            mtime_preserved,
+        }),
+        Some(Ok((path, _, Err(err)))) => report.failed.push((path, err)),
+        Some(Err(err)) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+        _ => (),
+    }
+}
+
+impl WebDAVClient {
+    /// Uploads the directory `local` into the collection at `remote`,
+    /// creating it if needed.
+    pub fn upload_dir(&self, local: impl Into<PathBuf>, remote: impl IntoUrl) -> DirTransfer {
+        self.dir_transfer(Direction::Upload, local.into(), remote)
+    }
+    /// Downloads the collection at `remote` into the directory `local`,
+    /// creating it if needed.
+    pub fn download_dir(&self, remote: impl IntoUrl, local: impl Into<PathBuf>) -> DirTransfer {
+        self.dir_transfer(Direction::Download, local.into(), remote)
+    }
+    /// Copies the collection at `from` into the collection at `to` on the
+    /// server, creating it if needed. Unlike a `COPY` of the whole
+    /// collection, only the paths the filter accepts are copied.
+    pub fn copy_dir(&self, from: impl IntoUrl, to: impl IntoUrl) -> DirTransfer {
+        let mut transfer = self.dir_transfer(Direction::Copy, PathBuf::new(), to);
+        match from.into_url() {
+            Ok(from) => transfer.source = Some(collection_url(from)),
+            Err(err) => transfer.remote = Err(err.into()),
+        }
+        transfer
+    }
+
+    fn dir_transfer(
+        &self,
+        direction: Direction,
+        local: PathBuf,
+        remote: impl IntoUrl,
+    ) -> DirTransfer {
+        DirTransfer {
+            client: self.clone(),
+            direction,
+            local,
+            source: None,
+            remote: remote.into_url().map_err(Error::from),
+            filter: Filter::default(),
+            concurrency: 4,
+        }
+    }
+}
@@ -0,0 +1,787 @@
+mod common;
+
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant, SystemTime};
+
+use common::{block_on, multistatus};
+use webdav_request::client::{Auth, CredentialProvider, Report, RetryPolicy};
+use webdav_request::error::{Error, Result};
+use webdav_request::header::{HeaderValue, AUTHORIZATION, LOCATION, RETRY_AFTER, WWW_AUTHENTICATE};
+use webdav_request::if_header::{IfHeader, IfList, IF};
+use webdav_request::res::acl::{AccessControl, Ace, AcePrincipal};
+use webdav_request::res::principal::{Principal, PrincipalSearch};
+use webdav_request::res::privilege::PrivilegeName;
+use webdav_request::res::quota::QuotaBytes;
+use webdav_request::search::{Property, SearchQuery};
+use webdav_request::testing::{Fault, MockServer};
+use webdav_request::{Method, StatusCode, Url, WebDAVClient};
+
+fn authorization(server: &MockServer, index: usize) -> Option<String> {
+    let requests = server.requests();
+    let header = requests[index].headers.get(AUTHORIZATION)?;
+    Some(header.to_str().unwrap().to_owned())
+}
+
+#[test]
+fn retries_with_retry_after() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        server.put_file("/a.txt", "hello");
+        server.inject(
+            Fault::new()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .header(RETRY_AFTER, HeaderValue::from_static("1"))
+                .times(2),
+        );
+        let client = WebDAVClient::builder()
+            .auth(Auth::Anonymous)
+            .retry(
+                RetryPolicy::new()
+                    .max_attempts(3)
+                    .backoff(Duration::from_millis(1), Duration::from_secs(5)),
+            )
+            .build()
+            .unwrap();
+
+        let started = Instant::now();
+        let response = client.get(server.url_for("/a.txt")).send().await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "hello");
+        assert!(started.elapsed() >= Duration::from_secs(2));
+        assert_eq!(server.requests().len(), 3);
+    });
+}
+
+#[test]
+fn does_not_retry_by_default() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        server.inject(
+            Fault::new()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .times(1),
+        );
+        let client = WebDAVClient::anonymous().unwrap();
+
+        let response = client.get(server.url_for("/a.txt")).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(server.requests().len(), 1);
+    });
+}
+
+#[test]
+fn follows_redirects_with_method_and_body() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        server.inject(
+            Fault::new()
+                .path("/old.txt")
+                .status(StatusCode::TEMPORARY_REDIRECT)
+                .header(LOCATION, HeaderValue::from_static("/new.txt")),
+        );
+        let client = WebDAVClient::new("user", "password").unwrap();
+
+        let response = client
+            .put(server.url_for("/old.txt"))
+            .body("moved")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.url().path(), "/new.txt");
+        assert_eq!(server.file("/new.txt").unwrap(), "moved");
+        assert!(authorization(&server, 1).unwrap().starts_with("Basic "));
+    });
+}
+
+#[test]
+fn drops_credentials_on_redirects_to_other_origins() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        let other = MockServer::start().await.unwrap();
+        other.put_file("/a.txt", "elsewhere");
+        let location = HeaderValue::from_str(other.url_for("/a.txt").as_str()).unwrap();
+        server.inject(
+            Fault::new()
+                .status(StatusCode::FOUND)
+                .header(LOCATION, location),
+        );
+        let client = WebDAVClient::new("user", "password").unwrap();
+
+        let response = client.get(server.url_for("/a.txt")).send().await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "elsewhere");
+        assert!(authorization(&server, 0).is_some());
+        assert_eq!(authorization(&other, 0), None);
+    });
+}
+
+#[test]
+fn stops_after_too_many_redirects() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        server.inject(
+            Fault::new()
+                .status(StatusCode::FOUND)
+                .header(LOCATION, HeaderValue::from_static("/loop")),
+        );
+        let client = WebDAVClient::builder()
+            .auth(Auth::Anonymous)
+            .max_redirects(3)
+            .build()
+            .unwrap();
+
+        let err = client
+            .get(server.url_for("/loop"))
+            .send()
+            .await
+            .unwrap_err();
+        assert!(err.is_too_many_redirects_err());
+        assert_eq!(server.requests().len(), 4);
+    });
+}
+
+#[test]
+fn answers_digest_challenges() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        server.put_file("/a.txt", "hello");
+        let challenge = r#"Digest realm="dav", nonce="abc", qop="auth", algorithm=MD5"#;
+        server.inject(
+            Fault::new()
+                .status(StatusCode::UNAUTHORIZED)
+                .header(WWW_AUTHENTICATE, HeaderValue::from_static(challenge))
+                .times(1),
+        );
+        let client = WebDAVClient::builder()
+            .digest_auth("user", "password")
+            .build()
+            .unwrap();
+
+        let response = client.get(server.url_for("/a.txt")).send().await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "hello");
+        assert_eq!(authorization(&server, 0), None);
+        let digest = authorization(&server, 1).unwrap();
+        assert!(digest.starts_with("Digest "));
+        assert!(digest.contains(r#"username="user""#));
+        assert!(digest.contains(r#"realm="dav""#));
+        assert!(digest.contains("nc=00000001"));
+
+        // The nonce is reused without another challenge.
+        client.get(server.url_for("/a.txt")).send().await.unwrap();
+        assert!(authorization(&server, 2).unwrap().contains("nc=00000002"));
+    });
+}
+
+#[test]
+fn keeps_digest_challenges_per_origin() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        let other = MockServer::start().await.unwrap();
+        server.put_file("/a.txt", "hello");
+        other.put_file("/a.txt", "elsewhere");
+        let challenge = r#"Digest realm="dav", nonce="abc", qop="auth", algorithm=MD5"#;
+        server.inject(
+            Fault::new()
+                .status(StatusCode::UNAUTHORIZED)
+                .header(WWW_AUTHENTICATE, HeaderValue::from_static(challenge))
+                .times(1),
+        );
+        let client = WebDAVClient::builder()
+            .digest_auth("user", "password")
+            .build()
+            .unwrap();
+
+        client.get(server.url_for("/a.txt")).send().await.unwrap();
+        assert!(authorization(&server, 1).is_some());
+        let response = client.get(other.url_for("/a.txt")).send().await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "elsewhere");
+        assert_eq!(authorization(&other, 0), None);
+    });
+}
+
+struct TokenStore {
+    token: Mutex<String>,
+    /// The thread of the runtime, which the provider must not block.
+    runtime: ThreadId,
+}
+
+impl CredentialProvider for TokenStore {
+    fn credentials(&self, _url: &Url) -> Result<Auth> {
+        assert_ne!(thread::current().id(), self.runtime);
+        Ok(Auth::bearer(&self.token.lock().unwrap()))
+    }
+    fn refresh(&self) -> Result<bool> {
+        assert_ne!(thread::current().id(), self.runtime);
+        *self.token.lock().unwrap() = "new".to_owned();
+        Ok(true)
+    }
+}
+
+#[test]
+fn refreshes_rejected_credentials() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        server.put_file("/a.txt", "hello");
+        server.inject(Fault::new().status(StatusCode::UNAUTHORIZED).times(1));
+        let client = WebDAVClient::builder()
+            .credentials(TokenStore {
+                token: Mutex::new("old".to_owned()),
+                runtime: thread::current().id(),
+            })
+            .build()
+            .unwrap();
+
+        let response = client.get(server.url_for("/a.txt")).send().await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "hello");
+        assert_eq!(authorization(&server, 0).unwrap(), "Bearer old");
+        assert_eq!(authorization(&server, 1).unwrap(), "Bearer new");
+    });
+}
+
+#[test]
+fn writes_locked_resources_with_the_if_header() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        server.put_file("/a.txt", "hello");
+        let client = WebDAVClient::anonymous().unwrap();
+        let url = server.url_for("/a.txt");
+
+        let lock = client
+            .lock(url.clone(), Some(Duration::from_secs(60)))
+            .await
+            .unwrap();
+        assert!(server.is_locked("/a.txt"));
+        let response = client.put(url.clone()).body("other").send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::LOCKED);
+
+        let response = client
+            .put(url.clone())
+            .if_header(&lock)
+            .body("mine")
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+        assert_eq!(server.file("/a.txt").unwrap(), "mine");
+
+        client.unlock(&lock).await.unwrap();
+        assert!(!server.is_locked("/a.txt"));
+    });
+}
+
+#[test]
+fn fails_to_unlock_with_an_invalid_lock_token() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        server.inject(
+            Fault::new()
+                .method(Method::LOCK)
+                .status(StatusCode::OK)
+                .body(
+                    r#"<d:prop xmlns:d="DAV:"><d:lockdiscovery><d:activelock>
+                        <d:locktoken><d:href>urn:a&#10;b</d:href></d:locktoken>
+                    </d:activelock></d:lockdiscovery></d:prop>"#,
+                ),
+        );
+        let client = WebDAVClient::anonymous().unwrap();
+
+        let lock = client.lock(server.url_for("/a.txt"), None).await.unwrap();
+        let err = client.unlock(&lock).await.unwrap_err();
+        assert!(err.is_invalid_header_err());
+        assert_eq!(server.requests().len(), 1);
+    });
+}
+
+#[test]
+fn rejects_malformed_if_headers() {
+    let list = || IfList::new().token("urn:a");
+    let err = IfHeader::new(list()).tag("/a", list()).unwrap_err();
+    assert!(err.is_invalid_header_err());
+    assert!(IfHeader::tagged("", list())
+        .unwrap_err()
+        .is_invalid_header_err());
+    assert!(IfHeader::Tagged(Vec::new())
+        .or(list())
+        .to_header_value()
+        .is_err());
+    assert!(IfHeader::new(IfList::new()).to_header_value().is_err());
+    let header = IfHeader::tagged("/a", list()).unwrap().tag("/b", list());
+    assert_eq!(
+        header.unwrap().to_header_value().unwrap(),
+        "</a> (<urn:a>) </b> (<urn:a>)"
+    );
+}
+
+#[test]
+fn encodes_tagged_if_headers() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        let client = WebDAVClient::anonymous().unwrap();
+        let url = server.url_for("/a.txt");
+
+        let header = IfHeader::tagged("/dir/ä b.txt", IfList::new().etag("1")).unwrap();
+        client
+            .get(url.clone())
+            .if_header(header)
+            .send()
+            .await
+            .unwrap();
+        let requests = server.requests();
+        assert_eq!(requests[0].headers[IF], r#"</dir/%C3%A4%20b.txt> (["1"])"#);
+
+        let header = IfHeader::new(IfList::new().etag("a\nb"));
+        let err = client.get(url).if_header(header).send().await.unwrap_err();
+        assert!(err.is_invalid_header_err());
+        assert_eq!(server.requests().len(), 1);
+    });
+}
+
+#[test]
+fn fails_conditional_writes_with_precondition_failed() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        server.put_file("/a.txt", "hello");
+        let client = WebDAVClient::anonymous().unwrap();
+
+        let err = client
+            .put_if_absent(server.url_for("/a.txt"), "other")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::PreconditionFailed(_)));
+        let err = client
+            .put_if_unchanged(server.url_for("/a.txt"), "\"stale\"", "other")
+            .await
+            .unwrap_err();
+        assert!(err.is_precondition_failed());
+        assert_eq!(server.file("/a.txt").unwrap(), "hello");
+    });
+}
+
+#[test]
+fn rejects_invalid_conditional_headers() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        let client = WebDAVClient::anonymous().unwrap();
+        let url = server.url_for("/a.txt");
+
+        let err = client
+            .get(url.clone())
+            .if_match("a\nb")
+            .send()
+            .await
+            .unwrap_err();
+        assert!(err.is_invalid_header_err());
+        let before_1970 = SystemTime::UNIX_EPOCH - Duration::from_secs(1);
+        let err = client
+            .get(url.clone())
+            .if_modified_since(before_1970)
+            .send()
+            .await
+            .unwrap_err();
+        assert!(err.is_invalid_header_err());
+        let year_10000 = SystemTime::UNIX_EPOCH + Duration::from_secs(253_402_300_800);
+        let err = client
+            .get(url)
+            .if_unmodified_since(year_10000)
+            .build()
+            .unwrap_err();
+        assert!(err.is_invalid_header_err());
+        assert!(server.requests().is_empty());
+    });
+}
+
+#[test]
+fn rejects_invalid_xml_names() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        let client = WebDAVClient::anonymous().unwrap();
+        let url = server.url_for("/a.txt");
+
+        let acl = [Ace::grant(
+            AcePrincipal::All,
+            [PrivilegeName::dav(r#"read/><D:all"#)],
+        )];
+        let err = client.set_acl(url.clone(), &acl).await.unwrap_err();
+        assert!(err.is_invalid_xml_name_err());
+        let principal = AcePrincipal::Property {
+            namespace: "DAV:".to_owned(),
+            name: "owner group".to_owned(),
+        };
+        let acl = [Ace::grant(principal, [PrivilegeName::dav("read")])];
+        let err = client.set_acl(url.clone(), &acl).await.unwrap_err();
+        assert!(err.is_invalid_xml_name_err());
+        let query = SearchQuery::new("/").condition(Property::new("urn:x", "a:b").is_defined());
+        let err = client.search(url, &query).await.unwrap_err();
+        assert!(err.is_invalid_xml_name_err());
+        assert!(server.requests().is_empty());
+    });
+}
+
+#[test]
+fn writes_acls_that_parse_back() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        let client = WebDAVClient::anonymous().unwrap();
+        let url = server.url_for("/docs/");
+        let props = r#"
+            <d:owner><d:href>/principals/alice</d:href></d:owner>
+            <d:group><d:href>/principals/staff</d:href></d:group>
+            <d:acl>
+                <d:ace>
+                    <d:principal><d:property><d:owner/></d:property></d:principal>
+                    <d:grant><d:privilege><d:all/></d:privilege></d:grant>
+                    <d:protected/>
+                </d:ace>
+                <d:ace>
+                    <d:invert><d:principal><d:href>/principals/a&amp;b</d:href></d:principal></d:invert>
+                    <d:deny>
+                        <d:privilege><d:write/></d:privilege>
+                        <d:privilege><C:read-free-busy xmlns:C="urn:ietf:params:xml:ns:caldav"/></d:privilege>
+                    </d:deny>
+                </d:ace>
+                <d:ace>
+                    <d:principal><d:self/></d:principal>
+                    <d:grant><d:privilege><d:read-acl/></d:privilege></d:grant>
+                </d:ace>
+                <d:ace>
+                    <d:principal><d:authenticated/></d:principal>
+                    <d:grant><d:privilege><d:read/></d:privilege></d:grant>
+                    <d:inherited><d:href>/</d:href></d:inherited>
+                </d:ace>
+            </d:acl>
+            <d:supported-privilege-set>
+                <d:supported-privilege>
+                    <d:privilege><d:all/></d:privilege>
+                    <d:abstract/>
+                    <d:description xml:lang="en">Any operation</d:description>
+                    <d:supported-privilege>
+                        <d:privilege><d:read/></d:privilege>
+                        <d:description xml:lang="en">Read any object</d:description>
+                        <d:supported-privilege>
+                            <d:privilege><C:read-free-busy xmlns:C="urn:ietf:params:xml:ns:caldav"/></d:privilege>
+                        </d:supported-privilege>
+                    </d:supported-privilege>
+                </d:supported-privilege>
+            </d:supported-privilege-set>
+            <d:principal-collection-set><d:href>/principals/</d:href></d:principal-collection-set>"#;
+        server.inject(
+            Fault::new()
+                .method(Method::PROPFIND)
+                .status(StatusCode::MULTI_STATUS)
+                .body(multistatus(&[("/docs/", props)], "")),
+        );
+        let access_control = client.access_control(url.clone()).await.unwrap();
+        assert_eq!(access_control.owner.as_deref(), Some("/principals/alice"));
+        assert_eq!(access_control.group.as_deref(), Some("/principals/staff"));
+        assert_eq!(access_control.principal_collections, ["/principals/"]);
+
+        let free_busy = PrivilegeName::new("urn:ietf:params:xml:ns:caldav", "read-free-busy");
+        let owner = AcePrincipal::Property {
+            namespace: "DAV:".to_owned(),
+            name: "owner".to_owned(),
+        };
+        let acl = [
+            Ace {
+                protected: true,
+                ..Ace::grant(owner, [PrivilegeName::dav("all")])
+            },
+            Ace::deny(
+                AcePrincipal::Href("/principals/a&b".to_owned()),
+                [PrivilegeName::dav("write"), free_busy.clone()],
+            )
+            .invert(),
+            Ace::grant(
+                AcePrincipal::SelfPrincipal,
+                [PrivilegeName::dav("read-acl")],
+            ),
+            Ace {
+                inherited: Some("/".to_owned()),
+                ..Ace::grant(AcePrincipal::Authenticated, [PrivilegeName::dav("read")])
+            },
+        ];
+        assert_eq!(access_control.acl, acl);
+
+        let supported = &access_control.supported_privileges;
+        assert_eq!(supported.len(), 1);
+        assert_eq!(supported[0].privilege, PrivilegeName::dav("all"));
+        assert!(supported[0].is_abstract);
+        assert_eq!(supported[0].description.as_deref(), Some("Any operation"));
+        let read = &supported[0].aggregates[0];
+        assert_eq!(read.privilege, PrivilegeName::dav("read"));
+        assert!(!read.is_abstract);
+        assert_eq!(read.aggregates[0].privilege, free_busy);
+        assert!(read.aggregates[0].aggregates.is_empty());
+
+        server.inject(Fault::new().method(Method::ACL).status(StatusCode::OK));
+        client.set_acl(url, &access_control.acl).await.unwrap();
+        let requests = server.requests();
+        let written = std::str::from_utf8(&requests.last().unwrap().body).unwrap();
+        let written = &written[written.find("<D:acl").unwrap()..];
+        // The inherited entry is left out.
+        let parsed = AccessControl::parse(&multistatus(&[("/docs/", written)], "")).unwrap();
+        assert_eq!(parsed.acl, acl[..3]);
+    });
+}
+
+#[test]
+fn searches_principal_properties() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        let client = WebDAVClient::anonymous().unwrap();
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+            <d:multistatus xmlns:d="DAV:" xmlns:s="http://sabredav.org/ns" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+                <d:response>
+                    <d:href>/principals/users/alice/</d:href>
+                    <d:propstat>
+                        <d:prop>
+                            <d:displayname>Alice Doe</d:displayname>
+                            <s:email-address>alice@example.com</s:email-address>
+                            <d:group-membership>
+                                <d:href>/principals/groups/staff/</d:href>
+                                <d:href>/principals/groups/admin/</d:href>
+                            </d:group-membership>
+                        </d:prop>
+                        <d:status>HTTP/1.1 200 OK</d:status>
+                    </d:propstat>
+                </d:response>
+                <d:response>
+                    <d:href>/principals/users/bob/</d:href>
+                    <d:propstat>
+                        <d:prop>
+                            <d:displayname>Bob Doe</d:displayname>
+                            <cal:calendar-user-address-set>
+                                <d:href>/principals/users/bob/</d:href>
+                                <d:href>MAILTO:bob@example.com</d:href>
+                            </cal:calendar-user-address-set>
+                        </d:prop>
+                        <d:status>HTTP/1.1 200 OK</d:status>
+                    </d:propstat>
+                    <d:propstat>
+                        <d:prop><s:email-address/><d:group-membership/></d:prop>
+                        <d:status>HTTP/1.1 404 Not Found</d:status>
+                    </d:propstat>
+                </d:response>
+            </d:multistatus>"#;
+        server.inject(
+            Fault::new()
+                .method(Method::REPORT)
+                .status(StatusCode::MULTI_STATUS)
+                .body(xml),
+        );
+        let search = PrincipalSearch::new()
+            .display_name("doe")
+            .property("urn:example", "team", "a&b")
+            .all_of()
+            .apply_to_principal_collection_set();
+
+        let principals = client
+            .principal_property_search(server.url_for("/principals/"), &search)
+            .await
+            .unwrap();
+        let alice = Principal {
+            href: "/principals/users/alice/".to_owned(),
+            display_name: Some("Alice Doe".to_owned()),
+            email: Some("alice@example.com".to_owned()),
+            group_membership: vec![
+                "/principals/groups/staff/".to_owned(),
+                "/principals/groups/admin/".to_owned(),
+            ],
+        };
+        let bob = Principal {
+            href: "/principals/users/bob/".to_owned(),
+            display_name: Some("Bob Doe".to_owned()),
+            email: Some("bob@example.com".to_owned()),
+            group_membership: Vec::new(),
+        };
+        assert_eq!(principals, [alice, bob]);
+
+        let requests = server.requests();
+        let request = requests.last().unwrap();
+        assert_eq!(request.headers["depth"], "0");
+        assert_eq!(
+            std::str::from_utf8(&request.body).unwrap(),
+            concat!(
+                r#"<?xml version="1.0" encoding="utf-8"?>"#,
+                r#"<d:principal-property-search test="allof" xmlns:d="DAV:" "#,
+                r#"xmlns:s="http://sabredav.org/ns" xmlns:c="urn:ietf:params:xml:ns:caldav" "#,
+                r#"xmlns:p1="urn:example">"#,
+                r#"<d:property-search><d:prop><d:displayname/></d:prop><d:match>doe</d:match></d:property-search>"#,
+                r#"<d:property-search><d:prop><p1:team/></d:prop><d:match>a&amp;b</d:match></d:property-search>"#,
+                r#"<d:prop><d:displayname/><s:email-address/><c:calendar-user-address-set/><d:group-membership/></d:prop>"#,
+                r#"<d:apply-to-principal-collection-set/>"#,
+                r#"</d:principal-property-search>"#,
+            )
+        );
+    });
+}
+
+#[test]
+fn follows_truncated_sync_collection_reports() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        let truncated = r#"<d:response><d:href>/cal/</d:href><d:status>HTTP/1.1 507 Insufficient Storage</d:status></d:response><d:sync-token>t1</d:sync-token>"#;
+        let removed = r#"<d:response><d:href>/cal/c.ics</d:href><d:status>HTTP/1.1 404 Not Found</d:status></d:response><d:sync-token>t2</d:sync-token>"#;
+        for body in [
+            multistatus(&[("/cal/a.ics", "<d:getetag>\"1\"</d:getetag>")], truncated),
+            multistatus(&[("/cal/b.ics", "<d:getetag>\"2\"</d:getetag>")], removed),
+        ] {
+            server.inject(
+                Fault::new()
+                    .method(Method::REPORT)
+                    .status(StatusCode::MULTI_STATUS)
+                    .body(body)
+                    .times(1),
+            );
+        }
+        let client = WebDAVClient::anonymous().unwrap();
+
+        let changes = client
+            .sync_collection(server.url_for("/cal/"), None)
+            .await
+            .unwrap();
+        let changed: Vec<&str> = changes.changed.iter().map(|r| r.href.as_str()).collect();
+        assert_eq!(changed, ["/cal/a.ics", "/cal/b.ics"]);
+        assert_eq!(changes.removed, ["/cal/c.ics"]);
+        assert_eq!(changes.sync_token, "t2");
+        assert!(!changes.truncated);
+        assert_eq!(server.requests().len(), 2);
+    });
+}
+
+#[test]
+fn stops_when_the_sync_token_stalls() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        let truncated = r#"<d:response><d:href>/cal/</d:href><d:status>HTTP/1.1 507 Insufficient Storage</d:status></d:response><d:sync-token>t1</d:sync-token>"#;
+        server.inject(
+            Fault::new()
+                .method(Method::REPORT)
+                .status(StatusCode::MULTI_STATUS)
+                .body(multistatus(
+                    &[("/cal/a.ics", "<d:getetag>\"1\"</d:getetag>")],
+                    truncated,
+                ))
+                .times(2),
+        );
+        let client = WebDAVClient::anonymous().unwrap();
+
+        let changes = client
+            .sync_collection(server.url_for("/cal/"), None)
+            .await
+            .unwrap();
+        assert_eq!(changes.changed.len(), 1);
+        assert_eq!(changes.sync_token, "t1");
+        assert!(changes.truncated);
+        assert_eq!(server.requests().len(), 2);
+    });
+}
+
+#[test]
+fn reports_insufficient_storage_with_the_quota() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        server.create_dir("/dir");
+        let quota = "<d:quota-available-bytes>10</d:quota-available-bytes>\
+                     <d:quota-used-bytes>90</d:quota-used-bytes>";
+        server.inject(
+            Fault::new()
+                .method(Method::PUT)
+                .status(StatusCode::INSUFFICIENT_STORAGE)
+                .times(1),
+        );
+        server.inject(
+            Fault::new()
+                .method(Method::PROPFIND)
+                .path("/dir")
+                .status(StatusCode::MULTI_STATUS)
+                .body(multistatus(&[("/dir/", quota)], "")),
+        );
+        let client = WebDAVClient::anonymous().unwrap();
+
+        let err = client
+            .put_if_absent(server.url_for("/dir/a.txt"), "too large")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InsufficientStorage(..)));
+        assert!(err.is_insufficient_storage());
+        let quota = err.quota().unwrap();
+        assert_eq!(quota.available, QuotaBytes::Bytes(10));
+        assert_eq!(quota.total(), QuotaBytes::Bytes(100));
+
+        let requests = server.requests();
+        assert_eq!(requests[1].method, "PROPFIND");
+        assert_eq!(requests[1].path, "/dir");
+    });
+}
+
+#[test]
+fn keeps_other_insufficient_storage_responses() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        server.inject(
+            Fault::new()
+                .method(Method::REPORT)
+                .status(StatusCode::INSUFFICIENT_STORAGE),
+        );
+        let client = WebDAVClient::anonymous().unwrap();
+
+        let err = client
+            .sync_collection_page(server.url_for("/cal/"), None, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::ResponseError(_)));
+        assert!(err.is_insufficient_storage());
+        assert!(err.quota().is_none());
+    });
+}
+
+#[test]
+fn does_not_redirect_built_requests() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        server.inject(
+            Fault::new()
+                .status(StatusCode::FOUND)
+                .header(LOCATION, HeaderValue::from_static("/b.txt")),
+        );
+        let client = WebDAVClient::anonymous().unwrap();
+
+        let request = client.get(server.url_for("/a.txt")).build().unwrap();
+        let response = request.send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(server.requests().len(), 1);
+    });
+}
+
+#[test]
+fn declares_report_namespaces_on_the_root_element() {
+    #[derive(serde::Serialize)]
+    #[serde(rename = "oc:filter-files")]
+    struct FilterFiles {
+        #[serde(rename = "@xmlns:oc")]
+        oc: &'static str,
+        #[serde(rename = "@note")]
+        note: &'static str,
+        #[serde(rename = "oc:favorite")]
+        favorite: u8,
+    }
+    let report = Report::new(FilterFiles {
+        oc: "http://owncloud.org/ns",
+        note: "a/b > c",
+        favorite: 1,
+    })
+    .namespace("oc", "urn:ignored")
+    .namespace("nc", "http://nextcloud.org/ns?a&b");
+    assert_eq!(
+        report.to_xml().unwrap(),
+        r#"<?xml version="1.0" encoding="utf-8"?><oc:filter-files xmlns:oc="http://owncloud.org/ns" note="a/b &gt; c" xmlns:d="DAV:" xmlns:nc="http://nextcloud.org/ns?a&amp;b"><oc:favorite>1</oc:favorite></oc:filter-files>"#
+    );
+
+    #[derive(serde::Serialize)]
+    #[serde(rename = "d:sync-collection")]
+    struct Empty {}
+    assert_eq!(
+        Report::new(Empty {}).to_xml().unwrap(),
+        r#"<?xml version="1.0" encoding="utf-8"?><d:sync-collection xmlns:d="DAV:"/>"#
+    );
+}
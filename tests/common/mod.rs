@@ -0,0 +1,38 @@
+#![allow(dead_code)]
+
+use std::future::Future;
+use std::path::PathBuf;
+
+/// Runs `future` on a runtime of its own, which also runs the mock server.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+/// An empty directory for the test `name`.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir()
+        .join("webdav-request-tests")
+        .join(format!("{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// A `DAV:multistatus` with one `response` per `(href, props)`.
+pub fn multistatus(responses: &[(&str, &str)], extra: &str) -> String {
+    let mut xml =
+        String::from(r#"<?xml version="1.0" encoding="utf-8"?><d:multistatus xmlns:d="DAV:">"#);
+    for (href, props) in responses {
+        xml.push_str(&format!(
+            "<d:response><d:href>{href}</d:href><d:propstat><d:prop>{props}</d:prop>\
+             <d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>"
+        ));
+    }
+    xml.push_str(extra);
+    xml.push_str("</d:multistatus>");
+    xml
+}
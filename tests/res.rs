@@ -0,0 +1,116 @@
+use webdav_request::res::privilege::{Privilege, PrivilegeName, PrivilegeType};
+use webdav_request::res::{Collection, DavCondition, MultiStatus};
+
+#[test]
+fn parses_dav_error_conditions() {
+    let xml = r#"<?xml version="1.0" encoding="utf-8" ?>
+        <D:error xmlns:D="DAV:" xmlns:x="urn:example">
+            <D:lock-token-submitted>
+                <D:href>/locked/</D:href>
+                <D:href>/locked/a.txt</D:href>
+            </D:lock-token-submitted>
+            <D:no-conflicting-lock><D:href>/other/</D:href></D:no-conflicting-lock>
+            <D:valid-sync-token/>
+            <x:quota-exceeded/>
+        </D:error>"#;
+
+    assert_eq!(
+        DavCondition::parse_all(xml),
+        [
+            DavCondition::LockTokenSubmitted(vec![
+                "/locked/".to_owned(),
+                "/locked/a.txt".to_owned()
+            ]),
+            DavCondition::NoConflictingLock(vec!["/other/".to_owned()]),
+            DavCondition::ValidSyncToken,
+            DavCondition::Other {
+                namespace: "urn:example".to_owned(),
+                name: "quota-exceeded".to_owned(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn parses_server_exceptions() {
+    let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+        <d:error xmlns:d="DAV:" xmlns:s="http://sabredav.org/ns">
+            <s:exception>Sabre\DAV\Exception\Locked</s:exception>
+            <s:message>&quot;a.txt&quot; is locked</s:message>
+        </d:error>"#;
+
+    assert_eq!(
+        DavCondition::parse_all(xml),
+        [DavCondition::ServerException {
+            exception: Some(r"Sabre\DAV\Exception\Locked".to_owned()),
+            message: Some(r#""a.txt" is locked"#.to_owned()),
+        }]
+    );
+}
+
+#[test]
+fn ignores_other_bodies() {
+    assert!(DavCondition::parse_all("<html><body>Locked</body></html>").is_empty());
+    assert!(DavCondition::parse_all("Locked").is_empty());
+    let xml = r#"<d:multistatus xmlns:d="DAV:"><d:error/></d:multistatus>"#;
+    assert!(DavCondition::parse_all(xml).is_empty());
+}
+
+#[test]
+fn aggregates_current_user_privileges() {
+    let privileges = |set: &str| {
+        format!(
+            "<d:response><d:href>/dav/</d:href><d:propstat><d:prop>\
+             <d:current-user-privilege-set>{set}</d:current-user-privilege-set>\
+             </d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>"
+        )
+    };
+    let xml = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?><d:multistatus xmlns:d="DAV:">{}{}{}{}</d:multistatus>"#,
+        privileges(""),
+        privileges(
+            r#"<d:privilege><d:read-acl/></d:privilege>
+               <d:privilege><x:share xmlns:x="urn:example"/></d:privilege>
+               <d:privilege><read-free-busy xmlns="urn:ietf:params:xml:ns:caldav"/></d:privilege>
+               <d:privilege/>"#
+        ),
+        privileges("<d:privilege><d:all/></d:privilege>"),
+        privileges("<d:privilege><d:write/></d:privilege><d:privilege><d:unlock/></d:privilege>"),
+    );
+    let collection = Collection::from(MultiStatus::parse(&xml).unwrap());
+    let [custom, all, write] = &collection.children[..] else {
+        panic!("{collection:?}");
+    };
+
+    let custom = &custom.privilege;
+    assert!(custom.read_acl());
+    assert!(!custom.read() && !custom.write() && !custom.all());
+    assert!(custom.has(&PrivilegeType::Other(PrivilegeName::new(
+        "urn:example",
+        "share"
+    ))));
+    let free_busy = PrivilegeName::new("urn:ietf:params:xml:ns:caldav", "read-free-busy");
+    assert!(custom.has(&PrivilegeType::Other(free_busy)));
+    assert_eq!(custom.granted().len(), 3);
+
+    let all = &all.privilege;
+    assert!(all.all() && all.read() && all.write() && all.unlock());
+    assert!(all.read_acl() && all.write_acl() && all.read_current_user_privilege_set());
+    assert!(all.write_properties() && all.write_content() && all.bind() && all.unbind());
+
+    let write = &write.privilege;
+    assert!(write.write() && write.unlock());
+    assert!(write.write_properties() && write.write_content() && write.bind() && write.unbind());
+    assert!(!write.read() && !write.write_acl() && !write.all());
+}
+
+#[test]
+fn deserializes_privilege_flags() {
+    let privilege: Privilege = serde_json::from_str(r#"{"read": true, "write": true}"#).unwrap();
+    assert!(privilege.read() && privilege.write() && privilege.bind());
+    assert!(!privilege.read_acl() && !privilege.all());
+    let privilege: Privilege = serde_json::from_str(r#"{"all": true}"#).unwrap();
+    assert!(privilege.write_acl() && privilege.unbind());
+    let privilege: Privilege = serde_json::from_str("{}").unwrap();
+    assert!(privilege.granted().is_empty());
+}
@@ -0,0 +1,574 @@
+mod common;
+
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use common::{block_on, temp_dir};
+use http_body_util::{BodyExt, Empty, Full};
+use hyper::body::{Body, Frame};
+use webdav_request::server::{DavService, LocalFs, MemFs};
+use webdav_request::StatusCode;
+
+/// A body of unknown length, sent in chunks.
+struct Chunks(VecDeque<Bytes>);
+
+impl Body for Chunks {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+        Poll::Ready(self.0.pop_front().map(|chunk| Ok(Frame::data(chunk))))
+    }
+}
+
+fn put<B>(body: B) -> hyper::Request<B> {
+    hyper::Request::put("/a.txt").body(body).unwrap()
+}
+
+fn propfind<B>(body: B) -> hyper::Request<B> {
+    hyper::Request::builder()
+        .method("PROPFIND")
+        .uri("/")
+        .header("depth", "0")
+        .body(body)
+        .unwrap()
+}
+
+/// A request with an XML body.
+fn xml_request(
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    xml: &str,
+) -> hyper::Request<Full<Bytes>> {
+    let mut request = hyper::Request::builder().method(method).uri(path);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    request
+        .body(Full::new(Bytes::from(xml.to_owned())))
+        .unwrap()
+}
+
+async fn text(response: webdav_request::server::DavResponse) -> String {
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+/// A request without a body.
+fn request(method: &str, path: &str, headers: &[(&str, &str)]) -> hyper::Request<Empty<Bytes>> {
+    let mut request = hyper::Request::builder().method(method).uri(path);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    request.body(Empty::new()).unwrap()
+}
+
+#[test]
+fn rejects_xml_bodies_over_the_limit() {
+    block_on(async {
+        let allprop = r#"<d:propfind xmlns:d="DAV:"><d:allprop/></d:propfind>"#;
+        let service = DavService::new(MemFs::new()).max_body_size(allprop.len());
+
+        let too_large = format!("{allprop} ");
+        let response = service
+            .handle(propfind(Full::new(Bytes::from(too_large))))
+            .await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let chunks = [allprop, " "].map(Bytes::from);
+        let response = service.handle(propfind(Chunks(chunks.into()))).await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let chunks = allprop.split_inclusive('>').map(Bytes::from);
+        let response = service.handle(propfind(Chunks(chunks.collect()))).await;
+        assert_eq!(response.status(), StatusCode::MULTI_STATUS);
+    });
+}
+
+#[test]
+fn streams_files_of_any_size() {
+    block_on(async {
+        let dir = temp_dir("server-stream");
+        let service = DavService::new(LocalFs::new(&dir)).max_body_size(8);
+
+        let chunks = ["larger ", "than ", "the ", "limit"].map(Bytes::from);
+        let response = service.handle(put(Chunks(chunks.into()))).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("a.txt")).unwrap(),
+            "larger than the limit"
+        );
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        let response = service.handle(request("GET", "/a.txt", &[])).await;
+        assert_eq!(response.headers()["content-length"], "21");
+        assert_eq!(text(response).await, "larger than the limit");
+        let response = service
+            .handle(request("GET", "/a.txt", &[("range", "bytes=7-10")]))
+            .await;
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers()["content-range"], "bytes 7-10/21");
+        assert_eq!(text(response).await, "than");
+        let response = service.handle(request("HEAD", "/a.txt", &[])).await;
+        assert_eq!(response.headers()["content-length"], "21");
+        assert_eq!(text(response).await, "");
+    });
+}
+
+#[test]
+fn keeps_requests_inside_the_root() {
+    block_on(async {
+        let dir = temp_dir("server-root");
+        std::fs::create_dir(dir.join("root")).unwrap();
+        std::fs::write(dir.join("root/a.txt"), "inside").unwrap();
+        std::fs::write(dir.join("secret.txt"), "outside").unwrap();
+        let service = DavService::new(LocalFs::new(dir.join("root")));
+        let get = |path: &str| {
+            hyper::Request::get(path)
+                .body(Empty::<Bytes>::new())
+                .unwrap()
+        };
+
+        assert_eq!(service.handle(get("/a.txt")).await.status(), StatusCode::OK);
+        for path in [
+            "/../secret.txt",
+            "/..%2Fsecret.txt",
+            "/a.txt/..%5C..%5Csecret.txt",
+            "/C:%5Csecret.txt",
+        ] {
+            let response = service.handle(get(path)).await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND, "{path}");
+        }
+    });
+}
+
+#[test]
+fn refuses_to_copy_or_move_onto_an_ancestor() {
+    block_on(async {
+        let fs = MemFs::new();
+        fs.insert_file("/dir/sub/a.txt", "hello");
+        let service = DavService::new(fs);
+
+        for method in ["COPY", "MOVE"] {
+            for destination in ["/dir", "/", "http://localhost/dir/"] {
+                let headers = [("destination", destination), ("overwrite", "T")];
+                let response = service.handle(request(method, "/dir/sub", &headers)).await;
+                assert_eq!(
+                    response.status(),
+                    StatusCode::FORBIDDEN,
+                    "{method} {destination}"
+                );
+            }
+        }
+        assert_eq!(service.fs().file("/dir/sub/a.txt").unwrap(), "hello");
+    });
+}
+
+#[test]
+fn reports_missing_properties_as_not_found() {
+    block_on(async {
+        let fs = MemFs::new();
+        fs.insert_file("/a.txt", "hello");
+        let service = DavService::new(fs);
+        let xml = r#"<d:propfind xmlns:d="DAV:" xmlns:x="urn:x">
+            <d:prop><d:getcontentlength/><x:color/></d:prop>
+        </d:propfind>"#;
+
+        let response = service
+            .handle(xml_request("PROPFIND", "/a.txt", &[("depth", "0")], xml))
+            .await;
+        assert_eq!(response.status(), StatusCode::MULTI_STATUS);
+        let body = text(response).await;
+        assert!(
+            body.contains("<d:getcontentlength>5</d:getcontentlength>"),
+            "{body}"
+        );
+        assert!(
+            body.contains(r#"<d:prop><color xmlns="urn:x"></color></d:prop><d:status>HTTP/1.1 404 Not Found</d:status>"#),
+            "{body}"
+        );
+        assert!(!body.contains("getetag"), "{body}");
+
+        let xml = r#"<d:propfind xmlns:d="DAV:"><d:propname/></d:propfind>"#;
+        let response = service
+            .handle(xml_request("PROPFIND", "/a.txt", &[("depth", "0")], xml))
+            .await;
+        let body = text(response).await;
+        assert!(body.contains("<d:getetag></d:getetag>"), "{body}");
+        assert!(
+            body.contains("<d:supportedlock></d:supportedlock>"),
+            "{body}"
+        );
+
+        let response = service
+            .handle(xml_request("PROPFIND", "/a.txt", &[], "<not-propfind/>"))
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    });
+}
+
+#[test]
+fn stores_dead_properties() {
+    block_on(async {
+        let fs = MemFs::new();
+        fs.insert_file("/a.txt", "hello");
+        let service = DavService::new(fs);
+        let propfind = r#"<d:propfind xmlns:d="DAV:" xmlns:x="urn:x">
+            <d:prop><x:color/><x:size/></d:prop>
+        </d:propfind>"#;
+        let set = r#"<d:propertyupdate xmlns:d="DAV:" xmlns:x="urn:x">
+            <d:set><d:prop><x:color>red &amp; blue<x:shade>dark</x:shade></x:color></d:prop></d:set>
+            <d:set><d:prop><x:size>1</x:size></d:prop></d:set>
+            <d:remove><d:prop><x:size/></d:prop></d:remove>
+        </d:propertyupdate>"#;
+
+        let response = service
+            .handle(xml_request("PROPPATCH", "/a.txt", &[], set))
+            .await;
+        assert_eq!(response.status(), StatusCode::MULTI_STATUS);
+        let body = text(response).await;
+        assert!(body.contains("HTTP/1.1 200 OK"), "{body}");
+
+        let response = service
+            .handle(xml_request(
+                "PROPFIND",
+                "/a.txt",
+                &[("depth", "0")],
+                propfind,
+            ))
+            .await;
+        let body = text(response).await;
+        let color =
+            r#"<color xmlns="urn:x">red &amp; blue<shade xmlns="urn:x">dark</shade></color>"#;
+        assert!(body.contains(color), "{body}");
+        assert!(
+            body.contains(r#"<d:prop><size xmlns="urn:x"></size></d:prop><d:status>HTTP/1.1 404 Not Found</d:status>"#),
+            "{body}"
+        );
+
+        let headers = [("destination", "/b.txt")];
+        let response = service.handle(request("COPY", "/a.txt", &headers)).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let headers = [("destination", "/c.txt")];
+        let response = service.handle(request("MOVE", "/b.txt", &headers)).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let response = service
+            .handle(xml_request(
+                "PROPFIND",
+                "/c.txt",
+                &[("depth", "0")],
+                propfind,
+            ))
+            .await;
+        assert!(text(response).await.contains(color));
+    });
+}
+
+#[test]
+fn refuses_to_patch_live_properties() {
+    block_on(async {
+        let fs = MemFs::new();
+        fs.insert_file("/a.txt", "hello");
+        let service = DavService::new(fs);
+        let xml = r#"<d:propertyupdate xmlns:d="DAV:" xmlns:x="urn:x">
+            <d:set><d:prop><x:color>red</x:color><d:getetag>"1"</d:getetag></d:prop></d:set>
+        </d:propertyupdate>"#;
+
+        let response = service
+            .handle(xml_request("PROPPATCH", "/a.txt", &[], xml))
+            .await;
+        let body = text(response).await;
+        assert!(
+            body.contains(r#"<d:prop><d:getetag></d:getetag></d:prop><d:status>HTTP/1.1 403 Forbidden</d:status>"#),
+            "{body}"
+        );
+        assert!(
+            body.contains(r#"<d:prop><color xmlns="urn:x"></color></d:prop><d:status>HTTP/1.1 424 Failed Dependency</d:status>"#),
+            "{body}"
+        );
+
+        let xml = r#"<d:propfind xmlns:d="DAV:"><d:allprop/></d:propfind>"#;
+        let response = service
+            .handle(xml_request("PROPFIND", "/a.txt", &[("depth", "0")], xml))
+            .await;
+        assert!(!text(response).await.contains("color"));
+    });
+}
+
+const LOCK_INFO: &str = r#"<d:lockinfo xmlns:d="DAV:">
+    <d:lockscope><d:exclusive/></d:lockscope><d:locktype><d:write/></d:locktype>
+</d:lockinfo>"#;
+
+/// Locks `path` and returns the `If` header submitting the lock token.
+async fn lock(service: &DavService<MemFs>, path: &str, depth: &str) -> String {
+    let response = service
+        .handle(xml_request("LOCK", path, &[("depth", depth)], LOCK_INFO))
+        .await;
+    assert!(response.status().is_success(), "{}", response.status());
+    let token = response.headers()["lock-token"].to_str().unwrap();
+    format!("({token})")
+}
+
+#[test]
+fn locks_and_unlocks_resources() {
+    block_on(async {
+        let fs = MemFs::new();
+        fs.insert_file("/dir/a.txt", "hello");
+        let service = DavService::new(fs);
+
+        let submitted = lock(&service, "/dir", "infinity").await;
+        let token = submitted.trim_matches(['(', ')', '<', '>']).to_owned();
+        let response = service.handle(put(Full::new(Bytes::from("other")))).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let write = |headers: &[(&str, &str)]| {
+            let mut request = hyper::Request::put("/dir/a.txt");
+            for (name, value) in headers {
+                request = request.header(*name, *value);
+            }
+            request.body(Full::new(Bytes::from("mine"))).unwrap()
+        };
+        assert_eq!(
+            service.handle(write(&[])).await.status(),
+            StatusCode::LOCKED
+        );
+        let response = service.handle(write(&[("if", &submitted)])).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(service.fs().file("/dir/a.txt").unwrap(), "mine");
+
+        let response = service
+            .handle(xml_request("LOCK", "/dir/a.txt", &[], LOCK_INFO))
+            .await;
+        assert_eq!(response.status(), StatusCode::LOCKED);
+        let response = service
+            .handle(request("LOCK", "/dir", &[("timeout", "Second-60")]))
+            .await;
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+        let headers = [("if", submitted.as_str()), ("timeout", "Second-60")];
+        let response = service.handle(request("LOCK", "/dir", &headers)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(text(response)
+            .await
+            .contains("<d:timeout>Second-60</d:timeout>"));
+
+        let xml = r#"<d:propfind xmlns:d="DAV:"><d:prop><d:lockdiscovery/></d:prop></d:propfind>"#;
+        let response = service
+            .handle(xml_request(
+                "PROPFIND",
+                "/dir/a.txt",
+                &[("depth", "0")],
+                xml,
+            ))
+            .await;
+        let body = text(response).await;
+        assert!(
+            body.contains(&format!("<d:href>{token}</d:href>")),
+            "{body}"
+        );
+        assert!(
+            body.contains("<d:lockroot><d:href>/dir/</d:href></d:lockroot>"),
+            "{body}"
+        );
+
+        let wrong = [("lock-token", "<urn:uuid:other>")];
+        let response = service
+            .handle(request("UNLOCK", "/dir/a.txt", &wrong))
+            .await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let lock_token = format!("<{token}>");
+        let headers = [("lock-token", lock_token.as_str())];
+        let response = service
+            .handle(request("UNLOCK", "/dir/a.txt", &headers))
+            .await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(!service.is_locked("/dir"));
+        assert_eq!(
+            service.handle(write(&[])).await.status(),
+            StatusCode::NO_CONTENT
+        );
+
+        let response = service
+            .handle(xml_request(
+                "LOCK",
+                "/dir/new.txt",
+                &[("depth", "0")],
+                LOCK_INFO,
+            ))
+            .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(service.fs().file("/dir/new.txt").unwrap(), "");
+    });
+}
+
+#[test]
+fn evaluates_if_headers() {
+    block_on(async {
+        let fs = MemFs::new();
+        fs.insert_file("/a.txt", "hello");
+        fs.insert_file("/b.txt", "hello");
+        let service = DavService::new(fs);
+        let head = service.handle(request("HEAD", "/a.txt", &[])).await;
+        let etag = head.headers()["etag"].to_str().unwrap().to_owned();
+        let submitted = lock(&service, "/b.txt", "0").await;
+        let status = |header: String| {
+            let service = service.clone();
+            async move {
+                let headers = [("if", header.as_str())];
+                service
+                    .handle(request("GET", "/a.txt", &headers))
+                    .await
+                    .status()
+            }
+        };
+
+        assert_eq!(status(format!("([{etag}])")).await, StatusCode::OK);
+        assert_eq!(
+            status(format!("(Not [{etag}])")).await,
+            StatusCode::PRECONDITION_FAILED
+        );
+        assert_eq!(
+            status(r#"(["other"])"#.into()).await,
+            StatusCode::PRECONDITION_FAILED
+        );
+        assert_eq!(
+            status(r#"(["other"]) (Not <DAV:no-lock>)"#.into()).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status("(<DAV:no-lock>)".into()).await,
+            StatusCode::PRECONDITION_FAILED
+        );
+        assert_eq!(
+            status(format!("</b.txt> {submitted}")).await,
+            StatusCode::OK
+        );
+        let tagged = format!("<http://localhost/b.txt> {submitted}");
+        assert_eq!(status(tagged).await, StatusCode::OK);
+        assert_eq!(
+            status(submitted.clone()).await,
+            StatusCode::PRECONDITION_FAILED
+        );
+        assert_eq!(
+            status(format!("</other.txt> {submitted}")).await,
+            StatusCode::PRECONDITION_FAILED
+        );
+        assert_eq!(status("([\"1\"".into()).await, StatusCode::BAD_REQUEST);
+        assert_eq!(status("()".into()).await, StatusCode::BAD_REQUEST);
+
+        let headers = [("if", submitted.as_str())];
+        let response = service.handle(request("DELETE", "/b.txt", &headers)).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    });
+}
+
+#[test]
+fn copies_and_moves_with_depth_and_overwrite() {
+    block_on(async {
+        let fs = MemFs::new();
+        fs.insert_file("/dir/sub/a.txt", "a");
+        fs.insert_file("/b.txt", "b");
+        fs.insert_file("/c.txt", "c");
+        let service = DavService::new(fs);
+        let send =
+            |method: &'static str, from: &'static str, headers: Vec<(&'static str, String)>| {
+                let service = service.clone();
+                async move {
+                    let headers: Vec<(&str, &str)> = headers
+                        .iter()
+                        .map(|(name, value)| (*name, value.as_str()))
+                        .collect();
+                    service
+                        .handle(request(method, from, &headers))
+                        .await
+                        .status()
+                }
+            };
+        let to = |path: &str| ("destination", path.to_owned());
+
+        let shallow = vec![to("/shallow"), ("depth", "0".to_owned())];
+        assert_eq!(send("COPY", "/dir", shallow).await, StatusCode::CREATED);
+        assert!(service.fs().exists("/shallow"));
+        assert!(!service.fs().exists("/shallow/sub"));
+        assert_eq!(
+            send("COPY", "/dir", vec![to("/deep")]).await,
+            StatusCode::CREATED
+        );
+        assert_eq!(service.fs().file("/deep/sub/a.txt").unwrap(), "a");
+
+        let keep = vec![to("/c.txt"), ("overwrite", "F".to_owned())];
+        assert_eq!(
+            send("COPY", "/b.txt", keep).await,
+            StatusCode::PRECONDITION_FAILED
+        );
+        assert_eq!(service.fs().file("/c.txt").unwrap(), "c");
+        let replace = vec![to("/c.txt"), ("overwrite", "T".to_owned())];
+        assert_eq!(
+            send("COPY", "/b.txt", replace).await,
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(service.fs().file("/c.txt").unwrap(), "b");
+        assert_eq!(
+            send("MOVE", "/b.txt", vec![to("/missing/b.txt")]).await,
+            StatusCode::CONFLICT
+        );
+
+        let submitted = lock(&service, "/dir", "infinity").await;
+        assert_eq!(
+            send("MOVE", "/dir", vec![to("/moved")]).await,
+            StatusCode::LOCKED
+        );
+        assert_eq!(
+            send("COPY", "/dir", vec![to("/copied")]).await,
+            StatusCode::CREATED
+        );
+        assert_eq!(
+            send("COPY", "/c.txt", vec![to("/dir/c.txt")]).await,
+            StatusCode::LOCKED
+        );
+        // An untagged list applies to the source, which is not locked.
+        let untagged = vec![to("/dir/c.txt"), ("if", submitted.clone())];
+        let status = send("COPY", "/c.txt", untagged).await;
+        assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+        let tagged = vec![to("/dir/c.txt"), ("if", format!("</dir> {submitted}"))];
+        assert_eq!(send("COPY", "/c.txt", tagged).await, StatusCode::CREATED);
+        let submitting = vec![to("/moved"), ("if", submitted)];
+        assert_eq!(send("MOVE", "/dir", submitting).await, StatusCode::CREATED);
+        assert!(!service.fs().exists("/dir"));
+        assert_eq!(service.fs().file("/moved/sub/a.txt").unwrap(), "a");
+        assert!(!service.is_locked("/moved"));
+    });
+}
+
+#[test]
+fn lists_collections_to_the_requested_depth() {
+    block_on(async {
+        let fs = MemFs::new();
+        fs.insert_file("/dir/a.txt", "a");
+        fs.insert_file("/dir/sub/b.txt", "b");
+        let service = DavService::new(fs);
+        let hrefs = |depth: Option<&'static str>| {
+            let service = service.clone();
+            async move {
+                let headers: Vec<_> = depth.map(|depth| ("depth", depth)).into_iter().collect();
+                let body = text(service.handle(request("PROPFIND", "/dir", &headers)).await).await;
+                body.split("<d:href>")
+                    .skip(1)
+                    .filter_map(|href| href.split_once("</d:href>"))
+                    .map(|(href, _)| href.to_owned())
+                    .filter(|href| href.starts_with('/'))
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(hrefs(Some("0")).await, ["/dir/"]);
+        assert_eq!(hrefs(Some("1")).await, ["/dir/", "/dir/a.txt", "/dir/sub/"]);
+        let all = ["/dir/", "/dir/a.txt", "/dir/sub/", "/dir/sub/b.txt"];
+        assert_eq!(hrefs(Some("infinity")).await, all);
+        assert_eq!(hrefs(None).await, all);
+    });
+}
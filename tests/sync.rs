@@ -0,0 +1,192 @@
+mod common;
+
+use std::fs;
+
+use common::{block_on, temp_dir};
+use webdav_request::sync::{Action, ConflictKind, PlannedAction, Reason, SyncPlan, Synchronizer};
+use webdav_request::testing::MockServer;
+use webdav_request::WebDAVClient;
+
+#[test]
+fn syncs_both_ways() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        let local = temp_dir("sync-both-ways");
+        fs::write(local.join("local.txt"), "local").unwrap();
+        server.put_file("/notes/remote.txt", "remote");
+        let client = WebDAVClient::anonymous().unwrap();
+        let sync = Synchronizer::new(client, &local, server.url_for("/notes/")).unwrap();
+
+        let report = sync.run().await.unwrap();
+        assert!(report.is_complete());
+        assert!(report
+            .applied
+            .contains(&Action::Upload("local.txt".to_owned())));
+        assert!(report
+            .applied
+            .contains(&Action::Download("remote.txt".to_owned())));
+        assert_eq!(server.file("/notes/local.txt").unwrap(), "local");
+        assert_eq!(
+            fs::read_to_string(local.join("remote.txt")).unwrap(),
+            "remote"
+        );
+        assert!(sync.plan().await.unwrap().is_empty());
+
+        fs::remove_file(local.join("local.txt")).unwrap();
+        let report = sync.run().await.unwrap();
+        assert_eq!(
+            report.applied,
+            [Action::DeleteRemote("local.txt".to_owned())]
+        );
+        assert!(!server.exists("/notes/local.txt"));
+    });
+}
+
+#[test]
+fn reports_files_changed_on_both_sides() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        let local = temp_dir("sync-conflict");
+        server.put_file("/notes/a.txt", "base");
+        let client = WebDAVClient::anonymous().unwrap();
+        let sync = Synchronizer::new(client, &local, server.url_for("/notes/")).unwrap();
+        sync.run().await.unwrap();
+
+        fs::write(local.join("a.txt"), "local edit").unwrap();
+        server.put_file("/notes/a.txt", "remote edit");
+        let report = sync.run().await.unwrap();
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].kind, ConflictKind::Modified);
+        assert_eq!(
+            fs::read_to_string(local.join("a.txt")).unwrap(),
+            "local edit"
+        );
+        assert_eq!(server.file("/notes/a.txt").unwrap(), "remote edit");
+    });
+}
+
+#[test]
+fn refuses_actions_that_leave_the_trees() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        let dir = temp_dir("sync-traversal");
+        let local = dir.join("local");
+        fs::create_dir(&local).unwrap();
+        server.put_file("/notes/a.txt", "a");
+        server.put_file("/evil.txt", "evil");
+        let client = WebDAVClient::anonymous().unwrap();
+        let sync = Synchronizer::new(client, &local, server.url_for("/notes/")).unwrap();
+
+        let actions = [
+            Action::Download("../evil.txt".to_owned()),
+            Action::DeleteRemote("../evil.txt".to_owned()),
+            Action::DeleteRemote("./a.txt".to_owned()),
+            Action::Upload("a\\..\\..\\evil.txt".to_owned()),
+        ];
+        let plan = SyncPlan {
+            actions: actions
+                .into_iter()
+                .map(|action| PlannedAction {
+                    action,
+                    reason: Reason::Created,
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let report = sync.apply(plan).await.unwrap();
+        assert!(report.applied.is_empty());
+        assert_eq!(report.failed.len(), 4);
+        assert!(!dir.join("evil.txt").exists());
+        assert!(server.exists("/evil.txt"));
+        assert!(server.exists("/notes/a.txt"));
+    });
+}
+
+#[test]
+fn keeps_changes_made_after_planning() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        let local = temp_dir("sync-after-planning");
+        server.put_file("/notes/a.txt", "base");
+        let client = WebDAVClient::anonymous().unwrap();
+        let sync = Synchronizer::new(client, &local, server.url_for("/notes/")).unwrap();
+        sync.run().await.unwrap();
+        let upload = [Action::Upload("a.txt".to_owned())];
+
+        // Edited between plan and apply.
+        let plan = sync.plan().await.unwrap();
+        assert!(plan.is_empty());
+        fs::write(local.join("a.txt"), "local edit").unwrap();
+        sync.apply(plan).await.unwrap();
+        let plan = sync.plan().await.unwrap();
+        let actions: Vec<Action> = plan.actions.iter().map(|p| p.action.clone()).collect();
+        assert_eq!(actions, upload);
+
+        // Removed from the reviewed plan.
+        let mut plan = sync.plan().await.unwrap();
+        plan.actions.clear();
+        sync.apply(plan).await.unwrap();
+        let plan = sync.plan().await.unwrap();
+        let actions: Vec<Action> = plan.actions.iter().map(|p| p.action.clone()).collect();
+        assert_eq!(actions, upload);
+
+        let report = sync.apply(plan).await.unwrap();
+        assert_eq!(report.applied, upload);
+        assert_eq!(server.file("/notes/a.txt").unwrap(), "local edit");
+        assert!(sync.plan().await.unwrap().is_empty());
+    });
+}
+
+#[test]
+fn renames_directories() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        let local = temp_dir("sync-rename-dirs");
+        server.put_file("/notes/docs/a.txt", "a");
+        server.put_file("/notes/docs/sub/b.txt", "b");
+        let client = WebDAVClient::anonymous().unwrap();
+        let sync = Synchronizer::new(client.clone(), &local, server.url_for("/notes/")).unwrap();
+        sync.run().await.unwrap();
+        let actions = |plan: &SyncPlan| plan.actions.clone();
+
+        fs::rename(local.join("docs"), local.join("papers")).unwrap();
+        let plan = sync.plan().await.unwrap();
+        let rename = Action::RenameRemote {
+            from: "docs".to_owned(),
+            to: "papers".to_owned(),
+        };
+        assert_eq!(
+            actions(&plan),
+            [PlannedAction {
+                action: rename,
+                reason: Reason::Moved
+            }]
+        );
+        assert!(sync.apply(plan).await.unwrap().is_complete());
+        assert!(!server.exists("/notes/docs"));
+        assert_eq!(server.file("/notes/papers/sub/b.txt").unwrap(), "b");
+        assert!(sync.plan().await.unwrap().is_empty());
+
+        let from = server.url_for("/notes/papers/");
+        client
+            .move_to(from, server.url_for("/notes/archive/"), false)
+            .await
+            .unwrap();
+        let plan = sync.plan().await.unwrap();
+        let rename = Action::RenameLocal {
+            from: "papers".to_owned(),
+            to: "archive".to_owned(),
+        };
+        assert_eq!(
+            plan.actions.iter().map(|p| &p.action).collect::<Vec<_>>(),
+            [&rename]
+        );
+        assert!(sync.apply(plan).await.unwrap().is_complete());
+        assert_eq!(
+            fs::read_to_string(local.join("archive/sub/b.txt")).unwrap(),
+            "b"
+        );
+        assert!(!local.join("papers").exists());
+        assert!(sync.plan().await.unwrap().is_empty());
+    });
+}
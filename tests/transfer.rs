@@ -0,0 +1,149 @@
+mod common;
+
+use std::fs;
+
+use common::{block_on, multistatus, temp_dir};
+use webdav_request::filter::Filter;
+use webdav_request::header::{HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE};
+use webdav_request::testing::{Fault, MockServer};
+use webdav_request::{Method, StatusCode, WebDAVClient};
+
+#[test]
+fn uploads_and_downloads_trees() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        let client = WebDAVClient::anonymous().unwrap();
+        let local = temp_dir("upload");
+        fs::create_dir_all(local.join("docs/empty")).unwrap();
+        fs::write(local.join("a.txt"), "a").unwrap();
+        fs::write(local.join("docs/b.txt"), "b").unwrap();
+        fs::write(local.join("docs/b.tmp"), "tmp").unwrap();
+
+        let report = client
+            .upload_dir(&local, server.url_for("/backup/"))
+            .filter(Filter::new().exclude("*.tmp").unwrap())
+            .send()
+            .await
+            .unwrap();
+        assert!(report.is_complete());
+        assert_eq!(report.transferred.len(), 2);
+        assert_eq!(server.file("/backup/a.txt").unwrap(), "a");
+        assert_eq!(server.file("/backup/docs/b.txt").unwrap(), "b");
+        assert!(server.exists("/backup/docs/empty"));
+        assert!(!server.exists("/backup/docs/b.tmp"));
+
+        let copy = temp_dir("download");
+        let report = client
+            .download_dir(server.url_for("/backup/"), &copy)
+            .send()
+            .await
+            .unwrap();
+        assert!(report.is_complete());
+        assert_eq!(fs::read_to_string(copy.join("a.txt")).unwrap(), "a");
+        assert_eq!(fs::read_to_string(copy.join("docs/b.txt")).unwrap(), "b");
+        assert!(copy.join("docs/empty").is_dir());
+    });
+}
+
+#[test]
+fn copies_filtered_trees_on_the_server() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        let client = WebDAVClient::anonymous().unwrap();
+        server.put_file("/src/a.txt", "a");
+        server.put_file("/src/.DS_Store", "junk");
+        server.put_file("/src/docs/b.txt", "b");
+        server.create_dir("/src/docs/empty");
+
+        let report = client
+            .copy_dir(server.url_for("/src"), server.url_for("/dst/"))
+            .filter(Filter::new().exclude(".DS_Store").unwrap())
+            .send()
+            .await
+            .unwrap();
+        assert!(report.is_complete());
+        assert_eq!(report.transferred.len(), 2);
+        assert_eq!(server.file("/dst/a.txt").unwrap(), "a");
+        assert_eq!(server.file("/dst/docs/b.txt").unwrap(), "b");
+        assert!(server.exists("/dst/docs/empty"));
+        assert!(!server.exists("/dst/.DS_Store"));
+        assert_eq!(server.file("/src/.DS_Store").unwrap(), "junk");
+    });
+}
+
+#[test]
+fn uploads_files_again_after_a_challenge() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        let challenge = r#"Digest realm="dav", nonce="abc", qop="auth", algorithm=MD5"#;
+        server.inject(
+            Fault::new()
+                .method(Method::PUT)
+                .status(StatusCode::UNAUTHORIZED)
+                .header(WWW_AUTHENTICATE, HeaderValue::from_static(challenge))
+                .times(1),
+        );
+        let client = WebDAVClient::builder()
+            .digest_auth("user", "password")
+            .build()
+            .unwrap();
+        let local = temp_dir("challenge");
+        fs::write(local.join("a.txt"), "a").unwrap();
+
+        let report = client
+            .upload_dir(&local, server.url_for("/backup/"))
+            .send()
+            .await
+            .unwrap();
+        assert!(report.is_complete());
+        assert_eq!(server.file("/backup/a.txt").unwrap(), "a");
+        let puts: Vec<_> = server
+            .requests()
+            .into_iter()
+            .filter(|request| request.method == "PUT")
+            .collect();
+        assert_eq!(puts.len(), 2);
+        let digest = puts[1].headers.get(AUTHORIZATION).unwrap();
+        assert!(digest.to_str().unwrap().starts_with("Digest "));
+    });
+}
+
+#[test]
+fn skips_hrefs_outside_the_tree() {
+    block_on(async {
+        let server = MockServer::start().await.unwrap();
+        server.put_file("/root/a.txt", "a");
+        // Where the crafted paths lead on the server, which drops `..`.
+        server.put_file("/root/evil.txt", "evil");
+        let file = "<d:resourcetype/><d:getcontentlength>1</d:getcontentlength>";
+        let listing = multistatus(
+            &[
+                ("/root/", "<d:resourcetype><d:collection/></d:resourcetype>"),
+                ("/root/..%2F..%2Fevil.txt", file),
+                ("/root/..%5C..%5Cevil.txt", file),
+                ("/root/a.txt", file),
+            ],
+            "",
+        );
+        server.inject(
+            Fault::new()
+                .method(Method::PROPFIND)
+                .path("/root")
+                .status(StatusCode::MULTI_STATUS)
+                .body(listing),
+        );
+        let client = WebDAVClient::anonymous().unwrap();
+        let dir = temp_dir("traversal");
+        let local = dir.join("a/b");
+
+        let report = client
+            .download_dir(server.url_for("/root/"), &local)
+            .send()
+            .await
+            .unwrap();
+        assert!(report.is_complete());
+        assert_eq!(report.transferred.len(), 1);
+        assert_eq!(fs::read_to_string(local.join("a.txt")).unwrap(), "a");
+        assert!(!dir.join("evil.txt").exists());
+    });
+}